@@ -0,0 +1,393 @@
+// The stable surface for embedding the compiler.
+// Everything reachable from here is meant to stay source compatible across refactors of the
+// checker and the backend; reach into the other modules at your own risk.
+
+use std::cell::{Cell, RefCell};
+use std::ffi::CString;
+use std::mem::transmute;
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::{Once, ONCE_INIT};
+
+use llvm_sys::prelude::LLVMModuleRef;
+
+use HsClosureFunc::{hs_init, hs_exit, _deRefStablePtr, StgClosure};
+use Parsing::parseProgram;
+use trans::FromHaskellRepr;
+use type_check::TypeCheck;
+use codegen::{Compile, Map, Symbols};
+use diagnostics::check_all;
+use const_eval::{fold_main, DEFAULT_BUDGET};
+use lint::{Lint, UnreachableArm, UnusedVariable};
+use progress::{report, ProgressEnv};
+
+pub use ast::{Operator, Position};
+pub use type_check::{Type, CheckOptions};
+pub use type_check::{TaggedProgram, TaggedBlock, TaggedStatement, TaggedTerm, TaggedFunctionCall};
+pub use type_check::{Env, LayeredEnv, TypeEnv, Binding, BindingKind, TypeKind};
+pub use typed_builder as builder;
+pub use strictness::Strictness;
+pub use codegen::{emit_ir, emit_exe};
+pub use externs::ExternSet;
+pub use progress::{Phase, ProgressEvent, Progress};
+pub use runtime::{LinkOptions, emit_exe_with, runtime_extern_set};
+pub use used::{Origin, UsedBinding, UsedBindings};
+pub use diagnostics::{CheckResult, Diagnostic, Severity};
+pub use const_eval::{ConstValue, Value};
+pub use usage::{Feature, FeatureUsage, feature_usage};
+pub use validate::{ValidationError, validate};
+pub use typed_ast::{read_typed_ast, write_typed_ast};
+pub use env_trace::{CheckTrace, EnvEvent, RecordingEnv, dump_env};
+
+static HASKELL_INIT: Once = ONCE_INIT;
+
+// The parser lives in the Haskell runtime, which can only be started once per process.
+fn init_haskell() {
+    HASKELL_INIT.call_once(|| {
+        unsafe {
+            let filename: &[u8] = b"main\x00";
+            let mut argc: i32 = 1;
+            let mut argv: &[*const u8] = &[filename.as_ptr(), ::std::ptr::null()];
+            hs_init(&mut argc, transmute(&mut argv));
+        }
+    });
+}
+
+// Stops the Haskell runtime.
+// Call it at most once, right before the process exits; nothing can be parsed afterwards.
+pub unsafe fn shutdown() {
+    hs_exit();
+}
+
+static PANIC_HOOK: Once = ONCE_INIT;
+
+thread_local! {
+    // Whether a panic on this thread is about to be caught, and what it was if one was.
+    static CATCHING: Cell<bool> = Cell::new(false);
+    static CAUGHT: RefCell<Option<String>> = RefCell::new(None);
+}
+
+// The payload of a panic doesn't know where it happened, so a hook records that before
+// unwinding starts. Panics that aren't caught are reported by the hook that was there before.
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.with(|catching| catching.get()) {
+                return previous(info);
+            }
+            let payload = info.payload();
+            let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                message.to_string()
+            } else if let Some(message) = payload.downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "unknown panic".to_string()
+            };
+            let panic = match info.location() {
+                Some(location) => format!("{} at {}:{}", message, location.file(), location.line()),
+                None => message,
+            };
+            CAUGHT.with(|caught| *caught.borrow_mut() = Some(panic));
+        }));
+    });
+}
+
+// A panic inside the compiler is a bug in it, but it shouldn't take the embedding application
+// down, so it's turned into a bug diagnostic if `catch_panics` is set.
+fn catch_internal_errors<T, F>(catch_panics: bool, f: F) -> Result<T, Diagnostic>
+    where F: FnOnce() -> T
+{
+    if !catch_panics {
+        return Ok(f());
+    }
+    install_panic_hook();
+    let was_catching = CATCHING.with(|catching| catching.get());
+    CATCHING.with(|catching| catching.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|catching| catching.set(was_catching));
+    result.map_err(|_| {
+        let panic = CAUGHT.with(|caught| caught.borrow_mut().take());
+        Diagnostic::bug(&panic.unwrap_or("unknown panic".to_string()))
+    })
+}
+
+// For the entry points that report errors as strings.
+fn or_bug<T>(result: Result<Result<T, Vec<String>>, Diagnostic>) -> Result<T, Vec<String>> {
+    match result {
+        Ok(result) => result,
+        Err(bug) => Err(vec![bug.message]),
+    }
+}
+
+pub fn parse_str(input: &str) -> Result<TaggedProgram<Position>, Vec<String>> {
+    init_haskell();
+    let c_input = match CString::new(input) {
+        Ok(c_input) => c_input,
+        Err(err) => return Err(
+            vec![format!("Failed to transform input data to c ptr: {}", err)]
+        ),
+    };
+    unsafe {
+        let tree_prim = parseProgram(c_input.as_ptr() as *mut c_void);
+        let tree = _deRefStablePtr(tree_prim) as *mut StgClosure;
+        Ok(FromHaskellRepr::from_haskell_repr(tree))
+    }
+}
+
+pub fn check_str(input: &str) -> Result<TaggedProgram<Type>, Vec<String>> {
+    check_str_with(input, &[])
+}
+
+// Checks the program with the declarations of the extern sets in scope.
+pub fn check_str_with(input: &str,
+                      extern_sets: &[ExternSet]) -> Result<TaggedProgram<Type>, Vec<String>> {
+    check_str_with_options(input, extern_sets, &CheckOptions::default())
+}
+
+pub fn check_str_with_options(input: &str,
+                              extern_sets: &[ExternSet],
+                              options: &CheckOptions)
+                              -> Result<TaggedProgram<Type>, Vec<String>> {
+    or_bug(catch_internal_errors(options.catch_panics, || {
+        let program = try!(try!(parse_str(input)).with_extern_sets(extern_sets));
+        program.type_check_with(&mut Env::new(), options)
+    }))
+}
+
+// Compiles the program checked with `options`, returning the module with the warnings of the
+// check.
+pub fn compile_str_with_options(input: &str,
+                                extern_sets: &[ExternSet],
+                                options: &CheckOptions,
+                                compile_options: &CompileOptions)
+                                -> Result<(LLVMModuleRef, Vec<String>), Vec<String>> {
+    use self::Phase::*;
+    use self::ProgressEvent::*;
+    let progress = &compile_options.progress;
+    or_bug(catch_internal_errors(options.catch_panics, || {
+        report(progress, || PhaseStarted(Parse));
+        let program = try!(try!(parse_str(input)).with_extern_sets(extern_sets));
+        report(progress, || PhaseFinished(Parse));
+        report(progress, || PhaseStarted(Check));
+        let (typed, warnings) = try!(match *progress {
+            Some(ref progress) => {
+                let mut env = ProgressEnv::new(Env::new(), progress.clone());
+                program.type_check_with_warnings(&mut env, options)
+            }
+            None => program.type_check_with_warnings(&mut Env::new(), options),
+        });
+        report(progress, || PhaseFinished(Check));
+        let (module, _) = try!(build(&typed, compile_options));
+        Ok((module, warnings))
+    }))
+}
+
+// Every error and warning of the program, the default lints' included, from one pass.
+pub fn check_str_all(input: &str,
+                     extern_sets: &[ExternSet],
+                     options: &CheckOptions) -> CheckResult {
+    let program = catch_internal_errors(options.catch_panics, || {
+        parse_str(input).and_then(|program| program.with_extern_sets(extern_sets))
+    });
+    match program {
+        Ok(Ok(program)) => check_program(&program, &mut Env::new(), options),
+        Ok(Err(errors)) => CheckResult::from_errors(errors),
+        Err(bug) => CheckResult::from_bug(bug),
+    }
+}
+
+// Like `check_str_all`, for a program the host parsed or built itself, checked in `env`.
+pub fn check_program(program: &TaggedProgram<Position>,
+                     env: &mut Env,
+                     options: &CheckOptions) -> CheckResult {
+    check_program_with(program, env, options, |program, env, options| {
+        let lints: &[&Lint] = &[&UnusedVariable, &UnreachableArm];
+        check_all(program, env, options, lints, &Map::new())
+    })
+}
+
+// `check_program` with the check itself passed in, so that tests can make it panic.
+fn check_program_with<F>(program: &TaggedProgram<Position>,
+                         env: &mut Env,
+                         options: &CheckOptions,
+                         check: F) -> CheckResult
+    where F: FnOnce(&TaggedProgram<Position>, &mut Env, &CheckOptions) -> CheckResult
+{
+    let result = catch_internal_errors(options.catch_panics, || check(program, env, options));
+    // The program and the env it's checked in are what it takes to reproduce a bug.
+    result.unwrap_or_else(|mut bug| {
+        if !env.values.is_empty() {
+            bug.message.push_str(&format!("\nThe program was checked in this env:\n{}",
+                                          dump_env(&env.values)));
+        }
+        CheckResult::from_bug(bug)
+    })
+}
+
+// Compiles a program `check_str_all` found no errors in.
+pub fn compile_checked(result: &CheckResult) -> Result<LLVMModuleRef, Vec<String>> {
+    compile_checked_with(result, &CompileOptions::default()).map(|(module, _)| module)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CompileOptions {
+    // Replaces a pure main with its value before codegen, if it can be computed within
+    // `const_eval_budget` steps. Otherwise main is compiled as it is.
+    pub evaluate_const_main: bool,
+    pub const_eval_budget: usize,
+    // Like `CheckOptions::catch_panics`, for codegen.
+    pub catch_panics: bool,
+    // Called with the progress of every compilation done with these options, see `progress`.
+    pub progress: Option<Progress>,
+}
+
+impl Default for CompileOptions {
+    fn default() -> CompileOptions {
+        CompileOptions {
+            evaluate_const_main: false,
+            const_eval_budget: DEFAULT_BUDGET,
+            catch_panics: !cfg!(debug_assertions),
+            progress: None,
+        }
+    }
+}
+
+// What compiling did besides producing the module.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CompilationReport {
+    // The steps it took to evaluate main, if it was replaced by its value.
+    pub const_main_steps: Option<usize>,
+}
+
+// Like `compile_checked`, with the passes `options` asks for.
+pub fn compile_checked_with(result: &CheckResult,
+                            options: &CompileOptions)
+                            -> Result<(LLVMModuleRef, CompilationReport), Vec<String>> {
+    if result.has_errors() {
+        return Err(result.errors().iter().map(|error| error.to_string()).collect());
+    }
+    or_bug(catch_internal_errors(options.catch_panics, || build(&result.program, options)))
+}
+
+// The codegen phase of every compilation.
+fn build(program: &TaggedProgram<Type>,
+         options: &CompileOptions)
+         -> Result<(LLVMModuleRef, CompilationReport), Vec<String>> {
+    use self::ProgressEvent::*;
+    let progress = &options.progress;
+    report(progress, || PhaseStarted(Phase::Codegen));
+    let mut compilation = CompilationReport::default();
+    let folded = if options.evaluate_const_main {
+        report(progress, || Pass("evaluate const main"));
+        fold_main(program, options.const_eval_budget)
+    } else {
+        None
+    };
+    let program = match folded {
+        Some((ref folded, ref evaluation)) => {
+            compilation.const_main_steps = Some(evaluation.steps);
+            folded
+        }
+        None => program,
+    };
+    let env = match *progress {
+        Some(ref progress) => {
+            let total = feature_usage(program).count(Feature::Lambda) + 1;
+            let done = Cell::new(0);
+            let progress = progress.clone();
+            Symbols::with_callback(Rc::new(move || {
+                done.set(done.get() + 1);
+                progress.report(FunctionsEmitted { done: done.get(), total: total });
+            }))
+        }
+        None => Symbols::new(),
+    };
+    // Validating the program comes first, see `validate`.
+    report(progress, || Pass("validate"));
+    let module = try!(program.gen_module_in(env));
+    report(progress, || PhaseFinished(Phase::Codegen));
+    Ok((module, compilation))
+}
+
+// The textual IR of `module`, which can be moved to and shared with other threads, unlike the
+// module itself. The module is disposed of, so it mustn't be used afterwards.
+pub unsafe fn into_thread_safe(module: LLVMModuleRef) -> String {
+    use std::ffi::CStr;
+    use llvm_sys::core::{LLVMPrintModuleToString, LLVMDisposeMessage, LLVMDisposeModule};
+    let raw = LLVMPrintModuleToString(module);
+    let ir = CStr::from_ptr(raw).to_string_lossy().into_owned();
+    LLVMDisposeMessage(raw);
+    LLVMDisposeModule(module);
+    ir
+}
+
+pub fn compile_str(input: &str) -> Result<LLVMModuleRef, Vec<String>> {
+    compile_str_with(input, &[])
+}
+
+pub fn compile_str_with(input: &str,
+                        extern_sets: &[ExternSet]) -> Result<LLVMModuleRef, Vec<String>> {
+    compile_str_with_options(input, extern_sets, &CheckOptions::default(),
+                             &CompileOptions::default())
+        .map(|(module, _)| module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program() -> TaggedProgram<Position> {
+        let pos = Position { start_pos: (1, 1), end_pos: (1, 1) };
+        let main = TaggedBlock {
+            tag: pos.clone(),
+            stmts: Vec::new(),
+            end: Box::new(Some(TaggedTerm::Literal(pos.clone(), 0))),
+        };
+        TaggedProgram { tag: pos, main: main }
+    }
+
+    fn options(catch_panics: bool) -> CheckOptions {
+        CheckOptions { catch_panics: catch_panics, ..CheckOptions::default() }
+    }
+
+    fn panicking(_: &TaggedProgram<Position>, _: &mut Env, _: &CheckOptions) -> CheckResult {
+        panic!("panicking on purpose")
+    }
+
+    #[test]
+    fn a_caught_panic_is_a_bug() {
+        let result = check_program_with(&program(), &mut Env::new(), &options(true), panicking);
+        assert!(result.has_errors());
+        assert_eq!(result.diagnostics.len(), 1);
+        let bug = &result.diagnostics[0];
+        assert_eq!(bug.severity, Severity::Bug);
+        assert_eq!(bug.code, Some("E9999"));
+        assert_eq!(bug.position, None);
+        assert!(bug.message.starts_with("error[E9999]: internal compiler error: panicking on \
+                                         purpose at "));
+        assert!(bug.message.contains("api.rs:"));
+        assert!(bug.message.ends_with("please report it together with the program."));
+        // Checking works as usual after a caught panic.
+        assert!(!check_program(&program(), &mut Env::new(), &options(true)).has_errors());
+    }
+
+    #[test]
+    #[should_panic(expected = "panicking on purpose")]
+    fn panics_go_through_unless_caught() {
+        check_program_with(&program(), &mut Env::new(), &options(false), panicking);
+    }
+
+    #[test]
+    fn a_bug_carries_the_env_the_program_was_checked_in() {
+        let mut env = Env::new();
+        env.insert("getchar".to_string(),
+                   Type::FunctionTy(Vec::new(), Vec::new(), Box::new(Type::I32Ty)));
+        let result = check_program_with(&program(), &mut env, &options(true), panicking);
+        let message = &result.diagnostics[0].message;
+        assert!(message.ends_with("please report it together with the program.\n\
+                                   The program was checked in this env:\n\
+                                   getchar: () -> I32 (host)\n"));
+    }
+}
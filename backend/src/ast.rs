@@ -3,12 +3,24 @@ use std::fmt::{Display, Formatter, Result};
 // Extern statements use `Type`.
 use type_check::Type;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
 pub enum Operator {
     Add,
     Sub,
     Mul,
     Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
 }
 
 impl Display for Operator {
@@ -19,11 +31,146 @@ impl Display for Operator {
             Sub => "-",
             Mul => "*",
             Div => "/",
+            Rem => "%",
+            Eq => "==",
+            Ne => "!=",
+            Lt => "<",
+            Le => "<=",
+            Gt => ">",
+            Ge => ">=",
+            And => "&&",
+            Or => "||",
+            BitAnd => "&",
+            BitOr => "|",
+            BitXor => "^",
         };
         write!(f, "{}", op_str)
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Assoc {
+    Left,
+    Right,
+    // Chaining is a parse error, as in `a < b < c`.
+    NonAssoc,
+}
+
+// Which operand of an infix operator a term is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+// The operator table in the Haskell parser must agree with these.
+impl Operator {
+    // Operators with higher precedence bind tighter.
+    pub fn precedence(&self) -> u8 {
+        use self::Operator::*;
+        match *self {
+            Or => 0,
+            And => 1,
+            Eq | Ne | Lt | Le | Gt | Ge => 2,
+            BitOr => 3,
+            BitXor => 4,
+            BitAnd => 5,
+            Add | Sub => 6,
+            Mul | Div | Rem => 7,
+        }
+    }
+
+    pub fn associativity(&self) -> Assoc {
+        if self.is_comparison() {
+            Assoc::NonAssoc
+        } else {
+            Assoc::Left
+        }
+    }
+
+    // Comparisons give a Bool rather than a value of the type of their operands.
+    pub fn is_comparison(&self) -> bool {
+        use self::Operator::*;
+        match *self {
+            Eq | Ne | Lt | Le | Gt | Ge => true,
+            Add | Sub | Mul | Div | Rem | And | Or | BitAnd | BitOr | BitXor => false,
+        }
+    }
+
+    // `&&` and `||` take and give Bools.
+    pub fn is_logical(&self) -> bool {
+        *self == Operator::And || *self == Operator::Or
+    }
+}
+
+// Whether `child` has to be parenthesized when printed as the `side` operand of `parent`.
+pub fn needs_parens(parent: &Term, child: &Term, side: Side) -> bool {
+    use self::Term::*;
+    let parent_op = match *parent {
+        Infix(_, op, _) => op,
+        // `as` binds tighter than any infix operator, and the parser only takes one at a time.
+        Cast(..) => return match *child {
+            Infix(..) | If(..) | Cast(..) | PtrEq(..) => true,
+            _ => false,
+        },
+        // Projections and indexing bind tighter still.
+        Project(..) | Field(..) => return match *child {
+            Infix(..) | If(..) | Cast(..) | PtrEq(..) => true,
+            _ => false,
+        },
+        // The index itself is already between brackets.
+        Index(..) => return side == Side::Left && match *child {
+            Infix(..) | If(..) | Cast(..) | PtrEq(..) => true,
+            _ => false,
+        },
+        // `==` is a comparison, and the parser only takes one at a time.
+        PtrEq(..) => return match *child {
+            PtrEq(..) => true,
+            Infix(_, child_op, _) => child_op.precedence() <= Operator::Eq.precedence(),
+            If(..) => side == Side::Left,
+            _ => false,
+        },
+        _ => return false,
+    };
+    match *child {
+        Infix(_, child_op, _) => {
+            if child_op.precedence() != parent_op.precedence() {
+                child_op.precedence() < parent_op.precedence()
+            } else {
+                match (parent_op.associativity(), side) {
+                    (Assoc::Left, Side::Left) | (Assoc::Right, Side::Right) => false,
+                    _ => true,
+                }
+            }
+        }
+        // The else part of an if extends as far to the right as possible.
+        If(..) => side == Side::Left,
+        PtrEq(..) => true,
+        _ => false,
+    }
+}
+
+// What an arm of a match matches.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    // `Shape::Circle(r)`, binding the payload of the variant to names.
+    Constructor(String, String, Vec<String>),
+    // `_`, which matches anything.
+    Wildcard,
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            Pattern::Constructor(ref name, ref variant, ref binds) if binds.is_empty() =>
+                write!(f, "{}::{}", name, variant),
+            Pattern::Constructor(ref name, ref variant, ref binds) =>
+                write!(f, "{}::{}({})", name, variant, binds.join(", ")),
+            Pattern::Wildcard => write!(f, "_"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct FunctionCall {
     pub name: String,
@@ -42,9 +189,55 @@ pub enum Term {
     Infix(Box<Term>, Operator, Box<Term>),
     Call(FunctionCall, Vec<Term>),
     Scope(Block),
-    If(Box<Term>, Box<Term>, Box<Term>),
+    If(Box<Term>, Box<Term>, Option<Box<Term>>),
     While(Box<Term>, Block),
     Stmt(Box<Statement>),
+    BoolLiteral(bool),
+    SuffixedLiteral(i64, Type),
+    // `x as I8`, between integer types.
+    Cast(Box<Term>, Type),
+    // The bits of an f64, like in `TaggedTerm::FloatLiteral`.
+    FloatLiteral(u64),
+    CharLit(char),
+    StrLit(String),
+    // `(1, true)`, with at least two elements.
+    Tuple(Vec<Term>),
+    // `t.0`, binding tighter than `as`.
+    Project(Box<Term>, u32),
+    // `[1, 2, 3]`, with at least one element.
+    ArrayLit(Vec<Term>),
+    // `a[i]`, binding as tightly as projections.
+    Index(Box<Term>, Box<Term>),
+    // `Point { x: 1, y: 2 }`.
+    StructLit(String, Vec<(String, Term)>),
+    // `p.x`, binding as tightly as projections.
+    Field(Box<Term>, String),
+    // `Shape::Circle(1)`, or `Shape::Empty` for a variant without a payload.
+    Variant(String, String, Vec<Term>),
+    // `match s { Shape::Circle(r) => r, _ => 0 }`.
+    Match(Box<Term>, Vec<(Pattern, Term)>),
+    // `&x`.
+    Borrow(Box<Term>),
+    // `*p`.
+    Deref(Box<Term>),
+    // `&mut x`.
+    BorrowMut(Box<Term>),
+    // `null`.
+    Null,
+    // `p == q`.
+    PtrEq(Box<Term>, Box<Term>),
+    // `fn(x: I32) { x + 1 }`.
+    Lambda(Vec<(String, Type)>, Block),
+    // `f.0(1)`.
+    Apply(Box<Term>, Vec<Term>),
+    // `(1 : I64)`.
+    Ascribe(Box<Term>, Type),
+    // `x.f(1)`.
+    MethodCall(Box<Term>, FunctionCall, Vec<Term>),
+    // `-x`, binding like `*p`.
+    Neg(Box<Term>),
+    // `!b`, binding like `-x`.
+    Not(Box<Term>),
 }
 
 #[macro_export]
@@ -59,10 +252,46 @@ macro_rules! infix {
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Statement {
     TermSemicolon(Term),
-    Let(String, Term),
-    LetMut(String, Term),
+    // `let x: T = e;`, where the type is Hole if it's left out.
+    Let(String, Type, Term),
+    LetMut(String, Type, Term),
     Mutate(String, Term),
     Extern(String, Type),
+    // `let mut x: T;`, a mutable variable which must be assigned before it's read.
+    Declare(String, Type),
+    // `struct Point { x: I32, y: I32 };`
+    StructDecl(String, Vec<(String, Type)>),
+    // `enum Shape { Circle(I32), Empty };`
+    EnumDecl(String, Vec<(String, Vec<Type>)>),
+    // `type Pair = (I32, I32);`
+    TypeAlias(String, Type),
+    // `*p = e;`.
+    MutateThrough(Term, Term),
+}
+
+// Words the parser never reads as names. The list in the Haskell parser must agree with this.
+pub const RESERVED_WORDS: &'static [&'static str] =
+    &["fn", "let", "mut", "if", "then", "else", "while", "extern", "true", "false", "as", "struct",
+      "enum", "match", "type", "null"];
+
+// Checks that a bound name could have come from the parser, since trees can be built by hand.
+pub fn check_identifier(name: &str) -> ::std::result::Result<(), Vec<String>> {
+    let mut chars = name.chars();
+    let valid = match chars.next() {
+        Some(first) => {
+            (first.is_alphabetic() || first == '_') &&
+                chars.all(|c| c.is_alphanumeric() || c == '_')
+        }
+        None => false,
+    };
+    if !valid {
+        Err(vec![format!("Invalid name {:?}: a name starts with a letter or an underscore, \
+                          followed by letters, digits or underscores.", name)])
+    } else if RESERVED_WORDS.contains(&name) {
+        Err(vec![format!("The keyword {} can't be used as a name.", name)])
+    } else {
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
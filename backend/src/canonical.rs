@@ -0,0 +1,145 @@
+// What it means for two programs to be the same.
+// The untagged trees in `ast` are the canonical form: equality and hashing of them include
+// everything that changes what a program means, like names, literals, operators, types and the
+// order of statements, fields and arguments, and nothing else. Floats are kept as their bits,
+// so equality stays reflexive and agrees with hashing.
+// Tagged trees compare their tags too, so the same program parsed at different places isn't
+// equal to itself; untag them first to compare or hash them canonically.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use ast::*;
+use type_check::*;
+
+pub trait Untag {
+    type Untagged: Clone + Eq + Hash;
+    fn untag(&self) -> Self::Untagged;
+}
+
+impl<Tag> Untag for TaggedFunctionCall<Tag> {
+    type Untagged = FunctionCall;
+    fn untag(&self) -> FunctionCall {
+        FunctionCall { name: self.name.clone() }
+    }
+}
+
+fn untag_all<T: Untag>(trees: &[T]) -> Vec<T::Untagged> {
+    trees.iter().map(Untag::untag).collect()
+}
+
+impl<Tag> Untag for TaggedTerm<Tag> {
+    type Untagged = Term;
+    fn untag(&self) -> Term {
+        use type_check::TaggedTerm::*;
+        match *self {
+            Literal(_, i) => Term::Literal(i),
+            Var(_, ref name) => Term::Var(name.clone()),
+            Infix(_, ref left, op, ref right) =>
+                Term::Infix(Box::new(left.untag()), op, Box::new(right.untag())),
+            Call(_, ref func, ref args) => Term::Call(func.untag(), untag_all(args)),
+            Scope(_, ref block) => Term::Scope(block.untag()),
+            If(_, ref cond, ref if_true, ref if_false) =>
+                Term::If(Box::new(cond.untag()),
+                         Box::new(if_true.untag()),
+                         if_false.as_ref().map(|if_false| Box::new(if_false.untag()))),
+            While(_, ref cond, ref block) => Term::While(Box::new(cond.untag()), block.untag()),
+            Stmt(ref stmt) => Term::Stmt(Box::new(stmt.untag())),
+            BoolLiteral(_, b) => Term::BoolLiteral(b),
+            SuffixedLiteral(_, i, ref ty) => Term::SuffixedLiteral(i, ty.clone()),
+            Cast(_, ref term, ref ty) => Term::Cast(Box::new(term.untag()), ty.clone()),
+            FloatLiteral(_, bits) => Term::FloatLiteral(bits),
+            CharLit(_, c) => Term::CharLit(c),
+            StrLit(_, ref s) => Term::StrLit(s.clone()),
+            Tuple(_, ref elems) => Term::Tuple(untag_all(elems)),
+            Project(_, ref term, index) => Term::Project(Box::new(term.untag()), index),
+            ArrayLit(_, ref elems) => Term::ArrayLit(untag_all(elems)),
+            Index(_, ref array, ref index) =>
+                Term::Index(Box::new(array.untag()), Box::new(index.untag())),
+            StructLit(_, ref name, ref inits) => {
+                let inits = inits.iter()
+                                 .map(|&(ref field, ref term)| (field.clone(), term.untag()))
+                                 .collect();
+                Term::StructLit(name.clone(), inits)
+            }
+            Field(_, ref term, ref field) => Term::Field(Box::new(term.untag()), field.clone()),
+            Variant(_, ref name, ref variant, ref args) =>
+                Term::Variant(name.clone(), variant.clone(), untag_all(args)),
+            Match(_, ref scrutinee, ref arms) => {
+                let arms = arms.iter()
+                               .map(|&(ref pattern, ref body)| (pattern.clone(), body.untag()))
+                               .collect();
+                Term::Match(Box::new(scrutinee.untag()), arms)
+            }
+            Borrow(_, ref term) => Term::Borrow(Box::new(term.untag())),
+            Deref(_, ref term) => Term::Deref(Box::new(term.untag())),
+            BorrowMut(_, ref term) => Term::BorrowMut(Box::new(term.untag())),
+            Null(_) => Term::Null,
+            PtrEq(_, ref left, ref right) =>
+                Term::PtrEq(Box::new(left.untag()), Box::new(right.untag())),
+            Lambda(_, ref params, ref body) => Term::Lambda(params.clone(), body.untag()),
+            Apply(_, ref callee, ref args) =>
+                Term::Apply(Box::new(callee.untag()), untag_all(args)),
+            MethodCall(_, ref receiver, ref method, ref args) =>
+                Term::MethodCall(Box::new(receiver.untag()), method.untag(), untag_all(args)),
+            Ascribe(_, ref term, ref ty) => Term::Ascribe(Box::new(term.untag()), ty.clone()),
+            Neg(_, ref term) => Term::Neg(Box::new(term.untag())),
+            Not(_, ref term) => Term::Not(Box::new(term.untag())),
+        }
+    }
+}
+
+impl<Tag> Untag for TaggedStatement<Tag> {
+    type Untagged = Statement;
+    fn untag(&self) -> Statement {
+        use type_check::TaggedStatement::*;
+        match *self {
+            TermSemicolon(_, ref term) => Statement::TermSemicolon(term.untag()),
+            Let(_, ref name, ref ty, ref rhs) =>
+                Statement::Let(name.clone(), ty.clone(), rhs.untag()),
+            LetMut(_, ref name, ref ty, ref rhs) =>
+                Statement::LetMut(name.clone(), ty.clone(), rhs.untag()),
+            Mutate(_, ref name, ref rhs) => Statement::Mutate(name.clone(), rhs.untag()),
+            Extern(_, ref name, ref ty) => Statement::Extern(name.clone(), ty.clone()),
+            Declare(_, ref name, ref ty) => Statement::Declare(name.clone(), ty.clone()),
+            StructDecl(_, ref name, ref fields) =>
+                Statement::StructDecl(name.clone(), fields.clone()),
+            EnumDecl(_, ref name, ref variants) =>
+                Statement::EnumDecl(name.clone(), variants.clone()),
+            TypeAlias(_, ref name, ref ty) => Statement::TypeAlias(name.clone(), ty.clone()),
+            MutateThrough(_, ref target, ref rhs) =>
+                Statement::MutateThrough(target.untag(), rhs.untag()),
+        }
+    }
+}
+
+impl<Tag> Untag for TaggedBlock<Tag> {
+    type Untagged = Block;
+    fn untag(&self) -> Block {
+        let end = match *self.end {
+            Some(ref term) => Some(term.untag()),
+            None => None,
+        };
+        Block { stmts: untag_all(&self.stmts), end: Box::new(end) }
+    }
+}
+
+impl<Tag> Untag for TaggedProgram<Tag> {
+    type Untagged = Program;
+    fn untag(&self) -> Program {
+        Program { main: self.main.untag() }
+    }
+}
+
+// Whether two trees are the same once their tags are dropped, whatever the tags are.
+pub fn same<A: Untag, B: Untag<Untagged = A::Untagged>>(a: &A, b: &B) -> bool {
+    a.untag() == b.untag()
+}
+
+// A hash of the canonical form of `tree`, which is the same for trees `same` considers the same.
+// Like `DefaultHasher`, it's only stable within one build of the crate.
+pub fn canonical_hash<T: Untag>(tree: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tree.untag().hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,86 @@
+// The variables a lambda uses from outside of it, which it would have to capture.
+// Scoping follows the checker: the parameters, and whatever the body binds, shadow the names
+// outside.
+
+use std::collections::HashSet;
+
+use ast::{Pattern, Position};
+use type_check::*;
+use visit::*;
+
+struct Captures {
+    scopes: Vec<HashSet<String>>,
+    captured: Vec<String>,
+}
+
+impl Captures {
+    fn bind(&mut self, name: &str) {
+        self.scopes.last_mut().unwrap().insert(name.to_string());
+    }
+
+    fn use_name(&mut self, name: &str) {
+        let bound = self.scopes.iter().any(|scope| scope.contains(name));
+        if !bound && !self.captured.iter().any(|captured| captured == name) {
+            self.captured.push(name.to_string());
+        }
+    }
+
+    fn scope<F: FnOnce(&mut Captures)>(&mut self, names: &[&String], f: F) {
+        self.scopes.push(names.iter().map(|name| name.to_string()).collect());
+        f(self);
+        self.scopes.pop();
+    }
+}
+
+impl Visitor<Position> for Captures {
+    fn visit_block(&mut self, block: &TaggedBlock<Position>) {
+        self.scope(&[], |captures| walk_block(captures, block));
+    }
+
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Position>) {
+        use type_check::TaggedStatement::*;
+        walk_statement(self, stmt);
+        match *stmt {
+            Let(_, ref name, _, _) | LetMut(_, ref name, _, _) | Extern(_, ref name, _) |
+            Declare(_, ref name, _) => self.bind(name),
+            Mutate(_, ref name, _) => self.use_name(name),
+            TermSemicolon(..) | StructDecl(..) | EnumDecl(..) | TypeAlias(..) |
+            MutateThrough(..) => {}
+        }
+    }
+
+    fn visit_term(&mut self, term: &TaggedTerm<Position>) {
+        use type_check::TaggedTerm::*;
+        match *term {
+            Var(_, ref name) => self.use_name(name),
+            Call(_, ref func, _) | MethodCall(_, _, ref func, _) => self.use_name(&func.name),
+            Match(_, ref scrutinee, ref arms) => {
+                self.visit_term(scrutinee);
+                for &(ref pattern, ref body) in arms {
+                    let binds = match *pattern {
+                        Pattern::Constructor(_, _, ref binds) => binds.iter().collect(),
+                        Pattern::Wildcard => Vec::new(),
+                    };
+                    self.scope(&binds, |captures| captures.visit_term(body));
+                }
+                return;
+            }
+            Lambda(_, ref params, ref body) => {
+                let names: Vec<&String> = params.iter().map(|&(ref name, _)| name).collect();
+                self.scope(&names, |captures| captures.visit_block(body));
+                return;
+            }
+            _ => {}
+        }
+        walk_term(self, term);
+    }
+}
+
+// The names the body of a lambda with parameters `params` uses without binding them, in the
+// order they're first used.
+pub fn captures(params: &[(String, Type)], body: &TaggedBlock<Position>) -> Vec<String> {
+    let mut captures = Captures { scopes: Vec::new(), captured: Vec::new() };
+    let names: Vec<&String> = params.iter().map(|&(ref name, _)| name).collect();
+    captures.scope(&names, |captures| captures.visit_block(body));
+    captures.captured
+}
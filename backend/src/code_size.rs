@@ -0,0 +1,52 @@
+use std::ffi::CStr;
+
+use llvm_sys::prelude::*;
+use llvm_sys::core::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FunctionSize {
+    pub name: String,
+    pub basic_blocks: usize,
+    pub instructions: usize,
+}
+
+// The size of the code emitted for every function defined in a module.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CodeSizeReport {
+    pub functions: Vec<FunctionSize>,
+}
+
+impl CodeSizeReport {
+    pub fn total_instructions(&self) -> usize {
+        self.functions.iter().map(|function| function.instructions).sum()
+    }
+}
+
+// Declarations, like externs, have no code and are left out.
+pub unsafe fn code_size_report(module: LLVMModuleRef) -> CodeSizeReport {
+    let mut functions = Vec::new();
+    let mut func = LLVMGetFirstFunction(module);
+    while !func.is_null() {
+        if LLVMIsDeclaration(func) == 0 {
+            let mut basic_blocks = 0;
+            let mut instructions = 0;
+            let mut block = LLVMGetFirstBasicBlock(func);
+            while !block.is_null() {
+                basic_blocks += 1;
+                let mut instruction = LLVMGetFirstInstruction(block);
+                while !instruction.is_null() {
+                    instructions += 1;
+                    instruction = LLVMGetNextInstruction(instruction);
+                }
+                block = LLVMGetNextBasicBlock(block);
+            }
+            functions.push(FunctionSize {
+                name: CStr::from_ptr(LLVMGetValueName(func)).to_string_lossy().into_owned(),
+                basic_blocks: basic_blocks,
+                instructions: instructions,
+            });
+        }
+        func = LLVMGetNextFunction(func);
+    }
+    CodeSizeReport { functions: functions }
+}
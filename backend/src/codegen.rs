@@ -1,24 +1,28 @@
-use std::os::raw::c_char;
+use std::ffi::CString;
 use std::collections::{HashSet, HashMap};
 use std::process::Command;
+use std::rc::Rc;
 
 use llvm_sys::prelude::*;
 use llvm_sys::core::*;
 
+use ast::{Operator, Pattern};
 use type_check::*;
 use type_check::Type::*;
+use validate::validate;
+use print::{Segment, is_builtin_print, parse_format};
+use runtime::runtime_extern_set;
 
 use inc::*;
+// The `CString` owns the buffer, so keep it alive for as long as LLVM reads the pointer.
 trait ToRaw: Into<Vec<u8>> {
-    fn to_raw(self) -> Result<*const c_char, Vec<String>>;
+    fn to_raw(self) -> Result<CString, Vec<String>>;
 }
 
 impl<'a> ToRaw for &'a str {
-    fn to_raw(self: &'a str) -> Result<*const c_char, Vec<String>> {
+    fn to_raw(self: &'a str) -> Result<CString, Vec<String>> {
         use std::error::Error;
-        use std::ffi::CString;
-        CString::new(self).map(|str| str.as_ptr() as *const i8)
-                          .map_err(|err| vec![err.description().to_string()])
+        CString::new(self).map_err(|err| vec![err.description().to_string()])
     }
 }
 
@@ -41,7 +45,8 @@ impl TaggedTerm<Type> {
     pub fn rhs_vars(self: &Self) -> HashSet<String> {
         use type_check::TaggedTerm::*;
         match *self {
-            Literal(_, _) => HashSet::new(),
+            Literal(_, _) | BoolLiteral(_, _) | SuffixedLiteral(_, _, _) | FloatLiteral(_, _) |
+            CharLit(_, _) | StrLit(_, _) | Null(_) => HashSet::new(),
             Var(_, ref name) => {
                 let mut set = HashSet::new();
                 set.insert(name.clone());
@@ -59,11 +64,44 @@ impl TaggedTerm<Type> {
             If(_, ref cond, ref if_true, ref if_false) => {
                 let set: HashSet<_> =
                     cond.rhs_vars().union(&if_true.rhs_vars()).cloned().collect();
-                set.union(&if_false.rhs_vars()).cloned().collect()
+                match *if_false {
+                    Some(ref if_false) => set.union(&if_false.rhs_vars()).cloned().collect(),
+                    None => set,
+                }
             }
             While(_, ref cond, ref block) =>
                 cond.rhs_vars().union(&block.rhs_vars()).cloned().collect(),
-            Stmt(ref stmt) => stmt.rhs_vars()
+            Stmt(ref stmt) => stmt.rhs_vars(),
+            Cast(_, ref term, _) | Project(_, ref term, _) | Ascribe(_, ref term, _) |
+            Neg(_, ref term) | Not(_, ref term) => term.rhs_vars(),
+            Tuple(_, ref elems) | ArrayLit(_, ref elems) =>
+                elems.iter()
+                     .map(|elem| elem.rhs_vars())
+                     .fold(HashSet::new(), |l, r| l.union(&r).cloned().collect()),
+            Index(_, ref array, ref index) =>
+                array.rhs_vars().union(&index.rhs_vars()).cloned().collect(),
+            PtrEq(_, ref left, ref right) =>
+                left.rhs_vars().union(&right.rhs_vars()).cloned().collect(),
+            // A lambda can't read the variables outside of it.
+            Lambda(..) => HashSet::new(),
+            Apply(_, ref callee, ref args) | MethodCall(_, ref callee, _, ref args) =>
+                args.iter()
+                    .map(|arg| arg.rhs_vars())
+                    .fold(callee.rhs_vars(), |l, r| l.union(&r).cloned().collect()),
+            StructLit(_, _, ref inits) =>
+                inits.iter()
+                     .map(|&(_, ref term)| term.rhs_vars())
+                     .fold(HashSet::new(), |l, r| l.union(&r).cloned().collect()),
+            Field(_, ref term, _) | Borrow(_, ref term) | Deref(_, ref term) |
+            BorrowMut(_, ref term) => term.rhs_vars(),
+            Variant(_, _, _, ref args) =>
+                args.iter()
+                    .map(|arg| arg.rhs_vars())
+                    .fold(HashSet::new(), |l, r| l.union(&r).cloned().collect()),
+            Match(_, ref scrutinee, ref arms) =>
+                arms.iter()
+                    .map(|&(_, ref body)| body.rhs_vars())
+                    .fold(scrutinee.rhs_vars(), |l, r| l.union(&r).cloned().collect()),
         }
     }
 }
@@ -73,10 +111,14 @@ impl TaggedStatement<Type> {
         use type_check::TaggedStatement::*;
         match *self {
             TermSemicolon(_, ref term) => term.rhs_vars(),
-            Let(_, _, ref rhs) => rhs.rhs_vars(),
-            LetMut(_, _, ref rhs) => rhs.rhs_vars(),
+            Let(_, _, _, ref rhs) => rhs.rhs_vars(),
+            LetMut(_, _, _, ref rhs) => rhs.rhs_vars(),
             Mutate(_, _, ref rhs) => rhs.rhs_vars(),
             Extern(_, _, _) => HashSet::new(),
+            Declare(_, _, _) => HashSet::new(),
+            StructDecl(_, _, _) | EnumDecl(_, _, _) | TypeAlias(_, _, _) => HashSet::new(),
+            MutateThrough(_, ref target, ref rhs) =>
+                target.rhs_vars().union(&rhs.rhs_vars()).cloned().collect(),
         }
     }
 }
@@ -95,6 +137,242 @@ impl TaggedBlock<Type> {
     }
 }
 
+// The bindings codegen sees, one map per scope with the innermost last. A term that opens a scope
+// pushes one and pops it when it's built, so the bindings are never copied and a lookup borrows
+// the binding it finds. The scopes are the ones the checker checks the program in.
+pub struct Symbols {
+    scopes: Vec<Map<EnvData>>,
+    // Called as each function is built, each lambda's and then main's.
+    built_function: Option<Rc<Fn()>>,
+}
+
+impl Symbols {
+    pub fn new() -> Symbols {
+        Symbols { scopes: vec![Map::new()], built_function: None }
+    }
+
+    pub fn with_callback(built_function: Rc<Fn()>) -> Symbols {
+        Symbols { scopes: vec![Map::new()], built_function: Some(built_function) }
+    }
+
+    pub fn built_function(&self) {
+        if let Some(ref built_function) = self.built_function {
+            built_function();
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EnvData> {
+        self.scopes.iter().rev().filter_map(|scope| scope.get(name)).next()
+    }
+
+    pub fn insert(&mut self, name: String, data: EnvData) {
+        self.scopes.last_mut().unwrap().insert(name, data);
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Map::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+}
+
+// Builds a phi for each variable `cond` reads, with the value it has at the end of `incoming`.
+// They're sorted by name so that the IR doesn't depend on the order of a hash set.
+unsafe fn cond_phis(cond: &TaggedTerm<Type>,
+                    env: &Symbols,
+                    builder: LLVMBuilderRef,
+                    incoming: LLVMBasicBlockRef) -> Result<Vec<(String, EnvData)>, Vec<String>> {
+    let mut names: Vec<String> = cond.rhs_vars().into_iter().collect();
+    names.sort();
+    let mut phis = Vec::new();
+    for name in names {
+        if let Some(data) = env.get(&name) {
+            let ty = LLVMTypeOf(data.llvm_value);
+            let phi = LLVMBuildPhi(builder, ty, try!(name.to_raw()).as_ptr());
+            LLVMAddIncoming(phi, [data.llvm_value].as_mut_ptr(), [incoming].as_mut_ptr(), 1);
+            let phi_data =
+                EnvData { llvm_value: phi, direction: data.direction, ty: data.ty.clone() };
+            phis.push((name, phi_data));
+        }
+    }
+    Ok(phis)
+}
+
+// Builds `cond` as an i1. Only legacy mode lets an I32 condition through, which is true when it
+// isn't zero.
+unsafe fn build_cond(cond: &TaggedTerm<Type>,
+                     module: LLVMModuleRef,
+                     func: LLVMValueRef,
+                     entry: LLVMBasicBlockRef,
+                     builder: LLVMBuilderRef,
+                     env: &mut Symbols) -> Result<LLVMValueRef, Vec<String>> {
+    use llvm_sys::LLVMIntPredicate::LLVMIntNE;
+    let built = try!(cond.build(module, func, entry, builder, env));
+    if *cond.get_tag() == I32Ty {
+        let zero = LLVMConstInt(LLVMIntType(32), 0, 0);
+        Ok(LLVMBuildICmp(builder, LLVMIntNE, built, zero, try!("cond".to_raw()).as_ptr()))
+    } else {
+        Ok(built)
+    }
+}
+
+// The width in bits of an integer type.
+fn int_width(ty: &Type) -> u32 {
+    match *ty {
+        I8Ty => 8,
+        I16Ty => 16,
+        I32Ty | U32Ty | CharTy => 32,
+        I64Ty => 64,
+        _ => unreachable!(),
+    }
+}
+
+// Widening extends the sign of a signed source and zero-extends U32; narrowing truncates, and
+// casting between I32, U32 and Char keeps the bits as they are.
+// A cast to F64 rounds to the nearest float, and one from F64 rounds toward zero. A float out of
+// the range of the integer type gives an undefined value.
+unsafe fn build_cast(value: LLVMValueRef,
+                     from: &Type,
+                     to: &Type,
+                     builder: LLVMBuilderRef) -> Result<LLVMValueRef, Vec<String>> {
+    let name = try!("cast".to_raw());
+    let target = LLVMTypeRef::from(to);
+    if *from == F64Ty && *to == F64Ty {
+        Ok(value)
+    } else if *to == F64Ty {
+        if *from == U32Ty {
+            Ok(LLVMBuildUIToFP(builder, value, target, name.as_ptr()))
+        } else {
+            Ok(LLVMBuildSIToFP(builder, value, target, name.as_ptr()))
+        }
+    } else if *from == F64Ty {
+        if *to == U32Ty {
+            Ok(LLVMBuildFPToUI(builder, value, target, name.as_ptr()))
+        } else {
+            Ok(LLVMBuildFPToSI(builder, value, target, name.as_ptr()))
+        }
+    } else if int_width(to) > int_width(from) {
+        if *from == U32Ty {
+            Ok(LLVMBuildZExt(builder, value, target, name.as_ptr()))
+        } else {
+            Ok(LLVMBuildSExt(builder, value, target, name.as_ptr()))
+        }
+    } else if int_width(to) < int_width(from) {
+        Ok(LLVMBuildTrunc(builder, value, target, name.as_ptr()))
+    } else {
+        Ok(value)
+    }
+}
+
+// Loads element `index` of `array`, trapping if it's out of bounds. The index is compared
+// unsigned, so a negative one is out of bounds too.
+// The array is stored in a slot in the entry block, so indexing in a loop doesn't grow the stack.
+unsafe fn build_index(array: LLVMValueRef,
+                      array_ty: &Type,
+                      index: LLVMValueRef,
+                      module: LLVMModuleRef,
+                      func: LLVMValueRef,
+                      entry: LLVMBasicBlockRef,
+                      builder: LLVMBuilderRef) -> Result<LLVMValueRef, Vec<String>> {
+    use llvm_sys::LLVMIntPredicate::LLVMIntULT;
+    let len = match *array_ty {
+        ArrayTy(_, len) => len,
+        _ => unreachable!(),
+    };
+    let current = LLVMGetInsertBlock(builder);
+    let first = LLVMGetFirstInstruction(entry);
+    if first.is_null() {
+        LLVMPositionBuilderAtEnd(builder, entry);
+    } else {
+        LLVMPositionBuilderBefore(builder, first);
+    }
+    let slot = LLVMBuildAlloca(builder, LLVMTypeRef::from(array_ty),
+                               try!("array".to_raw()).as_ptr());
+    LLVMPositionBuilderAtEnd(builder, current);
+    LLVMBuildStore(builder, array, slot);
+    let in_bounds = LLVMBuildICmp(builder, LLVMIntULT, index,
+                                  LLVMConstInt(LLVMIntType(32), len as u64, 0),
+                                  try!("inbounds".to_raw()).as_ptr());
+    let ok_block = LLVMAppendBasicBlock(func, try!("inbounds".to_raw()).as_ptr());
+    let trap_block = LLVMAppendBasicBlock(func, try!("outofbounds".to_raw()).as_ptr());
+    LLVMBuildCondBr(builder, in_bounds, ok_block, trap_block);
+    LLVMPositionBuilderAtEnd(builder, trap_block);
+    let trap_name = try!("llvm.trap".to_raw());
+    let mut trap = LLVMGetNamedFunction(module, trap_name.as_ptr());
+    if trap.is_null() {
+        let args: &mut [LLVMTypeRef] = &mut [];
+        let trap_ty = LLVMFunctionType(LLVMVoidType(), args.as_mut_ptr(), 0, 0);
+        trap = LLVMAddFunction(module, trap_name.as_ptr(), trap_ty);
+    }
+    let no_args: &mut [LLVMValueRef] = &mut [];
+    LLVMBuildCall(builder, trap, no_args.as_mut_ptr(), 0, try!("".to_raw()).as_ptr());
+    LLVMBuildUnreachable(builder);
+    LLVMPositionBuilderAtEnd(builder, ok_block);
+    let mut indices = [LLVMConstInt(LLVMIntType(32), 0, 0), index];
+    let elem = LLVMBuildGEP(builder, slot, indices.as_mut_ptr(), 2,
+                            try!("elem".to_raw()).as_ptr());
+    Ok(LLVMBuildLoad(builder, elem, try!("load".to_raw()).as_ptr()))
+}
+
+// Calls the runtime function `name`, declaring it first if the module doesn't yet.
+unsafe fn call_runtime(name: &str,
+                       args: &mut [LLVMValueRef],
+                       module: LLVMModuleRef,
+                       builder: LLVMBuilderRef) -> Result<LLVMValueRef, Vec<String>> {
+    let raw_name = try!(name.to_raw());
+    let mut runtime_func = LLVMGetNamedFunction(module, raw_name.as_ptr());
+    if runtime_func.is_null() {
+        let ty = runtime_extern_set().decls
+                                     .into_iter()
+                                     .find(|&(ref symbol, _)| symbol == name)
+                                     .map(|(_, ty)| ty)
+                                     .unwrap();
+        runtime_func = LLVMAddFunction(module, raw_name.as_ptr(), LLVMTypeRef::from(&ty));
+    }
+    Ok(LLVMBuildCall(builder, runtime_func, args.as_mut_ptr(), args.len() as u32,
+                     try!("call".to_raw()).as_ptr()))
+}
+
+// Lowers a call to the prelude's print to a runtime call for every piece of the format string,
+// after building all of the arguments in order.
+unsafe fn build_print(args: &[TaggedTerm<Type>],
+                      module: LLVMModuleRef,
+                      func: LLVMValueRef,
+                      entry: LLVMBasicBlockRef,
+                      builder: LLVMBuilderRef,
+                      env: &mut Symbols) -> Result<LLVMValueRef, Vec<String>> {
+    use std::ptr::null;
+    use llvm_sys::LLVMValue;
+    let segments = match args[0] {
+        TaggedTerm::StrLit(_, ref format) =>
+            try!(parse_format(format).map_err(|error| vec![error])),
+        _ => unreachable!(),
+    };
+    let mut values = Vec::new();
+    for arg in &args[1..] {
+        values.push((try!(arg.build(module, func, entry, builder, env)), *arg.get_tag()));
+    }
+    let mut values = values.into_iter();
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => {
+                let text = LLVMBuildGlobalStringPtr(builder, try!(text[..].to_raw()).as_ptr(),
+                                                    try!("str".to_raw()).as_ptr());
+                try!(call_runtime("ende_print_str", &mut [text], module, builder));
+            }
+            Segment::Directive => {
+                let (value, ty) = values.next().unwrap();
+                let name = if ty == StrTy { "ende_print_str" } else { "ende_write_i32" };
+                try!(call_runtime(name, &mut [value], module, builder));
+            }
+        }
+    }
+    try!(call_runtime("ende_print_newline", &mut [], module, builder));
+    Ok(null::<LLVMValue>() as *mut _)
+}
+
 pub trait Compile {
 
     type Env;
@@ -106,16 +384,25 @@ pub trait Compile {
                     func: LLVMValueRef,
                     entry: LLVMBasicBlockRef,
                     builder: LLVMBuilderRef,
-                    env: Self::Env) -> Result<LLVMValueRef, Vec<String>>;
+                    env: &mut Self::Env) -> Result<LLVMValueRef, Vec<String>>;
 
     fn init_module(self: &Self,
                    module: LLVMModuleRef,
                    func: LLVMValueRef,
                    builder: LLVMBuilderRef) -> Result<(), Vec<String>> {
+        self.init_module_in(module, func, builder, <Self as Compile>::new_env())
+    }
+
+    // Like `init_module`, building in `env`.
+    fn init_module_in(self: &Self,
+                      module: LLVMModuleRef,
+                      func: LLVMValueRef,
+                      builder: LLVMBuilderRef,
+                      mut env: Self::Env) -> Result<(), Vec<String>> {
         unsafe {
             let entry = LLVMAppendBasicBlock(func, "entry\0".as_ptr() as *const i8);
             LLVMPositionBuilderAtEnd(builder, entry);
-            match self.build(module, func, entry, builder, <Self as Compile>::new_env()) {
+            match self.build(module, func, entry, builder, &mut env) {
                 Ok(val) => {
                     LLVMBuildRet(builder, val);
                     Ok(())
@@ -126,14 +413,19 @@ pub trait Compile {
     }
 
     fn gen_module(self: &Self) -> Result<LLVMModuleRef, Vec<String>> {
+        self.gen_module_in(<Self as Compile>::new_env())
+    }
+
+    // Like `gen_module`, building in `env`.
+    fn gen_module_in(self: &Self, env: Self::Env) -> Result<LLVMModuleRef, Vec<String>> {
         unsafe {
             let name = try!("Main".to_raw());
-            let module = LLVMModuleCreateWithName(name);
+            let module = LLVMModuleCreateWithName(name.as_ptr());
             let args: &mut [LLVMTypeRef] = &mut [];
             let func_ty = LLVMFunctionType(LLVMInt32Type(), args.as_mut_ptr() , 0, 0);
-            let func = LLVMAddFunction(module, try!("main".to_raw()), func_ty);
+            let func = LLVMAddFunction(module, try!("main".to_raw()).as_ptr(), func_ty);
             let builder = LLVMCreateBuilder();
-            try!(self.init_module(module, func, builder));
+            try!(self.init_module_in(module, func, builder, env));
             Ok(module)
         }
     }
@@ -142,28 +434,37 @@ pub trait Compile {
 
 impl Compile for TaggedTerm<Type> {
 
-    type Env = Map<EnvData>;
+    type Env = Symbols;
 
-    fn new_env() -> Self::Env { Map::new() }
+    fn new_env() -> Self::Env { Symbols::new() }
 
     fn build(self: &Self,
              module: LLVMModuleRef,
              func: LLVMValueRef,
              entry: LLVMBasicBlockRef,
              builder: LLVMBuilderRef,
-             env: Self::Env) -> Result<LLVMValueRef, Vec<String>> {
+             env: &mut Self::Env) -> Result<LLVMValueRef, Vec<String>> {
         use type_check::TaggedTerm::*;
         unsafe {
             // Build the instructions.
             match *self {
-                Literal(_, i) => Ok(LLVMConstInt(LLVMIntType(32), i as u64, 0)),
+                Literal(ref ty, i) => Ok(LLVMConstInt(LLVMTypeRef::from(ty), i as u64, 0)),
+                BoolLiteral(_, b) => Ok(LLVMConstInt(LLVMInt1Type(), b as u64, 0)),
+                SuffixedLiteral(_, i, ref ty) =>
+                    Ok(LLVMConstInt(LLVMTypeRef::from(ty), i as u64, 1)),
+                FloatLiteral(_, bits) => Ok(LLVMConstReal(LLVMDoubleType(), f64::from_bits(bits))),
+                CharLit(_, c) => Ok(LLVMConstInt(LLVMIntType(32), c as u64, 0)),
+                // A pointer to a NUL-terminated global constant.
+                StrLit(_, ref s) => Ok(LLVMBuildGlobalStringPtr(builder,
+                                                                try!(s[..].to_raw()).as_ptr(),
+                                                                try!("str".to_raw()).as_ptr())),
                 Var(_, ref str) => {
                     match env.get(str) {
                         Some(data) => {
                             use self::Direction::*;
                             match data.direction {
                                 Indirect => Ok(LLVMBuildLoad(
-                                    builder, data.llvm_value, try!("load".to_raw())
+                                    builder, data.llvm_value, try!("load".to_raw()).as_ptr()
                                 )),
                                 Direct => Ok(data.llvm_value),
                             }
@@ -172,32 +473,80 @@ impl Compile for TaggedTerm<Type> {
                             Err(vec![format!("Variable {} isn't declared yet.", str)]),
                     }
                 }
-                Infix(_, ref left, ref op, ref right) => {
+                Infix(ref tag, ref left, ref op, ref right) => {
                     use ast::Operator::*;
-                    let another_env = env.clone();
+                    let operand_ty = left.get_tag();
+                    // See `semantics::INFIX_OPERANDS`.
                     let left = try!(left.build(module, func, entry, builder, env));
-                    let right = try!(right.build(module, func, entry, builder, another_env));
+                    let right = try!(right.build(module, func, entry, builder, env));
+                    if op.is_comparison() {
+                        return build_comparison(*op, &operand_ty, left, right, builder);
+                    }
+                    // Both operands are already built; see `semantics` on `&&` and `||`.
+                    match *op {
+                        And | BitAnd => return Ok(LLVMBuildAnd(builder, left, right,
+                                                               try!("and".to_raw()).as_ptr())),
+                        Or | BitOr => return Ok(LLVMBuildOr(builder, left, right,
+                                                            try!("or".to_raw()).as_ptr())),
+                        BitXor => return Ok(LLVMBuildXor(builder, left, right,
+                                                         try!("xor".to_raw()).as_ptr())),
+                        _ => {}
+                    }
+                    if *tag == F64Ty {
+                        let name = try!("float".to_raw());
+                        return Ok(match *op {
+                            Add => LLVMBuildFAdd(builder, left, right, name.as_ptr()),
+                            Sub => LLVMBuildFSub(builder, left, right, name.as_ptr()),
+                            Mul => LLVMBuildFMul(builder, left, right, name.as_ptr()),
+                            Div => LLVMBuildFDiv(builder, left, right, name.as_ptr()),
+                            Rem | Eq | Ne | Lt | Le | Gt | Ge | And | Or | BitAnd | BitOr |
+                            BitXor => unreachable!(),
+                        });
+                    }
                     match *op {
                         Add => Ok(LLVMBuildAdd(
-                            builder, left, right, try!("add".to_raw())
+                            builder, left, right, try!("add".to_raw()).as_ptr()
                         )),
                         Sub => Ok(LLVMBuildSub(
-                            builder, left, right, try!("sub".to_raw())
+                            builder, left, right, try!("sub".to_raw()).as_ptr()
                         )),
                         Mul => Ok(LLVMBuildMul(
-                            builder, left, right, try!("mul".to_raw())
-                        )),
-                        Div => Ok(LLVMBuildSDiv(
-                            builder, left, right, try!("div".to_raw())
+                            builder, left, right, try!("mul".to_raw()).as_ptr()
                         )),
+                        // Truncating, as `semantics::apply` defines it; unsigned for U32.
+                        Div => if *tag == U32Ty {
+                            Ok(LLVMBuildUDiv(
+                                builder, left, right, try!("div".to_raw()).as_ptr()
+                            ))
+                        } else {
+                            Ok(LLVMBuildSDiv(
+                                builder, left, right, try!("div".to_raw()).as_ptr()
+                            ))
+                        },
+                        // Takes the sign of the dividend, as `semantics::apply` defines it.
+                        Rem => if *tag == U32Ty {
+                            Ok(LLVMBuildURem(
+                                builder, left, right, try!("rem".to_raw()).as_ptr()
+                            ))
+                        } else {
+                            Ok(LLVMBuildSRem(
+                                builder, left, right, try!("rem".to_raw()).as_ptr()
+                            ))
+                        },
+                        Eq | Ne | Lt | Le | Gt | Ge | And | Or | BitAnd | BitOr | BitXor =>
+                            unreachable!(),
                     }
                 }
+                Call(_, ref func_call, ref args)
+                    if is_builtin_print(&func_call.name, env.get(&func_call.name).is_some()) =>
+                    build_print(args, module, func, entry, builder, env),
                 Call(_, ref func_call, ref args) => {
 
+                    let terms = args;
                     let ref name = func_call.name;
                     let llvm_func = if let Some(env_data) = env.get(name) {
                         let expected_arity;
-                        if let FunctionTy(ref args_types, _) = env_data.ty {
+                        if let FunctionTy(_, ref args_types, _) = env_data.ty {
                             expected_arity = args_types.len();
                         } else {
                             unreachable!();
@@ -213,16 +562,32 @@ impl Compile for TaggedTerm<Type> {
                             );
                             return Err(vec![error_message]);
                         }
-                        env_data.llvm_value
+                        // A mutable variable of function type holds a pointer to the function
+                        // in its slot.
+                        let callee = match env_data.direction {
+                            Direction::Indirect => LLVMBuildLoad(builder,
+                                                                 env_data.llvm_value,
+                                                                 try!("callee".to_raw()).as_ptr()),
+                            Direction::Direct => env_data.llvm_value,
+                        };
+                        if is_generic(&env_data.ty) {
+                            LLVMBuildBitCast(builder,
+                                             callee,
+                                             slot_type(&func_call.tag),
+                                             try!("instance".to_raw()).as_ptr())
+                        } else {
+                            callee
+                        }
                     } else {
                         return Err(
                             vec![format!("Function {} hasn't been declared yet.", name)]
                         );
                     };
 
+                    // The arguments are built in order, see `semantics::CALL_ARGUMENTS`.
                     let results: Vec<Result<LLVMValueRef, Vec<String>>> =
                         args.iter()
-                            .map(|term| term.build(module, func, entry, builder, env.clone()))
+                            .map(|term| term.build(module, func, entry, builder, env))
                             .collect();
 
                     // It's really so painful.
@@ -253,235 +618,576 @@ impl Compile for TaggedTerm<Type> {
                     // let mut raw_args = try!(result_args).as_mut_ptr();
                     // The above line makes the program segfault. Wierd.
                     let mut args: Vec<LLVMValueRef> = try!(result_args);
+                    if let FunctionTy(_, ref args_types, _) = func_call.tag {
+                        for ((value, term), ty) in args.iter_mut().zip(terms).zip(args_types) {
+                            *value = value_or_undef(*value, term, ty);
+                        }
+                    }
                     let raw_args = args.as_mut_ptr();
                     let name = &*("call".to_string() + &*func_call.name);
                     let value = LLVMBuildCall(builder,
                                               llvm_func,
                                               raw_args,
                                               args.len() as u32,
-                                              try!(name.to_raw())
+                                              try!(name.to_raw()).as_ptr()
                                              );
                     Ok(value)
                 }
                 Scope(_, ref block) => {
-                    let new_env = env.clone();
-                    let block_result =
-                        block.build(module, func, entry, builder, Box::new(new_env));
+                    env.push_scope();
+                    let block_result = block.build(module, func, entry, builder, env);
+                    env.pop_scope();
                     let block = try!(block_result);
                     Ok(block)
                 }
-                If(_, ref cond, ref if_true, ref if_false) => {
-                    use self::Direction::*;
+                If(ref tag, ref cond, ref if_true, ref if_false) => {
+                    use std::ptr::null;
+                    use llvm_sys::LLVMValue;
                     // Build the condition.
-                    let built_cond = try!(cond.build(module, func, entry, builder, env.clone()));
-                    // And check if the condition equals to zero.
-                    let zero = LLVMConstInt(LLVMIntType(32), 0, 0);
-                    use llvm_sys::LLVMIntPredicate::LLVMIntEQ;
-                    let is_zero = LLVMBuildICmp(
-                        builder, LLVMIntEQ, built_cond, zero, try!("iszero".to_raw())
-                    );
+                    let built_cond = try!(build_cond(cond, module, func, entry, builder, env));
                     // Create the basic blocks.
-                    let then_branch = LLVMAppendBasicBlock(func, try!("then".to_raw()));
-                    let else_branch = LLVMAppendBasicBlock(func, try!("else".to_raw()));
-                    let next = LLVMAppendBasicBlock(func, try!("next".to_raw()));
-                    LLVMBuildCondBr(builder, is_zero, else_branch, then_branch);
+                    let then_branch = LLVMAppendBasicBlock(func, try!("then".to_raw()).as_ptr());
+                    let else_branch = LLVMAppendBasicBlock(func, try!("else".to_raw()).as_ptr());
+                    let next = LLVMAppendBasicBlock(func, try!("next".to_raw()).as_ptr());
+                    // The condition may have ended in another block than it started in.
+                    let cond_block = LLVMGetInsertBlock(builder);
+                    LLVMBuildCondBr(builder, built_cond, then_branch, else_branch);
                     // Now go inside the true case.
                     LLVMPositionBuilderAtEnd(builder, then_branch);
-                    // Create a new environment.
-                    let mut new_env = env.clone();
-                    // Build the phi nodes.
-                    for (key, env_data) in &env {
-                        if cond.rhs_vars().contains(&**key) {
-                            match env_data.direction {
-                                Indirect => {
-                                    let ty = LLVMPointerType(LLVMIntType(32), 0);
-                                    let phi = LLVMBuildPhi(builder, ty, key.as_ptr() as *const i8);
-                                    LLVMAddIncoming(phi,
-                                                    [env_data.llvm_value].as_mut_ptr(),
-                                                    [entry].as_mut_ptr(),
-                                                    1);
-                                    let new_data = EnvData {
-                                        llvm_value: phi,
-                                        direction: Indirect,
-                                        ty: I32Ty,
-                                    };
-                                    new_env.insert(key.clone(), new_data);
-                                }
-                                Direct => {
-                                    let name = try!((*key).to_raw());
-                                    let phi =
-                                        LLVMBuildPhi(builder, LLVMIntType(32), name);
-                                    let another_env = env.clone();
-                                    let old_data = another_env.get(key).unwrap(); // Safe here.
-                                    LLVMAddIncoming(phi,
-                                                    [old_data.llvm_value].as_mut_ptr(),
-                                                    [entry].as_mut_ptr(),
-                                                    1);
-                                    // Update the enviroment.
-                                    let new_data = EnvData {
-                                        llvm_value: phi,
-                                        direction: old_data.direction,
-                                        ty: I32Ty,
-                                    };
-                                    new_env.insert(key.clone(), new_data);
-                                }
-                            }
-                        }
+                    // Build the phi nodes in a scope of their own.
+                    let phis = try!(cond_phis(cond, env, builder, cond_block));
+                    env.push_scope();
+                    for (name, data) in phis {
+                        env.insert(name, data);
                     }
-                    let then_val =
-                        try!(if_true.build(module, func, entry, builder, env.clone()));
+                    let then_result = if_true.build(module, func, entry, builder, env);
+                    env.pop_scope();
+                    let then_val = try!(then_result);
+                    let then_end = LLVMGetInsertBlock(builder);
                     LLVMBuildBr(builder, next);
                     // Switch to the false case and do everything again.
-                    // The code below is copy-pasted for not overengineering.
                     LLVMPositionBuilderAtEnd(builder, else_branch);
-                    let mut new_env = env.clone();
-                    for (key, env_data) in &env {
-                        if cond.rhs_vars().contains(&**key) {
-                            match env_data.direction {
-                                Indirect => {
-                                    let ty = LLVMPointerType(LLVMIntType(32), 0);
-                                    let phi = LLVMBuildPhi(builder, ty, key.as_ptr() as *const i8);
-                                    LLVMAddIncoming(phi,
-                                                    [env_data.llvm_value].as_mut_ptr(),
-                                                    [entry].as_mut_ptr(),
-                                                    1);
-                                    let env_data = EnvData {
-                                        llvm_value: phi,
-                                        direction: Indirect,
-                                        ty: I32Ty,
-                                    };
-                                    new_env.insert(key.clone(), env_data);
-                                }
-                                Direct => {
-                                    let name = try!((*key).to_raw());
-                                    let phi =
-                                        LLVMBuildPhi(builder, LLVMIntType(32), name);
-                                    let another_env = env.clone();
-                                    let old_data = another_env.get(key).unwrap(); // Safe here.
-                                    LLVMAddIncoming(phi,
-                                                    [old_data.llvm_value].as_mut_ptr(),
-                                                    [entry].as_mut_ptr(),
-                                                    1);
-                                    // Update the enviroment.
-                                    let new_data = EnvData {
-                                        llvm_value: phi,
-                                        direction: old_data.direction,
-                                        ty: I32Ty,
-                                    };
-                                    new_env.insert(key.clone(), new_data);
-                                }
-                            }
-                        }
+                    let phis = try!(cond_phis(cond, env, builder, cond_block));
+                    env.push_scope();
+                    for (name, data) in phis {
+                        env.insert(name, data);
                     }
-                    let else_val =
-                        try!(if_false.build(module, func, entry, builder, env.clone()));
+                    let else_result = match *if_false {
+                        Some(ref if_false) =>
+                            if_false.build(module, func, entry, builder, env)
+                                    .map(|value| value_or_undef(value, if_false, tag)),
+                        None => Ok(null::<LLVMValue>() as *mut _),
+                    };
+                    env.pop_scope();
+                    let else_val = try!(else_result);
+                    let else_end = LLVMGetInsertBlock(builder);
                     LLVMBuildBr(builder, next);
                     // Place The builder at the end of the last loop.
                     LLVMPositionBuilderAtEnd(builder, next);
-                    // New enviroment, again.
-                    let mut new_env = env.clone();
+                    // Unit has no values to choose between.
+                    if *tag == UnitTy {
+                        return Ok(null::<LLVMValue>() as *mut _);
+                    }
                     // Build the last phi node representing the value of the whole if-then-else
                     // clause.
-                    let if_str = "if";
-                    let name = try!(if_str.to_raw());
-                    let phi = LLVMBuildPhi(builder, LLVMIntType(32), name);
+                    // A branch that never finishes has no value of its own.
+                    let then_val = value_or_undef(then_val, if_true, tag);
+                    let name = try!("if".to_raw());
+                    let phi = LLVMBuildPhi(builder, LLVMTypeOf(then_val), name.as_ptr());
                     LLVMAddIncoming(phi,
                                     [then_val, else_val].as_mut_ptr(),
-                                    [then_branch, else_branch].as_mut_ptr(),
+                                    [then_end, else_end].as_mut_ptr(),
                                     2);
-                    let env_data = EnvData { llvm_value: phi, direction: Direct, ty: I32Ty };
-                    new_env.insert(if_str.to_string(), env_data);
                     Ok(phi)
                 }
                 While(_, ref cond, ref block) => {
                     // Build the condition.
                     // It has to be done first because it could mutate variables.
-                    let built_cond = try!(cond.build(module, func, entry, builder, env.clone()));
-                    // And check if the condition equals to zero.
-                    let zero = LLVMConstInt(LLVMIntType(32), 0, 0);
-                    use llvm_sys::LLVMIntPredicate::LLVMIntEQ;
-                    let is_zero = LLVMBuildICmp(
-                        builder, LLVMIntEQ, built_cond, zero, try!("iszero".to_raw())
-                    );
+                    let built_cond = try!(build_cond(cond, module, func, entry, builder, env));
                     // Create the basic blocks.
-                    let loop_block = LLVMAppendBasicBlock(func, try!("loop".to_raw()));
-                    let after_loop = LLVMAppendBasicBlock(func, try!("afterloop".to_raw()));
-                    LLVMBuildCondBr(builder, is_zero, after_loop, loop_block);
+                    let loop_block = LLVMAppendBasicBlock(func, try!("loop".to_raw()).as_ptr());
+                    let after_loop =
+                        LLVMAppendBasicBlock(func, try!("afterloop".to_raw()).as_ptr());
+                    let before_loop = LLVMGetInsertBlock(builder);
+                    LLVMBuildCondBr(builder, built_cond, loop_block, after_loop);
                     // Now go inside the loop.
                     LLVMPositionBuilderAtEnd(builder, loop_block);
-                    // Create a new environment.
-                    let mut new_env = env.clone();
-                    // Build the phi nodes.
-                    for (key, pair) in &env {
-                        if cond.rhs_vars().contains(key) {
-                            use self::Direction::*;
-                            match pair.direction {
-                                Indirect => {
-                                    let ty = LLVMPointerType(LLVMIntType(32), 0);
-                                    let phi = LLVMBuildPhi(builder, ty, key.as_ptr() as *const i8);
-                                    let old_ptr = (&env.get(key)).unwrap().llvm_value;
-                                    LLVMAddIncoming(phi,
-                                                    [old_ptr, phi].as_mut_ptr(),
-                                                    [entry, loop_block].as_mut_ptr(),
-                                                    2);
-                                    let env_data = EnvData {
-                                        llvm_value: phi,
-                                        direction: Indirect,
-                                        ty: I32Ty,
-                                    };
-                                    new_env.insert(key.clone(), env_data);
-                                }
-                                Direct => {
-                                    let name = try!((*key).to_raw());
-                                    let phi =
-                                        LLVMBuildPhi(builder, LLVMIntType(32), name);
-                                    let another_env = env.clone();
-                                    let old_data = another_env.get(key).unwrap(); // Safe here.
-                                    LLVMAddIncoming(phi,
-                                                    [old_data.llvm_value, phi].as_mut_ptr(),
-                                                    [entry, loop_block].as_mut_ptr(),
-                                                    2);
-                                    // Update the enviroment.
-                                    let new_data = EnvData {
-                                        llvm_value: phi,
-                                        direction: old_data.direction,
-                                        ty: I32Ty,
-                                    };
-                                    new_env.insert(key.clone(), new_data);
-                                }
-                            }
-                        }
+                    // Build the phi nodes in a scope of their own.
+                    // The incoming values from the end of the body are added once it's built.
+                    let phis = try!(cond_phis(cond, env, builder, before_loop));
+                    let loop_phis: Vec<LLVMValueRef> =
+                        phis.iter().map(|&(_, ref data)| data.llvm_value).collect();
+                    env.push_scope();
+                    for (name, data) in phis {
+                        env.insert(name, data);
                     }
-                    try!(block.build(module, func, entry, builder, Box::new(new_env.clone())));
+                    // The body gets another scope, so that the condition doesn't see its lets.
+                    env.push_scope();
+                    let body_result = block.build(module, func, entry, builder, env);
+                    env.pop_scope();
                     // Check the condition for next iteration.
-                    let built_cond = try!(cond.build(module, func, entry, builder, new_env));
-                    let is_zero = LLVMBuildICmp(
-                        builder, LLVMIntEQ, built_cond, zero, try!("iszero".to_raw())
-                    );
-                    LLVMBuildCondBr(builder, is_zero, after_loop, loop_block);
+                    let cond_result = body_result.and_then(|_| {
+                        build_cond(cond, module, func, entry, builder, env)
+                    });
+                    env.pop_scope();
+                    let built_cond = try!(cond_result);
+                    let loop_end = LLVMGetInsertBlock(builder);
+                    for phi in loop_phis {
+                        LLVMAddIncoming(phi, [phi].as_mut_ptr(), [loop_end].as_mut_ptr(), 1);
+                    }
+                    LLVMBuildCondBr(builder, built_cond, loop_block, after_loop);
                     // Place The builder at the end of the last loop.
                     LLVMPositionBuilderAtEnd(builder, after_loop);
                     // Done.
-                    Ok(zero)
+                    Ok(LLVMConstInt(LLVMIntType(32), 0, 0))
+                }
+                Stmt(_) => unimplemented!(),
+                Cast(ref to, ref term, _) => {
+                    let value = try!(term.build(module, func, entry, builder, env));
+                    build_cast(value, &term.get_tag(), to, builder)
+                }
+                Ascribe(ref ty, ref term, _) => {
+                    let value = try!(term.build(module, func, entry, builder, env));
+                    Ok(value_or_undef(value, term, ty))
+                }
+                // The checker turns method calls into calls, so only a tree built by hand can
+                // have one, and release builds compile it without validating it first.
+                MethodCall(_, _, ref method, _) =>
+                    Err(vec![format!("The method call .{}(...) wasn't turned into a call; only \
+                                      checked trees can be compiled.", method.name)]),
+                // A struct value, filled in an element at a time.
+                Tuple(ref ty, ref elems) => {
+                    let mut tuple = LLVMGetUndef(LLVMTypeRef::from(ty));
+                    for (i, elem) in elems.iter().enumerate() {
+                        let value = try!(elem.build(module, func, entry, builder, env));
+                        tuple = LLVMBuildInsertValue(builder, tuple, value, i as u32,
+                                                     try!("tuple".to_raw()).as_ptr());
+                    }
+                    Ok(tuple)
+                }
+                Project(_, ref term, index) => {
+                    let tuple = try!(term.build(module, func, entry, builder, env));
+                    Ok(LLVMBuildExtractValue(builder, tuple, index,
+                                             try!("elem".to_raw()).as_ptr()))
+                }
+                ArrayLit(ref ty, ref elems) => {
+                    let mut array = LLVMGetUndef(LLVMTypeRef::from(ty));
+                    for (i, elem) in elems.iter().enumerate() {
+                        let value = try!(elem.build(module, func, entry, builder, env));
+                        array = LLVMBuildInsertValue(builder, array, value, i as u32,
+                                                     try!("array".to_raw()).as_ptr());
+                    }
+                    Ok(array)
+                }
+                Index(_, ref array, ref index) => {
+                    let built_array = try!(array.build(module, func, entry, builder, env));
+                    let built_index = try!(index.build(module, func, entry, builder, env));
+                    build_index(built_array, &array.get_tag(), built_index,
+                                module, func, entry, builder)
+                }
+                // The fields are built in the order they're written, and stored in the order
+                // they're declared.
+                StructLit(ref ty, _, ref inits) => {
+                    let structure = match *ty {
+                        Struct(ref structure) => structure,
+                        _ => unreachable!(),
+                    };
+                    let mut value = LLVMGetUndef(LLVMTypeRef::from(ty));
+                    for &(ref field, ref term) in inits {
+                        let built = try!(term.build(module, func, entry, builder, env));
+                        let (index, _) = structure.field(field).unwrap();
+                        value = LLVMBuildInsertValue(builder, value, built, index as u32,
+                                                     try!("struct".to_raw()).as_ptr());
+                    }
+                    Ok(value)
+                }
+                Field(_, ref term, ref field) => {
+                    let index = match *term.get_tag() {
+                        Struct(ref structure) => structure.field(field).unwrap().0,
+                        _ => unreachable!(),
+                    };
+                    let value = try!(term.build(module, func, entry, builder, env));
+                    Ok(LLVMBuildExtractValue(builder, value, index as u32,
+                                             try!("field".to_raw()).as_ptr()))
                 }
-                Stmt(_) => unimplemented!()
+                // The discriminant, and then the payload in the variant's own fields.
+                Variant(ref ty, _, ref variant, ref args) => {
+                    let enumeration = match *ty {
+                        Enum(ref enumeration) => enumeration,
+                        _ => unreachable!(),
+                    };
+                    let (index, _) = enumeration.variant(variant).unwrap();
+                    let mut value = LLVMGetUndef(LLVMTypeRef::from(ty));
+                    let discriminant = LLVMConstInt(LLVMInt32Type(), index as u64, 0);
+                    value = LLVMBuildInsertValue(builder, value, discriminant, 0,
+                                                 try!("enum".to_raw()).as_ptr());
+                    let offset = payload_offset(enumeration, index);
+                    for (i, arg) in args.iter().enumerate() {
+                        let built = try!(arg.build(module, func, entry, builder, env));
+                        value = LLVMBuildInsertValue(builder, value, built, offset + i as u32,
+                                                     try!("enum".to_raw()).as_ptr());
+                    }
+                    Ok(value)
+                }
+                Match(ref tag, ref scrutinee, ref arms) =>
+                    build_match(tag, scrutinee, arms, module, func, entry, builder, env),
+                // A mutable variable already has a slot. An immutable one is only a value, so
+                // borrowing it stores a copy in a new slot, which is fine since it never changes.
+                Borrow(_, ref term) => {
+                    use self::Direction::*;
+                    let name = match **term {
+                        Var(_, ref name) => name,
+                        _ => unreachable!(),
+                    };
+                    match env.get(name) {
+                        Some(&EnvData { llvm_value, direction: Indirect, .. }) => Ok(llvm_value),
+                        Some(&EnvData { llvm_value, direction: Direct, ref ty }) => {
+                            let slot = LLVMBuildAlloca(builder,
+                                                       LLVMTypeRef::from(ty),
+                                                       try!(name.to_raw()).as_ptr());
+                            LLVMBuildStore(builder, llvm_value, slot);
+                            Ok(slot)
+                        }
+                        None => Err(vec![format!("Variable {} isn't declared yet.", name)]),
+                    }
+                }
+                Deref(_, ref term) => {
+                    let pointer = try!(term.build(module, func, entry, builder, env));
+                    Ok(LLVMBuildLoad(builder, pointer, try!("deref".to_raw()).as_ptr()))
+                }
+                // A negated literal is a constant, which might not fit its type before it's
+                // negated, like `-2147483648`.
+                Neg(ref ty, ref term) => match **term {
+                    Literal(_, i) => {
+                        Ok(LLVMConstInt(LLVMTypeRef::from(ty), -(i as i64) as u64, 1))
+                    }
+                    SuffixedLiteral(_, i, _) => {
+                        Ok(LLVMConstInt(LLVMTypeRef::from(ty), -i as u64, 1))
+                    }
+                    _ => {
+                        let value = try!(term.build(module, func, entry, builder, env));
+                        let name = try!("neg".to_raw());
+                        if *ty == F64Ty {
+                            Ok(LLVMBuildFNeg(builder, value, name.as_ptr()))
+                        } else {
+                            Ok(LLVMBuildNeg(builder, value, name.as_ptr()))
+                        }
+                    }
+                },
+                Not(_, ref term) => {
+                    let value = try!(term.build(module, func, entry, builder, env));
+                    let true_value = LLVMConstInt(LLVMInt1Type(), 1, 0);
+                    Ok(LLVMBuildXor(builder, value, true_value, try!("not".to_raw()).as_ptr()))
+                }
+                // Mutable variables all have slots.
+                BorrowMut(_, ref term) => {
+                    use self::Direction::*;
+                    let name = match **term {
+                        Var(_, ref name) => name,
+                        _ => unreachable!(),
+                    };
+                    match env.get(name) {
+                        Some(&EnvData { llvm_value, direction: Indirect, .. }) => Ok(llvm_value),
+                        Some(_) => Err(vec![format!("Variable {} is immutable, so it cannot be \
+                                                     borrowed mutably.", name)]),
+                        None => Err(vec![format!("Variable {} isn't declared yet.", name)]),
+                    }
+                }
+                Null(_) => Ok(LLVMConstNull(LLVMTypeRef::from(&Ptr))),
+                PtrEq(_, ref left, ref right) => {
+                    use llvm_sys::LLVMIntPredicate::LLVMIntEQ;
+                    let left = try!(left.build(module, func, entry, builder, env));
+                    let right = try!(right.build(module, func, entry, builder, env));
+                    Ok(LLVMBuildICmp(builder, LLVMIntEQ, left, right,
+                                     try!("ptreq".to_raw()).as_ptr()))
+                }
+                Lambda(ref ty, ref params, ref body) =>
+                    build_lambda(ty, params, body, module, env),
+                Apply(ref ty, ref callee, ref args) => {
+                    let args_types = match *callee.get_tag() {
+                        FunctionTy(_, args_types, _) => args_types,
+                        _ => unreachable!(),
+                    };
+                    let callee = try!(callee.build(module, func, entry, builder, env));
+                    let mut built_args = Vec::new();
+                    for (arg, ty) in args.iter().zip(&args_types) {
+                        let value = try!(arg.build(module, func, entry, builder, env));
+                        built_args.push(value_or_undef(value, arg, ty));
+                    }
+                    // Calls returning nothing have no value to name.
+                    let name = if *ty == UnitTy { "" } else { "apply" };
+                    Ok(LLVMBuildCall(builder,
+                                     callee,
+                                     built_args.as_mut_ptr(),
+                                     built_args.len() as u32,
+                                     try!(name.to_raw()).as_ptr()))
+                }
+            }
+        }
+    }
+
+}
+
+// A lambda is a private function of its own, built with a builder of its own. It can only use
+// the externs outside of it, which are still in `env`, so its parameters go in a scope on top.
+unsafe fn build_lambda(ty: &Type,
+                       params: &[(String, Type)],
+                       body: &TaggedBlock<Type>,
+                       module: LLVMModuleRef,
+                       env: &mut Symbols) -> Result<LLVMValueRef, Vec<String>> {
+    use llvm_sys::LLVMLinkage::LLVMPrivateLinkage;
+    let lambda = LLVMAddFunction(module, try!("lambda".to_raw()).as_ptr(), LLVMTypeRef::from(ty));
+    LLVMSetLinkage(lambda, LLVMPrivateLinkage);
+    let entry = LLVMAppendBasicBlock(lambda, try!("entry".to_raw()).as_ptr());
+    let builder = LLVMCreateBuilder();
+    LLVMPositionBuilderAtEnd(builder, entry);
+    env.push_scope();
+    for (i, &(ref name, ref ty)) in params.iter().enumerate() {
+        let param = LLVMGetParam(lambda, i as u32);
+        env.insert(name.clone(),
+                   EnvData { llvm_value: param, direction: Direction::Direct, ty: ty.clone() });
+    }
+    let built = body.build(module, lambda, entry, builder, env);
+    env.pop_scope();
+    if let Ok(value) = built {
+        if body.tag == UnitTy {
+            LLVMBuildRetVoid(builder);
+        } else {
+            LLVMBuildRet(builder, value);
+        }
+    }
+    LLVMDisposeBuilder(builder);
+    try!(built);
+    env.built_function();
+    Ok(lambda)
+}
+
+// An enum is a struct of an I32 discriminant, the index of the variant, followed by the payloads
+// of all the variants one after another. Each variant only fills in its own fields, so no value
+// is ever reinterpreted as another type.
+fn payload_offset(enumeration: &Enumeration, index: usize) -> u32 {
+    let before: usize = enumeration.variants()[..index]
+                                   .iter()
+                                   .map(|&(_, ref payload)| payload.len())
+                                   .sum();
+    1 + before as u32
+}
+
+// Compares two values of type `ty` as `semantics` defines it, giving an i1.
+unsafe fn build_comparison(op: Operator,
+                           ty: &Type,
+                           left: LLVMValueRef,
+                           right: LLVMValueRef,
+                           builder: LLVMBuilderRef) -> Result<LLVMValueRef, Vec<String>> {
+    use llvm_sys::LLVMIntPredicate::*;
+    use llvm_sys::LLVMRealPredicate::*;
+    use ast::Operator::*;
+    let name = try!("cmp".to_raw());
+    match *ty {
+        F64Ty => {
+            let predicate = match op {
+                Eq => LLVMRealOEQ,
+                Ne => LLVMRealUNE,
+                Lt => LLVMRealOLT,
+                Le => LLVMRealOLE,
+                Gt => LLVMRealOGT,
+                Ge => LLVMRealOGE,
+                Add | Sub | Mul | Div | Rem | And | Or | BitAnd | BitOr | BitXor =>
+                    unreachable!(),
+            };
+            Ok(LLVMBuildFCmp(builder, predicate, left, right, name.as_ptr()))
+        }
+        Enum(ref enumeration) => {
+            let equal = try!(build_enum_eq(enumeration, left, right, builder));
+            match op {
+                Eq => Ok(equal),
+                Ne => Ok(LLVMBuildNot(builder, equal, name.as_ptr())),
+                _ => unreachable!(),
             }
         }
+        _ => {
+            let unsigned = *ty == U32Ty;
+            let predicate = match op {
+                Eq => LLVMIntEQ,
+                Ne => LLVMIntNE,
+                Lt => if unsigned { LLVMIntULT } else { LLVMIntSLT },
+                Le => if unsigned { LLVMIntULE } else { LLVMIntSLE },
+                Gt => if unsigned { LLVMIntUGT } else { LLVMIntSGT },
+                Ge => if unsigned { LLVMIntUGE } else { LLVMIntSGE },
+                Add | Sub | Mul | Div | Rem | And | Or | BitAnd | BitOr | BitXor =>
+                    unreachable!(),
+            };
+            Ok(LLVMBuildICmp(builder, predicate, left, right, name.as_ptr()))
+        }
     }
+}
 
+// The discriminants are equal, and so are the payloads of the variant the left value is. The
+// payloads of the other variants are undefined, so `select` picks the right comparison instead
+// of branching on the discriminant; when the discriminants differ, the `and` is false whatever
+// the payloads compared to.
+unsafe fn build_enum_eq(enumeration: &Enumeration,
+                        left: LLVMValueRef,
+                        right: LLVMValueRef,
+                        builder: LLVMBuilderRef) -> Result<LLVMValueRef, Vec<String>> {
+    use llvm_sys::LLVMIntPredicate::LLVMIntEQ;
+    let name = try!("enumeq".to_raw());
+    let left_discriminant = LLVMBuildExtractValue(builder, left, 0, name.as_ptr());
+    let right_discriminant = LLVMBuildExtractValue(builder, right, 0, name.as_ptr());
+    let same_variant = LLVMBuildICmp(builder, LLVMIntEQ, left_discriminant, right_discriminant,
+                                     name.as_ptr());
+    let mut payloads_equal = LLVMConstInt(LLVMInt1Type(), 1, 0);
+    for (index, &(_, ref payload)) in enumeration.variants().iter().enumerate() {
+        let offset = payload_offset(enumeration, index);
+        let mut variant_equal = LLVMConstInt(LLVMInt1Type(), 1, 0);
+        for (i, ty) in payload.iter().enumerate() {
+            let left_field = LLVMBuildExtractValue(builder, left, offset + i as u32,
+                                                   name.as_ptr());
+            let right_field = LLVMBuildExtractValue(builder, right, offset + i as u32,
+                                                    name.as_ptr());
+            let field_equal = try!(build_comparison(Operator::Eq, ty, left_field, right_field,
+                                                    builder));
+            variant_equal = LLVMBuildAnd(builder, variant_equal, field_equal, name.as_ptr());
+        }
+        let is_variant = LLVMBuildICmp(builder, LLVMIntEQ, left_discriminant,
+                                       LLVMConstInt(LLVMInt32Type(), index as u64, 0),
+                                       name.as_ptr());
+        payloads_equal = LLVMBuildSelect(builder, is_variant, variant_equal, payloads_equal,
+                                         name.as_ptr());
+    }
+    Ok(LLVMBuildAnd(builder, same_variant, payloads_equal, name.as_ptr()))
+}
+
+// Switches on the discriminant of the scrutinee. An arm that can never be reached, after another
+// arm for the same variant or after a wildcard, isn't built at all.
+unsafe fn build_match(tag: &Type,
+                      scrutinee: &TaggedTerm<Type>,
+                      arms: &[(Pattern, TaggedTerm<Type>)],
+                      module: LLVMModuleRef,
+                      func: LLVMValueRef,
+                      entry: LLVMBasicBlockRef,
+                      builder: LLVMBuilderRef,
+                      env: &mut Symbols) -> Result<LLVMValueRef, Vec<String>> {
+    let enumeration = match *scrutinee.get_tag() {
+        Enum(enumeration) => enumeration,
+        _ => unreachable!(),
+    };
+    let value = try!(scrutinee.build(module, func, entry, builder, env));
+    let discriminant = LLVMBuildExtractValue(builder, value, 0,
+                                             try!("discriminant".to_raw()).as_ptr());
+    let otherwise = LLVMAppendBasicBlock(func, try!("otherwise".to_raw()).as_ptr());
+    let next = LLVMAppendBasicBlock(func, try!("next".to_raw()).as_ptr());
+    let switch = LLVMBuildSwitch(builder, discriminant, otherwise, arms.len() as u32);
+    let mut covered = HashSet::new();
+    let mut has_wildcard = false;
+    let mut values = Vec::new();
+    let mut ends = Vec::new();
+    for &(ref pattern, ref body) in arms {
+        if has_wildcard {
+            break;
+        }
+        env.push_scope();
+        match *pattern {
+            Pattern::Constructor(_, ref variant, ref binds) => {
+                if !covered.insert(variant) {
+                    env.pop_scope();
+                    continue;
+                }
+                let (index, payload) = enumeration.variant(variant).unwrap();
+                let arm = LLVMAppendBasicBlock(func, try!("arm".to_raw()).as_ptr());
+                LLVMAddCase(switch, LLVMConstInt(LLVMInt32Type(), index as u64, 0), arm);
+                LLVMPositionBuilderAtEnd(builder, arm);
+                let offset = payload_offset(&enumeration, index);
+                for (i, (bind, ty)) in binds.iter().zip(payload).enumerate() {
+                    let field = LLVMBuildExtractValue(builder, value, offset + i as u32,
+                                                      try!(bind.to_raw()).as_ptr());
+                    let env_data =
+                        EnvData { llvm_value: field, direction: Direction::Direct, ty: ty.clone() };
+                    env.insert(bind.clone(), env_data);
+                }
+            }
+            Pattern::Wildcard => {
+                has_wildcard = true;
+                LLVMPositionBuilderAtEnd(builder, otherwise);
+            }
+        }
+        let result = body.build(module, func, entry, builder, env);
+        env.pop_scope();
+        values.push(value_or_undef(try!(result), body, tag));
+        ends.push(LLVMGetInsertBlock(builder));
+        LLVMBuildBr(builder, next);
+    }
+    // The checker makes sure the arms cover every variant.
+    if !has_wildcard {
+        LLVMPositionBuilderAtEnd(builder, otherwise);
+        LLVMBuildUnreachable(builder);
+    }
+    LLVMPositionBuilderAtEnd(builder, next);
+    let phi = LLVMBuildPhi(builder, LLVMTypeOf(values[0]), try!("match".to_raw()).as_ptr());
+    LLVMAddIncoming(phi, values.as_mut_ptr(), ends.as_mut_ptr(), values.len() as u32);
+    Ok(phi)
 }
 
 impl<'a> From<&'a Type> for LLVMTypeRef {
     fn from(ty: &Type) -> LLVMTypeRef {
         unsafe {
             match *ty {
-                Forbidden => unreachable!(),
+                // The checker resolves names and infers the types left out, so typed trees never
+                // contain them. Type parameters only appear in the types of generic externs,
+                // which are declared below.
+                Forbidden | Named(_) | Param(_) | Hole | Meta(_) => unreachable!(),
                 I32Ty => LLVMInt32Type(),
-                Enum(ref en) => if en.name == "Unit" { LLVMVoidType() } else { unreachable!() },
-                FunctionTy(ref args_types, ref ret_type) => {
+                BoolTy => LLVMInt1Type(),
+                I64Ty => LLVMInt64Type(),
+                U32Ty => LLVMInt32Type(),
+                I8Ty => LLVMInt8Type(),
+                I16Ty => LLVMInt16Type(),
+                F64Ty => LLVMDoubleType(),
+                // A code point.
+                CharTy => LLVMInt32Type(),
+                StrTy => LLVMPointerType(LLVMInt8Type(), 0),
+                // A `void*`.
+                Ptr => LLVMPointerType(LLVMInt8Type(), 0),
+                // Terms that never finish still give the I32 every loop gives.
+                Never => LLVMInt32Type(),
+                TupleTy(ref elems) => {
+                    let mut elems_llvm_types: Vec<LLVMTypeRef> =
+                        elems.iter().map(LLVMTypeRef::from).collect();
+                    LLVMStructType(elems_llvm_types.as_mut_ptr(), elems.len() as u32, 0)
+                }
+                ArrayTy(ref elem, len) => LLVMArrayType(LLVMTypeRef::from(&**elem), len),
+                Ref(ref referent) | RefMut(ref referent) =>
+                    LLVMPointerType(LLVMTypeRef::from(&**referent), 0),
+                Struct(ref structure) => {
+                    let mut fields_llvm_types: Vec<LLVMTypeRef> =
+                        structure.fields()
+                                 .iter()
+                                 .map(|&(_, ref ty)| LLVMTypeRef::from(ty))
+                                 .collect();
+                    LLVMStructType(fields_llvm_types.as_mut_ptr(),
+                                   fields_llvm_types.len() as u32,
+                                   0)
+                }
+                UnitTy => LLVMVoidType(),
+                Enum(ref enumeration) => {
+                    let mut fields_llvm_types = vec![LLVMInt32Type()];
+                    for &(_, ref payload) in enumeration.variants() {
+                        fields_llvm_types.extend(payload.iter().map(LLVMTypeRef::from));
+                    }
+                    LLVMStructType(fields_llvm_types.as_mut_ptr(),
+                                   fields_llvm_types.len() as u32,
+                                   0)
+                }
+                // A generic extern is declared without a signature, like in K&R C, and each
+                // call casts it to the signature it's instantiated with.
+                FunctionTy(ref params, _, _) if !params.is_empty() => {
+                    let args: &mut [LLVMTypeRef] = &mut [];
+                    LLVMFunctionType(LLVMVoidType(), args.as_mut_ptr(), 0, 0)
+                }
+                FunctionTy(_, ref args_types, ref ret_type) => {
                     let args_llvm_types: Vec<LLVMTypeRef> =
-                        args_types.iter().map(|ty| LLVMTypeRef::from(&*ty)).collect();
-                    LLVMFunctionType(LLVMTypeRef::from(&**ret_type),
+                        args_types.iter().map(slot_type).collect();
+                    LLVMFunctionType(slot_type(ret_type),
                                      (args_llvm_types.clone()).as_mut_ptr(),
                                      args_types.len() as u32,
                                      0
@@ -492,54 +1198,77 @@ impl<'a> From<&'a Type> for LLVMTypeRef {
     }
 }
 
+// `value`, the value of `term`, or an undef of type `ty` if `term` never finishes.
+fn value_or_undef(value: LLVMValueRef, term: &TaggedTerm<Type>, ty: &Type) -> LLVMValueRef {
+    if *term.get_tag() == Never {
+        unsafe { LLVMGetUndef(LLVMTypeRef::from(ty)) }
+    } else {
+        value
+    }
+}
+
+// The type of a value of type `ty` once it's stored or passed around. Functions themselves can't
+// be, so pointers to them are.
+fn slot_type(ty: &Type) -> LLVMTypeRef {
+    match *ty {
+        FunctionTy(..) => unsafe { LLVMPointerType(LLVMTypeRef::from(ty), 0) },
+        _ => LLVMTypeRef::from(ty),
+    }
+}
+
 impl Compile for TaggedBlock<Type> {
 
-    type Env = Box<Map<EnvData>>;
+    type Env = Symbols;
 
-    fn new_env() ->  Self::Env { Box::new(Map::new()) }
+    fn new_env() -> Self::Env { Symbols::new() }
 
     fn build(self: &Self,
              module: LLVMModuleRef,
              func: LLVMValueRef,
              entry: LLVMBasicBlockRef,
              builder: LLVMBuilderRef,
-             mut env: Self::Env) -> Result<LLVMValueRef, Vec<String>> {
+             env: &mut Self::Env) -> Result<LLVMValueRef, Vec<String>> {
         use type_check::TaggedStatement::*;
         use self::Direction::*;
         unsafe {
             for stmt in &self.stmts {
                 match *stmt {
                     TermSemicolon(_, ref term) => {
-                        try!(term.build(module, func, entry, builder, *env.clone()));
+                        try!(term.build(module, func, entry, builder, env));
                     }
-                    Let(_, ref lhs, ref rhs) => {
-                        let value = try!(rhs.build(module, func, entry, builder, *env.clone()));
-                        let env_data = EnvData { llvm_value: value, direction: Direct, ty: I32Ty };
+                    Let(_, ref lhs, ref ty, ref rhs) => {
+                        let ty = let_type(ty, rhs);
+                        let value = try!(rhs.build(module, func, entry, builder, env));
+                        let value = value_or_undef(value, rhs, &ty);
+                        let env_data = EnvData { llvm_value: value, direction: Direct, ty: ty };
                         env.insert(lhs.clone(), env_data);
                     }
-                    LetMut(_, ref lhs, ref rhs) => {
-                        let alloca =
-                            LLVMBuildAlloca(builder, LLVMInt32Type(), lhs.as_ptr() as *const i8);
-                        let built_rhs =
-                            try!(rhs.build(module, func, entry, builder, *env.clone()));
+                    LetMut(_, ref lhs, ref ty, ref rhs) => {
+                        let ty = let_type(ty, rhs);
+                        let alloca = LLVMBuildAlloca(
+                            builder, slot_type(&ty), try!(lhs.to_raw()).as_ptr()
+                        );
+                        let built_rhs = try!(rhs.build(module, func, entry, builder, env));
+                        let built_rhs = value_or_undef(built_rhs, rhs, &ty);
                         LLVMBuildStore(builder, built_rhs, alloca);
                         let env_data =
-                            EnvData { llvm_value: alloca, direction: Indirect, ty: I32Ty };
+                            EnvData { llvm_value: alloca, direction: Indirect, ty: ty };
                         env.insert(lhs.clone(), env_data);
                     }
                     Mutate(_, ref lhs, ref rhs) => {
                         let var_result = match env.get(lhs) {
-                            Some(var) => Ok(var.clone()),
+                            Some(var) => Ok((var.llvm_value, var.direction, var.ty.clone())),
                             None => Err(
                                 vec![format!("Variable {} isn't declared yet.", lhs)]
                             ),
                         };
-                        let built_rhs =
-                            try!(rhs.build(module, func, entry, builder, *env.clone()));
-                        let env_data = try!(var_result);
-                        match env_data.direction {
+                        // See `semantics::RHS_BEFORE_STORE`.
+                        let built_rhs = try!(rhs.build(module, func, entry, builder, env));
+                        let (llvm_value, direction, ty) = try!(var_result);
+                        let built_rhs = value_or_undef(built_rhs, rhs, &ty);
+                        match direction {
                             Indirect => {
-                                LLVMBuildStore(builder, built_rhs, env_data.llvm_value);
+                                LLVMBuildStore(builder, built_rhs, llvm_value);
                             }
                             Direct =>
                                 return Err(
@@ -552,14 +1281,20 @@ impl Compile for TaggedBlock<Type> {
                                 ),
                         }
                     }
+                    // The checker only lets externs through at the top level of main, so this
+                    // declares each of them once for the whole module.
                     Extern(_, ref name, ref ty) => {
                         let func_ty = LLVMTypeRef::from(ty);
-                        let func = LLVMAddFunction(
-                            module,
-                            // Actually unnessasary clone.
-                            try!(name.to_raw().map_err(|err: Vec<String>| vec![err[0].clone()])),
-                            func_ty
-                        );
+                        // Actually unnessasary clone.
+                        let raw_name =
+                            try!(name.to_raw().map_err(|err: Vec<String>| vec![err[0].clone()]));
+                        // Another program compiled into the same module may have declared it.
+                        let declared = LLVMGetNamedFunction(module, raw_name.as_ptr());
+                        let func = if declared.is_null() {
+                            LLVMAddFunction(module, raw_name.as_ptr(), func_ty)
+                        } else {
+                            declared
+                        };
                         let env_data = EnvData {
                             llvm_value: func,
                             direction: Direct,
@@ -567,10 +1302,28 @@ impl Compile for TaggedBlock<Type> {
                         };
                         env.insert(name.clone(), env_data);
                     }
+                    Declare(_, ref lhs, ref ty) => {
+                        // Definite assignment guarantees a store before the first load,
+                        // so the slot is left uninitialized.
+                        let alloca = LLVMBuildAlloca(
+                            builder, slot_type(ty), try!(lhs.to_raw()).as_ptr()
+                        );
+                        let env_data =
+                            EnvData { llvm_value: alloca, direction: Indirect, ty: ty.clone() };
+                        env.insert(lhs.clone(), env_data);
+                    }
+                    // Struct literals and variants carry their types in their tags.
+                    StructDecl(..) | EnumDecl(..) | TypeAlias(..) => {}
+                    // See `semantics::RHS_BEFORE_STORE`.
+                    MutateThrough(_, ref target, ref rhs) => {
+                        let pointer = try!(target.build(module, func, entry, builder, env));
+                        let built_rhs = try!(rhs.build(module, func, entry, builder, env));
+                        LLVMBuildStore(builder, built_rhs, pointer);
+                    }
                 }
             }
             if let Some(ref term) = *self.end {
-                term.build(module, func, entry, builder, *env)
+                term.build(module, func, entry, builder, env)
             } else {
                 use std::ptr::null;
                 use llvm_sys::LLVMValue;
@@ -582,17 +1335,75 @@ impl Compile for TaggedBlock<Type> {
 
 impl Compile for TaggedProgram<Type> {
 
-    type Env = Box<Map<EnvData>>;
+    type Env = Symbols;
 
-    fn new_env() -> Self::Env { Box::new(Map::new()) }
+    fn new_env() -> Self::Env { Symbols::new() }
 
     fn build(self: &Self,
              module: LLVMModuleRef,
              func: LLVMValueRef,
              entry: LLVMBasicBlockRef,
              builder: LLVMBuilderRef,
-             env: Self::Env) -> Result<LLVMValueRef, Vec<String>> {
-        self.main.build(module, func, entry, builder, env)
+             env: &mut Self::Env) -> Result<LLVMValueRef, Vec<String>> {
+        // Also refuses the operators that were only registered for checking.
+        if let Err(errors) = validate(self) {
+            return Err(errors.iter().map(|error| error.to_string()).collect());
+        }
+        let value = try!(self.main.build(module, func, entry, builder, env));
+        env.built_function();
+        // The exit code rule in `semantics`.
+        if self.main.tag == I32Ty {
+            Ok(value)
+        } else {
+            unsafe { Ok(LLVMConstInt(LLVMIntType(32), 0, 0)) }
+        }
+    }
+}
+
+// Compiles several programs into one module, each into an exported function of the given name
+// instead of `main`. An extern declared by more than one program is declared once, so the
+// programs must agree on its type.
+pub fn compile_many(units: &[(&str, &TaggedProgram<Type>)]) -> Result<LLVMModuleRef, Vec<String>> {
+    let mut errors = Vec::new();
+    let mut unit_names = HashSet::new();
+    let mut externs: Map<(&str, &Type)> = Map::new();
+    for &(unit_name, program) in units {
+        if !unit_names.insert(unit_name) {
+            errors.push(format!("There is more than one unit named {}.", unit_name));
+        }
+        for stmt in &program.main.stmts {
+            if let TaggedStatement::Extern(_, ref name, ref ty) = *stmt {
+                match externs.get(name) {
+                    Some(&(other_unit, other_ty)) if other_ty != ty => errors.push(
+                        format!("Extern {} is declared with type {} in unit {}, \
+                                 but with type {} in unit {}.",
+                                name, other_ty, other_unit, ty, unit_name)
+                    ),
+                    _ => {}
+                }
+                externs.entry(name.clone()).or_insert((unit_name, ty));
+            }
+        }
+    }
+    for &(unit_name, _) in units {
+        if externs.contains_key(unit_name) {
+            errors.push(format!("Unit {} has the same name as an extern.", unit_name));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    unsafe {
+        let module = LLVMModuleCreateWithName(try!("Main".to_raw()).as_ptr());
+        let builder = LLVMCreateBuilder();
+        for &(unit_name, program) in units {
+            let args: &mut [LLVMTypeRef] = &mut [];
+            let func_ty = LLVMFunctionType(LLVMInt32Type(), args.as_mut_ptr(), 0, 0);
+            let func = LLVMAddFunction(module, try!(unit_name.to_raw()).as_ptr(), func_ty);
+            try!(program.init_module(module, func, builder));
+        }
+        LLVMDisposeBuilder(builder);
+        Ok(module)
     }
 }
 
@@ -600,7 +1411,29 @@ pub unsafe fn emit_ir(module: LLVMModuleRef, output: String) {
     use llvm_sys::bit_writer::*;
     let mut bc = output.clone();
     bc.push_str(".bc");
-    LLVMWriteBitcodeToFile(module, bc.to_raw().unwrap());
+    LLVMWriteBitcodeToFile(module, bc.to_raw().unwrap().as_ptr());
+}
+
+// Compiles the bitcode written by `emit_ir` to an object file, returning its path.
+pub fn emit_object(output: String) -> Result<String, Vec<String>> {
+    let mut bc = output.clone();
+    bc.push_str(".bc");
+    let mut o = output.clone();
+    o.push_str(".o");
+    let llc_output = try!(
+        Command::new(LLVM_LLC_PATH)
+            .arg(bc)
+            .arg("--filetype=obj")
+            .arg("-o")
+            .arg(o.clone())
+            .output()
+            .map_err(|err| vec![format!("Failed to execute llc: {}", err)])
+    );
+    if llc_output.status.success() {
+        Ok(o)
+    } else {
+        Err(vec![String::from_utf8_lossy(&*llc_output.stderr).into_owned()])
+    }
 }
 
 pub unsafe fn emit_exe(output: String) {
@@ -0,0 +1,300 @@
+// Evaluating main ahead of time, for programs that are really constant computations.
+// Only a pure main is evaluated: calls are the only way a program has side effects, so a main
+// that makes one is left alone. Values are I32s and Bools computed like `semantics` says;
+// anything else, like an I64 or a division by zero, gives up.
+// Every term evaluated costs a step, and evaluation gives up once the budget runs out, which also
+// stops loops that never end.
+// The top-level lets of main can be evaluated the same way one at a time, for a host reading
+// constants out of a program without running it.
+
+use std::collections::BTreeMap;
+
+use codegen::Map;
+use semantics;
+use type_check::*;
+use visit::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Value {
+    I32(i32),
+    Bool(bool),
+    Unit,
+}
+
+// Why evaluation stopped short of a value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GiveUp {
+    Impure,
+    OutOfSteps,
+    // Values other than I32s and Bools, and operations without a value.
+    Unsupported,
+}
+
+// The number of steps evaluation takes before giving up, unless it's told otherwise.
+pub const DEFAULT_BUDGET: usize = 1_000_000;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Evaluation {
+    pub value: Value,
+    pub steps: usize,
+}
+
+struct Purity {
+    pure: bool,
+}
+
+impl Visitor<Type> for Purity {
+    fn visit_term(&mut self, term: &TaggedTerm<Type>) {
+        if let TaggedTerm::Call(..) = *term {
+            self.pure = false;
+        }
+        walk_term(self, term);
+    }
+}
+
+pub fn is_pure(program: &TaggedProgram<Type>) -> bool {
+    let mut purity = Purity { pure: true };
+    purity.visit_program(program);
+    purity.pure
+}
+
+// The values of variables, one map per scope with the innermost last.
+struct Scopes {
+    scopes: Vec<Map<Value>>,
+}
+
+impl Scopes {
+    fn get(&self, name: &str) -> Option<Value> {
+        self.scopes.iter().rev().filter_map(|scope| scope.get(name)).next().cloned()
+    }
+
+    fn bind(&mut self, name: &str, value: Value) {
+        self.scopes.last_mut().unwrap().insert(name.to_string(), value);
+    }
+
+    fn unbind(&mut self, name: &str) {
+        self.scopes.last_mut().unwrap().remove(name);
+    }
+
+    fn assign(&mut self, name: &str, value: Value) -> Result<(), GiveUp> {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(slot) = scope.get_mut(name) {
+                *slot = value;
+                return Ok(());
+            }
+        }
+        Err(GiveUp::Unsupported)
+    }
+}
+
+struct Evaluator {
+    steps: usize,
+    budget: usize,
+}
+
+impl Evaluator {
+    fn step(&mut self) -> Result<(), GiveUp> {
+        if self.steps == self.budget {
+            return Err(GiveUp::OutOfSteps);
+        }
+        self.steps += 1;
+        Ok(())
+    }
+
+    fn block(&mut self, block: &TaggedBlock<Type>, env: &mut Scopes) -> Result<Value, GiveUp> {
+        env.scopes.push(Map::new());
+        let value = self.block_in_scope(block, env);
+        env.scopes.pop();
+        value
+    }
+
+    fn block_in_scope(&mut self,
+                      block: &TaggedBlock<Type>,
+                      env: &mut Scopes) -> Result<Value, GiveUp> {
+        for stmt in &block.stmts {
+            try!(self.statement(stmt, env));
+        }
+        match *block.end {
+            Some(ref term) => self.term(term, env),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn statement(&mut self,
+                 stmt: &TaggedStatement<Type>,
+                 env: &mut Scopes) -> Result<(), GiveUp> {
+        use type_check::TaggedStatement::*;
+        try!(self.step());
+        match *stmt {
+            TermSemicolon(_, ref term) => {
+                try!(self.term(term, env));
+            }
+            Let(_, ref name, _, ref rhs) | LetMut(_, ref name, _, ref rhs) => {
+                let value = try!(self.term(rhs, env));
+                env.bind(name, value);
+            }
+            Mutate(_, ref name, ref rhs) => {
+                let value = try!(self.term(rhs, env));
+                try!(env.assign(name, value));
+            }
+            // Definite assignment makes sure the placeholder is never read.
+            Declare(_, ref name, _) => env.bind(name, Value::Unit),
+            Extern(..) | StructDecl(..) | EnumDecl(..) | TypeAlias(..) => {}
+            MutateThrough(..) => return Err(GiveUp::Unsupported),
+        }
+        Ok(())
+    }
+
+    fn term(&mut self, term: &TaggedTerm<Type>, env: &mut Scopes) -> Result<Value, GiveUp> {
+        use type_check::TaggedTerm::*;
+        try!(self.step());
+        match *term {
+            Literal(ref ty, i) if *ty == Type::I32Ty => Ok(Value::I32(i)),
+            BoolLiteral(_, b) => Ok(Value::Bool(b)),
+            Var(_, ref name) => env.get(name).ok_or(GiveUp::Unsupported),
+            Infix(_, ref left, op, ref right) => {
+                match (try!(self.term(left, env)), try!(self.term(right, env))) {
+                    (Value::Bool(left), Value::Bool(right)) =>
+                        semantics::logic(op, left, right).map(Value::Bool)
+                                                         .ok_or(GiveUp::Unsupported),
+                    (Value::I32(left), Value::I32(right)) if op.is_comparison() =>
+                        semantics::compare(op, left, right).map(Value::Bool)
+                                                           .ok_or(GiveUp::Unsupported),
+                    (Value::I32(left), Value::I32(right)) =>
+                        semantics::apply(op, left, right).map(Value::I32)
+                                                         .ok_or(GiveUp::Unsupported),
+                    _ => Err(GiveUp::Unsupported),
+                }
+            }
+            Scope(_, ref block) => self.block(block, env),
+            If(_, ref cond, ref if_true, ref if_false) => {
+                if try!(self.cond(cond, env)) {
+                    self.term(if_true, env)
+                } else if let Some(ref if_false) = *if_false {
+                    self.term(if_false, env)
+                } else {
+                    Ok(Value::Unit)
+                }
+            }
+            // Codegen gives a loop the value 0 whatever its tag; `fold_main` only keeps a Unit
+            // main's.
+            While(_, ref cond, ref block) => {
+                while try!(self.cond(cond, env)) {
+                    try!(self.block(block, env));
+                }
+                Ok(Value::Unit)
+            }
+            // A statement used as a term binds in a scope of its own, as it's checked in one.
+            Stmt(ref stmt) => {
+                env.scopes.push(Map::new());
+                let done = self.statement(stmt, env);
+                env.scopes.pop();
+                done.map(|_| Value::Unit)
+            }
+            Ascribe(_, ref term, _) => self.term(term, env),
+            // Wraps around like `semantics::apply`; only `-2147483648` needs a suffixed literal.
+            Neg(ref ty, ref term) if *ty == Type::I32Ty => match **term {
+                SuffixedLiteral(_, i, _) => Ok(Value::I32(-i as i32)),
+                _ => match try!(self.term(term, env)) {
+                    Value::I32(i) => Ok(Value::I32(i.wrapping_neg())),
+                    _ => Err(GiveUp::Unsupported),
+                },
+            },
+            Not(_, ref term) => match try!(self.term(term, env)) {
+                Value::Bool(b) => Ok(Value::Bool(!b)),
+                _ => Err(GiveUp::Unsupported),
+            },
+            Call(..) => Err(GiveUp::Impure),
+            Literal(..) | SuffixedLiteral(..) | Cast(..) | FloatLiteral(..) | CharLit(..) |
+            StrLit(..) | Tuple(..) | Project(..) | ArrayLit(..) | Index(..) | StructLit(..) |
+            Field(..) | Variant(..) | Match(..) | Borrow(..) | Deref(..) | BorrowMut(..) |
+            Null(..) | PtrEq(..) | Lambda(..) | Apply(..) | MethodCall(..) | Neg(..) =>
+                Err(GiveUp::Unsupported),
+        }
+    }
+
+    // Legacy mode lets I32 conditions through, which are true when they aren't zero.
+    fn cond(&mut self, cond: &TaggedTerm<Type>, env: &mut Scopes) -> Result<bool, GiveUp> {
+        match try!(self.term(cond, env)) {
+            Value::Bool(b) => Ok(b),
+            Value::I32(i) => Ok(i != 0),
+            Value::Unit => Err(GiveUp::Unsupported),
+        }
+    }
+}
+
+// The value of main, if it's pure and can be computed within `budget` steps.
+pub fn evaluate_main(program: &TaggedProgram<Type>,
+                     budget: usize) -> Result<Evaluation, GiveUp> {
+    if !is_pure(program) {
+        return Err(GiveUp::Impure);
+    }
+    let mut evaluator = Evaluator { steps: 0, budget: budget };
+    let mut env = Scopes { scopes: Vec::new() };
+    let value = try!(evaluator.block(&program.main, &mut env));
+    Ok(Evaluation { value: value, steps: evaluator.steps })
+}
+
+// `program` with main replaced by its value, and the evaluation that computed it.
+pub fn fold_main(program: &TaggedProgram<Type>,
+                 budget: usize) -> Option<(TaggedProgram<Type>, Evaluation)> {
+    let evaluation = match evaluate_main(program, budget) {
+        Ok(evaluation) => evaluation,
+        Err(_) => return None,
+    };
+    let end = match (evaluation.value, &program.main.tag) {
+        (Value::I32(i), &Type::I32Ty) => Some(TaggedTerm::Literal(Type::I32Ty, i)),
+        (Value::Unit, &Type::UnitTy) => None,
+        _ => return None,
+    };
+    let main = TaggedBlock {
+        tag: program.main.tag.clone(),
+        stmts: Vec::new(),
+        end: Box::new(end),
+    };
+    Some((TaggedProgram { tag: program.tag.clone(), main: main }, evaluation))
+}
+
+// The value of a constant binding, with the type it was checked at.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConstValue {
+    pub value: Value,
+    pub ty: Type,
+}
+
+// The top-level lets of main whose right-hand sides evaluate within `budget` steps each, by
+// name. A later binding of a name replaces an earlier one, and leaves it out if it isn't a
+// constant itself.
+// Only immutable lets can be constants, so those are the only variables a right-hand side can
+// read. A call it would make gives up like anything else that isn't a constant.
+pub fn const_bindings(program: &TaggedProgram<Type>,
+                      budget: usize) -> BTreeMap<String, ConstValue> {
+    use type_check::TaggedStatement::*;
+    let mut bindings = BTreeMap::new();
+    let mut env = Scopes { scopes: vec![Map::new()] };
+    for stmt in &program.main.stmts {
+        match *stmt {
+            Let(_, ref name, _, ref rhs) => {
+                let mut evaluator = Evaluator { steps: 0, budget: budget };
+                match evaluator.term(rhs, &mut env) {
+                    Ok(value) => {
+                        env.bind(name, value);
+                        bindings.insert(name.clone(),
+                                        ConstValue { value: value, ty: rhs.tag().clone() });
+                    }
+                    Err(_) => {
+                        env.unbind(name);
+                        bindings.remove(name);
+                    }
+                }
+            }
+            LetMut(_, ref name, _, _) | Declare(_, ref name, _) | Extern(_, ref name, _) => {
+                env.unbind(name);
+                bindings.remove(name);
+            }
+            TermSemicolon(..) | Mutate(..) | StructDecl(..) | EnumDecl(..) | TypeAlias(..) |
+            MutateThrough(..) => {}
+        }
+    }
+    bindings
+}
@@ -0,0 +1,130 @@
+use semantics;
+use type_check::*;
+use visit::*;
+
+// How the running time of a program grows, judging by its loops alone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CostBucket {
+    StraightLine,
+    SingleLoop,
+    NestedLoops,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CostReport {
+    pub max_loop_depth: usize,
+    pub loops: usize,
+    pub call_sites: usize,
+    pub bucket: CostBucket,
+}
+
+// `None` leaves the corresponding measure unlimited.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CostLimits {
+    pub max_loop_depth: Option<usize>,
+    pub max_loops: Option<usize>,
+    pub max_call_sites: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Violation {
+    LoopDepth { limit: usize, actual: usize },
+    Loops { limit: usize, actual: usize },
+    CallSites { limit: usize, actual: usize },
+}
+
+// The value of a term made only of literals and arithmetic, if it can't fail.
+fn constant_value<Tag>(term: &TaggedTerm<Tag>) -> Option<i32> {
+    use type_check::TaggedTerm::*;
+    match *term {
+        Literal(_, i) => Some(i),
+        Infix(_, ref left, op, ref right) => {
+            let (left, right) = match (constant_value(left), constant_value(right)) {
+                (Some(left), Some(right)) => (left, right),
+                _ => return None,
+            };
+            semantics::apply(op, left, right)
+        }
+        _ => None,
+    }
+}
+
+fn is_false<Tag>(term: &TaggedTerm<Tag>) -> bool {
+    match *term {
+        TaggedTerm::BoolLiteral(_, false) => true,
+        _ => false,
+    }
+}
+
+struct CostCounter {
+    report: CostReport,
+    depth: usize,
+}
+
+impl Visitor<Type> for CostCounter {
+    fn visit_term(&mut self, term: &TaggedTerm<Type>) {
+        use type_check::TaggedTerm::*;
+        match *term {
+            // A loop that never runs costs nothing, and neither does anything inside it.
+            While(_, ref cond, _) if is_false(cond) => {}
+            While(..) => {
+                self.report.loops += 1;
+                self.depth += 1;
+                if self.depth > self.report.max_loop_depth {
+                    self.report.max_loop_depth = self.depth;
+                }
+                walk_term(self, term);
+                self.depth -= 1;
+            }
+            Call(..) => {
+                self.report.call_sites += 1;
+                walk_term(self, term);
+            }
+            _ => walk_term(self, term),
+        }
+    }
+}
+
+// Estimates how expensive a program is to run, e.g. to refuse scripts before running them.
+pub fn estimate_cost(program: &TaggedProgram<Type>) -> CostReport {
+    let mut counter = CostCounter {
+        report: CostReport {
+            max_loop_depth: 0,
+            loops: 0,
+            call_sites: 0,
+            bucket: CostBucket::StraightLine,
+        },
+        depth: 0,
+    };
+    counter.visit_program(program);
+    counter.report.bucket = match counter.report.max_loop_depth {
+        0 => CostBucket::StraightLine,
+        1 => CostBucket::SingleLoop,
+        _ => CostBucket::NestedLoops,
+    };
+    counter.report
+}
+
+pub fn enforce(limits: &CostLimits, report: &CostReport) -> Result<(), Vec<Violation>> {
+    let mut violations = Vec::new();
+    if let Some(limit) = limits.max_loop_depth {
+        if report.max_loop_depth > limit {
+            violations.push(Violation::LoopDepth { limit: limit, actual: report.max_loop_depth });
+        }
+    }
+    if let Some(limit) = limits.max_loops {
+        if report.loops > limit {
+            violations.push(Violation::Loops { limit: limit, actual: report.loops });
+        }
+    }
+    if let Some(limit) = limits.max_call_sites {
+        if report.call_sites > limit {
+            violations.push(Violation::CallSites { limit: limit, actual: report.call_sites });
+        }
+    }
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
@@ -0,0 +1,242 @@
+// Definite assignment for variables declared without an initializer (`let mut x: I32;`).
+// A read is only allowed when every path leading to it assigns the variable first.
+// The body of a while loop might not run at all, so assignments inside it don't count
+// afterwards.
+
+use ast::{Pattern, Position};
+use codegen::Map;
+use type_check::*;
+
+#[derive(Clone, Debug)]
+struct Binding {
+    // Tells apart bindings that shadow each other under the same name.
+    id: usize,
+    assigned: bool,
+    declared_at: Option<Position>,
+}
+
+struct Analysis {
+    next_id: usize,
+    errors: Vec<String>,
+}
+
+fn show(pos: &Position) -> String {
+    format!("line {}, column {}", pos.start_pos.0, pos.start_pos.1)
+}
+
+impl Analysis {
+    fn bind(&mut self, env: &mut Map<Binding>, name: &str, declared_at: Option<Position>) {
+        let binding = Binding {
+            id: self.next_id,
+            assigned: declared_at.is_none(),
+            declared_at: declared_at,
+        };
+        self.next_id += 1;
+        env.insert(name.to_string(), binding);
+    }
+
+    fn read(&mut self, env: &Map<Binding>, name: &str, pos: &Position) {
+        if let Some(binding) = env.get(name) {
+            if !binding.assigned {
+                let declared_at = binding.declared_at.as_ref().unwrap();
+                self.errors.push(
+                    format!("Variable {} is read at {} before it is definitely \
+                             assigned; it is declared without an initializer at {}.",
+                            name, show(pos), show(declared_at))
+                );
+            }
+        }
+    }
+
+    fn block(&mut self, block: &TaggedBlock<Position>, env: &mut Map<Binding>) {
+        for stmt in &block.stmts {
+            self.statement(stmt, env);
+        }
+        if let Some(ref term) = *block.end {
+            self.term(term, env);
+        }
+    }
+
+    // Analyzes a nested block, and only lets assignments to outer bindings escape it.
+    fn scope(&mut self, block: &TaggedBlock<Position>, env: &mut Map<Binding>) {
+        let mut inner = env.clone();
+        self.block(block, &mut inner);
+        merge_back(env, &inner);
+    }
+
+    fn statement(&mut self, stmt: &TaggedStatement<Position>, env: &mut Map<Binding>) {
+        use type_check::TaggedStatement::*;
+        match *stmt {
+            TermSemicolon(_, ref term) => self.term(term, env),
+            Let(_, ref name, _, ref rhs) | LetMut(_, ref name, _, ref rhs) => {
+                self.term(rhs, env);
+                self.bind(env, name, None);
+            }
+            Mutate(_, ref name, ref rhs) => {
+                self.term(rhs, env);
+                if let Some(binding) = env.get_mut(name) {
+                    binding.assigned = true;
+                }
+            }
+            Extern(_, ref name, _) => self.bind(env, name, None),
+            Declare(ref pos, ref name, _) => self.bind(env, name, Some(pos.clone())),
+            StructDecl(..) | EnumDecl(..) | TypeAlias(..) => {}
+            MutateThrough(_, ref target, ref rhs) => {
+                self.term(target, env);
+                self.term(rhs, env);
+            }
+        }
+    }
+
+    fn term(&mut self, term: &TaggedTerm<Position>, env: &mut Map<Binding>) {
+        use type_check::TaggedTerm::*;
+        match *term {
+            Literal(..) | BoolLiteral(..) | SuffixedLiteral(..) | FloatLiteral(..) |
+            CharLit(..) | StrLit(..) | Null(..) => {}
+            Var(ref pos, ref name) => self.read(env, name, pos),
+            Infix(_, ref left, _, ref right) => {
+                self.term(left, env);
+                self.term(right, env);
+            }
+            // Calling a variable of function type reads it too.
+            Call(_, ref func, ref args) => {
+                self.read(env, &func.name, &func.tag);
+                for arg in args {
+                    self.term(arg, env);
+                }
+            }
+            Scope(_, ref block) => self.scope(block, env),
+            If(_, ref cond, ref if_true, ref if_false) => {
+                self.term(cond, env);
+                let mut then_env = env.clone();
+                self.term(if_true, &mut then_env);
+                let mut else_env = env.clone();
+                if let Some(ref if_false) = *if_false {
+                    self.term(if_false, &mut else_env);
+                }
+                // Only what both branches assign is assigned afterwards.
+                for (name, binding) in env.iter_mut() {
+                    if let (Some(then_binding), Some(else_binding)) =
+                        (then_env.get(name), else_env.get(name)) {
+                        if then_binding.id == binding.id && else_binding.id == binding.id {
+                            binding.assigned = then_binding.assigned && else_binding.assigned;
+                        }
+                    }
+                }
+            }
+            While(_, ref cond, ref block) => {
+                self.term(cond, env);
+                let mut body_env = env.clone();
+                self.block(block, &mut body_env);
+            }
+            Stmt(ref stmt) => self.statement(stmt, env),
+            Cast(_, ref term, _) | Ascribe(_, ref term, _) | Neg(_, ref term) |
+            Not(_, ref term) => self.term(term, env),
+            Tuple(_, ref elems) => {
+                for elem in elems {
+                    self.term(elem, env);
+                }
+            }
+            Project(_, ref term, _) => self.term(term, env),
+            ArrayLit(_, ref elems) => {
+                for elem in elems {
+                    self.term(elem, env);
+                }
+            }
+            Index(_, ref array, ref index) => {
+                self.term(array, env);
+                self.term(index, env);
+            }
+            PtrEq(_, ref left, ref right) => {
+                self.term(left, env);
+                self.term(right, env);
+            }
+            StructLit(_, _, ref inits) => {
+                for &(_, ref term) in inits {
+                    self.term(term, env);
+                }
+            }
+            // A reference could be read through, so borrowing counts as a read.
+            Field(_, ref term, _) | Borrow(_, ref term) | Deref(_, ref term) |
+            BorrowMut(_, ref term) =>
+                self.term(term, env),
+            Variant(_, _, _, ref args) => {
+                for arg in args {
+                    self.term(arg, env);
+                }
+            }
+            Match(_, ref scrutinee, ref arms) => {
+                self.term(scrutinee, env);
+                let mut arm_envs = Vec::new();
+                for &(ref pattern, ref body) in arms {
+                    let mut arm_env = env.clone();
+                    if let Pattern::Constructor(_, _, ref binds) = *pattern {
+                        for bind in binds {
+                            self.bind(&mut arm_env, bind, None);
+                        }
+                    }
+                    self.term(body, &mut arm_env);
+                    arm_envs.push(arm_env);
+                }
+                // Like an if, only what every arm assigns is assigned afterwards.
+                for (name, binding) in env.iter_mut() {
+                    let arm_bindings: Vec<&Binding> =
+                        arm_envs.iter()
+                                .filter_map(|arm_env| arm_env.get(name))
+                                .filter(|arm_binding| arm_binding.id == binding.id)
+                                .collect();
+                    if arm_bindings.len() == arm_envs.len() {
+                        binding.assigned = arm_bindings.iter().all(|arm_binding| {
+                            arm_binding.assigned
+                        });
+                    }
+                }
+            }
+            // The body of a lambda doesn't run where it's written, so nothing it assigns counts
+            // outside of it. It can't use the variables outside anyway.
+            Lambda(_, ref params, ref body) => {
+                let mut body_env = env.clone();
+                for &(ref name, _) in params {
+                    self.bind(&mut body_env, name, None);
+                }
+                self.block(body, &mut body_env);
+            }
+            Apply(_, ref callee, ref args) => {
+                self.term(callee, env);
+                for arg in args {
+                    self.term(arg, env);
+                }
+            }
+            // The receiver is evaluated first, as the first argument.
+            MethodCall(_, ref receiver, ref method, ref args) => {
+                self.read(env, &method.name, &method.tag);
+                self.term(receiver, env);
+                for arg in args {
+                    self.term(arg, env);
+                }
+            }
+        }
+    }
+}
+
+fn merge_back(outer: &mut Map<Binding>, inner: &Map<Binding>) {
+    for (name, binding) in outer.iter_mut() {
+        if let Some(inner_binding) = inner.get(name) {
+            if inner_binding.id == binding.id {
+                binding.assigned = inner_binding.assigned;
+            }
+        }
+    }
+}
+
+impl TaggedBlock<Position> {
+    pub fn check_definite_assignment(&self) -> Result<(), Vec<String>> {
+        let mut analysis = Analysis { next_id: 0, errors: Vec::new() };
+        analysis.block(self, &mut Map::new());
+        if analysis.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(analysis.errors)
+        }
+    }
+}
@@ -0,0 +1,356 @@
+// Everything checking a program finds, errors and warnings alike, from one pass, so that fixing
+// the errors doesn't uncover warnings that were hidden behind them.
+// The statements of main are checked one at a time. A statement that fails is left out of the
+// checked program and the names it binds are poisoned: a later statement reading one of them is
+// left out as well, without errors of its own, since those would only repeat the first. Lints
+// and the other checks then run over whatever did check.
+// Diagnostics are also deduplicated where they're collected, so that a check running again over
+// a node it already reported on doesn't report it twice.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use ast::Position;
+use codegen::Map;
+use const_eval::{ConstValue, DEFAULT_BUDGET, const_bindings};
+use externs::check_extern_placement;
+use infer::{InferEnv, has_holes};
+use limits::check_size_limits;
+use lint::{Lint, LintLevel, run_lints_with};
+use safe::check_safe_subset;
+use strictness::{Strictness, StrictRule, LegacyEnv, gate};
+use type_check::*;
+use used::{UsageEnv, UsedBindings};
+use visit::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    // A panic inside the compiler, caught where the API was called. Bugs are errors too.
+    Bug,
+}
+
+// The code of every bug.
+pub const BUG_CODE: &'static str = "E9999";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // Only bugs have a code of their own; the codes of strict mode's errors are in their
+    // messages.
+    pub code: Option<&'static str>,
+    pub message: String,
+    // The statement or term it's about; `None` for checks over the whole program.
+    pub position: Option<Position>,
+}
+
+impl Diagnostic {
+    // `panic` is the message of the panic and where it happened.
+    pub fn bug(panic: &str) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Bug,
+            code: Some(BUG_CODE),
+            message: format!("error[{}]: internal compiler error: {}. This is a bug in the \
+                              compiler, please report it together with the program.",
+                             BUG_CODE, panic),
+            position: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CheckResult {
+    // The statements that checked; the program can only be compiled if there are no errors.
+    pub program: TaggedProgram<Type>,
+    // Errors and warnings in the order they were found.
+    pub diagnostics: Vec<Diagnostic>,
+    // The constant top-level lets of a program without errors.
+    const_bindings: BTreeMap<String, ConstValue>,
+    // The bindings main uses, as the checker looked them up.
+    used_bindings: UsedBindings,
+}
+
+impl CheckResult {
+    // The result of a check that stopped before anything checked.
+    pub fn from_errors(errors: Vec<String>) -> CheckResult {
+        let empty = TaggedBlock { tag: Type::UnitTy, stmts: Vec::new(), end: Box::new(None) };
+        let mut result = CheckResult {
+            program: TaggedProgram { tag: Type::Forbidden, main: empty },
+            diagnostics: Vec::new(),
+            const_bindings: BTreeMap::new(),
+            used_bindings: UsedBindings::default(),
+        };
+        result.error(errors);
+        result
+    }
+
+    // The result of a check that panicked.
+    pub fn from_bug(bug: Diagnostic) -> CheckResult {
+        let mut result = CheckResult::from_errors(Vec::new());
+        result.diagnostics.push(bug);
+        result
+    }
+
+    pub fn errors(&self) -> Vec<&str> {
+        let mut errors = self.messages(Severity::Error);
+        errors.extend(self.messages(Severity::Bug));
+        errors
+    }
+
+    pub fn warnings(&self) -> Vec<&str> {
+        self.messages(Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors().is_empty()
+    }
+
+    // The top-level lets of main whose values are known without running it, by name; empty if
+    // there are errors.
+    pub fn const_bindings(&self) -> &BTreeMap<String, ConstValue> {
+        &self.const_bindings
+    }
+
+    // Each name main looks up, with how often and where first; the host's names are the ones
+    // registered in the env it was checked in.
+    pub fn used_bindings(&self) -> &UsedBindings {
+        &self.used_bindings
+    }
+
+    fn messages(&self, severity: Severity) -> Vec<&str> {
+        self.diagnostics.iter()
+                        .filter(|diagnostic| diagnostic.severity == severity)
+                        .map(|diagnostic| &diagnostic.message[..])
+                        .collect()
+    }
+
+    fn error(&mut self, errors: Vec<String>) {
+        self.add(Severity::Error, None, errors);
+    }
+
+    fn warn(&mut self, warnings: Vec<String>) {
+        self.add(Severity::Warning, None, warnings);
+    }
+
+    // Adds what one check found. A check can report the same message more than once for
+    // different parts of a node, like two arguments of a call, so identical diagnostics are only
+    // dropped if there are already as many of them as the check found.
+    fn add(&mut self, severity: Severity, position: Option<Position>, messages: Vec<String>) {
+        let mut found: HashMap<Diagnostic, usize> = HashMap::new();
+        for message in messages {
+            let diagnostic = Diagnostic {
+                severity: severity,
+                code: None,
+                message: message,
+                position: position.clone(),
+            };
+            let count = found.entry(diagnostic.clone()).or_insert(0);
+            *count += 1;
+            let reported = self.diagnostics.iter().filter(|reported| **reported == diagnostic)
+                                                  .count();
+            if reported < *count {
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+}
+
+// The names a statement or term reads, calls or assigns, in nested scopes too.
+struct Reads {
+    names: HashSet<String>,
+}
+
+impl Visitor<Position> for Reads {
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Position>) {
+        if let TaggedStatement::Mutate(_, ref name, _) = *stmt {
+            self.names.insert(name.clone());
+        }
+        walk_statement(self, stmt);
+    }
+
+    fn visit_term(&mut self, term: &TaggedTerm<Position>) {
+        match *term {
+            TaggedTerm::Var(_, ref name) => {
+                self.names.insert(name.clone());
+            }
+            TaggedTerm::Call(_, ref func, _) | TaggedTerm::MethodCall(_, _, ref func, _) => {
+                self.names.insert(func.name.clone());
+            }
+            TaggedTerm::StructLit(_, ref name, _) => {
+                self.names.insert(name.clone());
+            }
+            _ => {}
+        }
+        walk_term(self, term);
+    }
+}
+
+fn bound_name(stmt: &TaggedStatement<Position>) -> Option<&String> {
+    use type_check::TaggedStatement::*;
+    match *stmt {
+        Let(_, ref name, _, _) | LetMut(_, ref name, _, _) | Extern(_, ref name, _) |
+        Declare(_, ref name, _) | StructDecl(_, ref name, _) | EnumDecl(_, ref name, _) |
+        TypeAlias(_, ref name, _) => Some(name),
+        TermSemicolon(..) | Mutate(..) | MutateThrough(..) => None,
+    }
+}
+
+struct Recovery {
+    result: CheckResult,
+    poisoned: HashSet<String>,
+    // Names read by the statements that were left out.
+    unchecked_reads: HashSet<String>,
+}
+
+impl Recovery {
+    // Runs `check` on the statement or term at `position` that reads `reads`, unless it reads a
+    // poisoned name.
+    fn check<T, F>(&mut self, position: &Position, reads: Reads, check: F) -> Option<T>
+        where F: FnOnce() -> Result<T, Vec<String>>
+    {
+        if reads.names.iter().any(|name| self.poisoned.contains(name)) {
+            self.unchecked_reads.extend(reads.names);
+            return None;
+        }
+        match check() {
+            Ok(typed) => Some(typed),
+            Err(errors) => {
+                self.result.add(Severity::Error, Some(position.clone()), errors);
+                self.unchecked_reads.extend(reads.names);
+                None
+            }
+        }
+    }
+
+    fn statement<E: TypeEnv>(&mut self,
+                             stmt: &TaggedStatement<Position>,
+                             env: &mut E) -> Option<TaggedStatement<Type>> {
+        let mut reads = Reads { names: HashSet::new() };
+        reads.visit_statement(stmt);
+        let checked = self.check(stmt.tag(), reads, || stmt.type_check(env));
+        if let Some(name) = bound_name(stmt) {
+            if checked.is_some() {
+                self.poisoned.remove(name);
+            } else {
+                self.poisoned.insert(name.clone());
+            }
+        }
+        checked
+    }
+
+    fn end<E: TypeEnv>(&mut self, term: &TaggedTerm<Position>, env: &mut E)
+                       -> Option<TaggedTerm<Type>> {
+        let mut reads = Reads { names: HashSet::new() };
+        reads.visit_term(term);
+        self.check(term.tag(), reads, || term.type_check(env))
+    }
+}
+
+// Like `type_check`, a program that leaves types out is checked with inference. Its metavariables
+// are only solved if everything else checks, since what failed may have been what solves them.
+fn check_main<E: TypeEnv>(program: &TaggedProgram<Position>,
+                          env: &mut E,
+                          options: &CheckOptions,
+                          recovery: &mut Recovery) {
+    add_prelude(env);
+    if !has_holes(program) {
+        return check_main_in(program, env, options, recovery);
+    }
+    let mut infer = InferEnv::new(env.clone());
+    check_main_in(program, &mut infer, options, recovery);
+    if !recovery.result.has_errors() {
+        let main = recovery.result.program.main.clone();
+        match infer.solve_block(main) {
+            Ok(main) => recovery.result.program.main = main,
+            Err(errors) => recovery.result.error(errors),
+        }
+    }
+    *env = infer.into_inner();
+}
+
+fn check_main_in<E: TypeEnv>(program: &TaggedProgram<Position>,
+                             env: &mut E,
+                             options: &CheckOptions,
+                             recovery: &mut Recovery) {
+    if let Err(errors) = check_safe_subset(program, options) {
+        recovery.result.error(errors);
+    }
+    if let Err(errors) = check_extern_placement(program) {
+        if let Err(errors) = gate(env, StrictRule::ExternPlacement, errors) {
+            recovery.result.error(errors);
+        }
+    }
+    let mut stmts = Vec::new();
+    for stmt in &program.main.stmts {
+        if let Some(typed) = recovery.statement(stmt, env) {
+            stmts.push(typed);
+        }
+    }
+    let end = match *program.main.end {
+        Some(ref term) => recovery.end(term, env),
+        None => None,
+    };
+    if let Err(errors) = program.main.check_definite_assignment() {
+        recovery.result.error(errors);
+    }
+    let failed_end = program.main.end.is_some() && end.is_none();
+    let ty = match end {
+        Some(ref term) => *term.get_tag(),
+        None => Type::UnitTy,
+    };
+    // The rest is about the type of main, which isn't known if its end failed.
+    if !failed_end {
+        if let Err(errors) = check_main_type(env, &ty) {
+            recovery.result.error(errors);
+        }
+        if let Err(errors) = check_expected_type(options, &ty) {
+            recovery.result.error(errors);
+        }
+    }
+    let main = TaggedBlock { tag: ty, stmts: stmts, end: Box::new(end) };
+    recovery.result.program = TaggedProgram { tag: Type::Forbidden, main: main };
+}
+
+// Checks `program` like `type_check_with` and runs `lints` over it, collecting every diagnostic.
+// Only the size limits stop the check early, so that huge inputs are still refused cheaply.
+pub fn check_all<E: TypeEnv>(program: &TaggedProgram<Position>,
+                             env: &mut E,
+                             options: &CheckOptions,
+                             lints: &[&Lint],
+                             levels: &Map<LintLevel>) -> CheckResult {
+    if let Err(errors) = check_size_limits(program, options) {
+        return CheckResult::from_errors(errors);
+    }
+    let mut recovery = Recovery {
+        result: CheckResult::from_errors(Vec::new()),
+        poisoned: HashSet::new(),
+        unchecked_reads: HashSet::new(),
+    };
+    add_operators(env, &options.check_only_operators);
+    let used_bindings = match options.strictness {
+        Strictness::Strict => {
+            let mut usage = UsageEnv::new(env.clone());
+            check_main(program, &mut usage, options, &mut recovery);
+            let used_bindings = usage.used_bindings();
+            *env = usage.into_inner();
+            used_bindings
+        }
+        Strictness::Legacy => {
+            let mut legacy = LegacyEnv::new(UsageEnv::new(env.clone()));
+            check_main(program, &mut legacy, options, &mut recovery);
+            recovery.result.warn(legacy.warnings());
+            legacy.into_inner().used_bindings()
+        }
+    };
+    let unchecked_reads = recovery.unchecked_reads;
+    let mut result = recovery.result;
+    result.used_bindings = used_bindings;
+    match run_lints_with(&result.program, unchecked_reads, lints, levels) {
+        Ok(warnings) => result.warn(warnings),
+        Err(errors) => result.error(errors),
+    }
+    if !result.has_errors() {
+        result.const_bindings = const_bindings(&result.program, DEFAULT_BUDGET);
+    }
+    result
+}
@@ -0,0 +1,164 @@
+// Recording every change the checker makes to its env, so that checking can be replayed step by
+// step, e.g. in a UI that scrubs through it.
+// The checker copies its env when it enters a nested scope and drops the copy when it leaves, so
+// copies and drops of a `RecordingEnv` are recorded as scope pushes and pops.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ast::Operator;
+use codegen::Map;
+use type_check::*;
+use validate::show_tag;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EnvEvent {
+    ScopePush,
+    ScopePop,
+    Insert(String, Binding),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CheckTrace {
+    // The index of an event is its step.
+    pub events: Vec<EnvEvent>,
+    pub max_steps: usize,
+    // Whether events were dropped because there were more than `max_steps` of them.
+    pub truncated: bool,
+}
+
+impl CheckTrace {
+    fn record(&mut self, event: EnvEvent) {
+        if self.events.len() < self.max_steps {
+            self.events.push(event);
+        } else {
+            self.truncated = true;
+        }
+    }
+
+    // The bindings in scope right before the event at `step`.
+    pub fn env_at(&self, step: usize) -> Map<Binding> {
+        let mut scopes = vec![Map::new()];
+        for event in self.events.iter().take(step) {
+            match *event {
+                EnvEvent::ScopePush => {
+                    let inner = scopes.last().unwrap().clone();
+                    scopes.push(inner);
+                }
+                EnvEvent::ScopePop => {
+                    scopes.pop();
+                }
+                EnvEvent::Insert(ref name, ref binding) => {
+                    scopes.last_mut().unwrap().insert(name.clone(), binding.clone());
+                }
+            }
+        }
+        scopes.pop().unwrap()
+    }
+
+    // `dump_env` of the bindings in scope right before the event at `step`.
+    pub fn dump_at(&self, step: usize) -> String {
+        dump_env(&self.env_at(step))
+    }
+
+    // The step at which `name` was first bound.
+    pub fn step_introducing(&self, name: &str) -> Option<usize> {
+        self.events.iter().position(|event| match *event {
+            EnvEvent::Insert(ref inserted, _) => inserted == name,
+            _ => false,
+        })
+    }
+}
+
+pub struct RecordingEnv {
+    bindings: Env,
+    trace: Rc<RefCell<CheckTrace>>,
+    // The env the checking starts from isn't a scope of its own.
+    is_root: bool,
+}
+
+impl RecordingEnv {
+    // Records at most `max_steps` events.
+    pub fn new(max_steps: usize) -> RecordingEnv {
+        let trace = CheckTrace { events: Vec::new(), max_steps: max_steps, truncated: false };
+        RecordingEnv { bindings: Env::new(), trace: Rc::new(RefCell::new(trace)), is_root: true }
+    }
+
+    pub fn trace(&self) -> CheckTrace {
+        self.trace.borrow().clone()
+    }
+}
+
+impl Clone for RecordingEnv {
+    fn clone(&self) -> RecordingEnv {
+        self.trace.borrow_mut().record(EnvEvent::ScopePush);
+        RecordingEnv {
+            bindings: self.bindings.clone(),
+            trace: self.trace.clone(),
+            is_root: false,
+        }
+    }
+}
+
+impl Drop for RecordingEnv {
+    fn drop(&mut self) {
+        if !self.is_root {
+            self.trace.borrow_mut().record(EnvEvent::ScopePop);
+        }
+    }
+}
+
+impl TypeEnv for RecordingEnv {
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.bindings.binding(name)
+    }
+
+    fn bind(&mut self, name: String, binding: Binding) {
+        self.trace.borrow_mut().record(EnvEvent::Insert(name.clone(), binding.clone()));
+        self.bindings.bind(name, binding);
+    }
+
+    fn declared_type(&self, kind: TypeKind, name: &str) -> Option<&Type> {
+        self.bindings.declared_type(kind, name)
+    }
+
+    fn declare_type(&mut self, kind: TypeKind, name: String, ty: Type) {
+        self.bindings.declare_type(kind, name, ty);
+    }
+
+    fn operator(&self, op: Operator, left: &Type, right: &Type) -> Option<&Type> {
+        self.bindings.operator(op, left, right)
+    }
+
+    fn add_operator(&mut self, op: Operator, left: Type, right: Type, result: Type) {
+        self.bindings.add_operator(op, left, right, result);
+    }
+}
+
+// One line per binding, sorted by name, saying what bound it and where, e.g. `x: I32 (let,
+// line 4)` or `putchar: (I32) -> I32 (host)`. Names are unique within an env, so the order is
+// fully determined. Tests snapshot it, so change the format with care.
+pub fn dump_env(env: &Map<Binding>) -> String {
+    let mut bindings: Vec<(&String, &Binding)> = env.iter().collect();
+    bindings.sort_by(|left, right| left.0.cmp(right.0));
+    let mut dump = String::new();
+    for (name, binding) in bindings {
+        dump.push_str(&format!("{}: {} ({})\n", name, show_tag(&binding.ty), origin(binding)));
+    }
+    dump
+}
+
+fn origin(binding: &Binding) -> String {
+    let kind = match binding.kind {
+        BindingKind::Host => "host",
+        BindingKind::Let => "let",
+        BindingKind::LetMut => "let mut",
+        BindingKind::Extern => "extern",
+        BindingKind::Param => "parameter",
+        BindingKind::Pattern => "pattern",
+    };
+    match binding.position {
+        Some(ref pos) => format!("{}, line {}", kind, pos.start_pos.0),
+        None => kind.to_string(),
+    }
+}
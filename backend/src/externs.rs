@@ -0,0 +1,131 @@
+use ast::Position;
+use type_check::*;
+use visit::*;
+
+// A named group of extern declarations, e.g. the prelude or the functions a host provides.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExternSet {
+    pub name: String,
+    pub decls: Vec<(String, Type)>,
+}
+
+// Merges the sets in order, remembering which set each declaration comes from.
+// Declaring the same name with different types in two sets is an error naming both sets.
+pub fn merge_extern_sets(sets: &[ExternSet]) -> Result<Vec<(String, String, Type)>, Vec<String>> {
+    let mut merged: Vec<(String, String, Type)> = Vec::new();
+    let mut errors = Vec::new();
+    for set in sets {
+        for &(ref name, ref ty) in &set.decls {
+            let previous = merged.iter()
+                                 .find(|&&(_, ref other_name, _)| other_name == name)
+                                 .cloned();
+            match previous {
+                // The same declaration twice does no harm.
+                Some((_, _, ref other_ty)) if other_ty == ty => {}
+                Some((ref other_set, _, ref other_ty)) => errors.push(
+                    format!("Extern {} is declared with type {} in extern set {}, \
+                             but with type {} in extern set {}.",
+                            name, other_ty, other_set, ty, set.name)
+                ),
+                None => merged.push((set.name.clone(), name.clone(), ty.clone())),
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(merged)
+    } else {
+        Err(errors)
+    }
+}
+
+impl TaggedProgram<Position> {
+    // Puts the declarations of the extern sets in front of the main block,
+    // so they are checked and compiled exactly like externs written in the program.
+    pub fn with_extern_sets(&self, sets: &[ExternSet]) -> Result<Self, Vec<String>> {
+        let merged = try!(merge_extern_sets(sets));
+        let mut stmts: Vec<TaggedStatement<Position>> =
+            merged.into_iter()
+                  .map(|(_, name, ty)| TaggedStatement::Extern(self.tag.clone(), name, ty))
+                  .collect();
+        let redeclared: Vec<String> =
+            self.redeclared_externs(sets)
+                .into_iter()
+                .map(|(name, set)| format!("Extern {} is declared by the program, \
+                                            but it already comes from extern set {}.",
+                                           name, set))
+                .collect();
+        if !redeclared.is_empty() {
+            return Err(redeclared);
+        }
+        stmts.extend(self.main.stmts.iter().cloned());
+        Ok(
+            TaggedProgram {
+                tag: self.tag.clone(),
+                main: TaggedBlock {
+                    tag: self.main.tag.clone(),
+                    stmts: stmts,
+                    end: self.main.end.clone(),
+                },
+            }
+        )
+    }
+
+    fn redeclared_externs(&self, sets: &[ExternSet]) -> Vec<(String, String)> {
+        let mut redeclared = Vec::new();
+        for stmt in &self.main.stmts {
+            if let TaggedStatement::Extern(_, ref name, _) = *stmt {
+                for set in sets {
+                    if set.decls.iter().any(|&(ref set_name, _)| set_name == name) {
+                        redeclared.push((name.clone(), set.name.clone()));
+                    }
+                }
+            }
+        }
+        redeclared
+    }
+}
+
+// Flags every extern declaration it walks over.
+struct NestedExterns {
+    errors: Vec<String>,
+}
+
+impl NestedExterns {
+    // A statement of the main block, which may be an extern itself but mustn't contain one.
+    fn top_level(&mut self, stmt: &TaggedStatement<Position>) {
+        if let TaggedStatement::Extern(..) = *stmt {
+            return;
+        }
+        walk_statement(self, stmt);
+    }
+}
+
+impl Visitor<Position> for NestedExterns {
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Position>) {
+        if let TaggedStatement::Extern(ref pos, ref name, _) = *stmt {
+            self.errors.push(format!("extern declarations must appear at the top level, \
+                                      but {} is declared at line {}, column {} inside a nested \
+                                      term or block.", name, pos.start_pos.0, pos.start_pos.1));
+        }
+        walk_statement(self, stmt);
+    }
+}
+
+// Externs are only allowed as statements of the main block itself, so that each one is declared
+// once for the whole module and is visible everywhere after it.
+pub fn check_extern_placement(program: &TaggedProgram<Position>) -> Result<(), Vec<String>> {
+    let mut nested = NestedExterns { errors: Vec::new() };
+    for stmt in &program.main.stmts {
+        nested.top_level(stmt);
+    }
+    match *program.main.end {
+        Some(TaggedTerm::Stmt(ref stmt)) => nested.top_level(stmt),
+        Some(ref term) => nested.visit_term(term),
+        None => {}
+    }
+    if nested.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(nested.errors)
+    }
+}
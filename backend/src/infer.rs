@@ -0,0 +1,354 @@
+// Inference of the types a program leaves out, like the type of `x` in `let mut x;` or of the
+// parameters of `fn(x) { x + 1 }`.
+// Each type left out becomes a metavariable, which checking solves by unifying it with the types
+// it's used at. Everything else is still checked bottom-up, so a metavariable is only solved by
+// the uses checked after it's made. Once the program is checked, every metavariable in it is
+// replaced by its solution, and one without a solution is an error.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ast::{Operator, Position};
+use strictness::Strictness;
+use type_check::*;
+use visit::*;
+
+pub struct Metas {
+    // The solution of each metavariable once it has one, and a description of what it's the type
+    // of.
+    solutions: Vec<(Option<Type>, String)>,
+}
+
+impl Metas {
+    fn fresh(&mut self, origin: String) -> Type {
+        self.solutions.push((None, origin));
+        Type::Meta(self.solutions.len() as u32 - 1)
+    }
+
+    // `ty` with every solved metavariable in it replaced by its solution, all the way down.
+    pub fn zonk(&self, ty: &Type) -> Type {
+        use type_check::Type::*;
+        let all = |types: &[Type]| types.iter().map(|ty| self.zonk(ty)).collect();
+        match *ty {
+            Meta(meta) => match self.solutions[meta as usize].0 {
+                Some(ref solution) => self.zonk(solution),
+                None => ty.clone(),
+            },
+            TupleTy(ref elems) => TupleTy(all(elems)),
+            ArrayTy(ref elem, len) => ArrayTy(Box::new(self.zonk(elem)), len),
+            Ref(ref referent) => Ref(Box::new(self.zonk(referent))),
+            RefMut(ref referent) => RefMut(Box::new(self.zonk(referent))),
+            FunctionTy(ref params, ref args_types, ref ret_ty) =>
+                FunctionTy(params.clone(), all(args_types), Box::new(self.zonk(ret_ty))),
+            _ => ty.clone(),
+        }
+    }
+
+    // Solves metavariables so that `left` and `right` are the same type, if they can be.
+    // A metavariable can't be solved by a type containing itself, which would be infinite.
+    pub fn unify(&mut self, left: &Type, right: &Type) -> bool {
+        use type_check::Type::*;
+        match (self.zonk(left), self.zonk(right)) {
+            // Never fits anywhere, without solving anything.
+            (Never, _) | (_, Never) => true,
+            (Meta(left), Meta(right)) if left == right => true,
+            (Meta(meta), ty) | (ty, Meta(meta)) => {
+                if occurs(meta, &ty) {
+                    return false;
+                }
+                self.solutions[meta as usize].0 = Some(ty);
+                true
+            }
+            (TupleTy(left), TupleTy(right)) => self.unify_all(&left, &right),
+            (ArrayTy(left, left_len), ArrayTy(right, right_len)) =>
+                left_len == right_len && self.unify(&left, &right),
+            (Ref(left), Ref(right)) | (RefMut(left), RefMut(right)) => self.unify(&left, &right),
+            (FunctionTy(left_params, left_args, left_ret),
+             FunctionTy(right_params, right_args, right_ret)) =>
+                left_params == right_params && self.unify_all(&left_args, &right_args) &&
+                self.unify(&left_ret, &right_ret),
+            (left, right) => left == right,
+        }
+    }
+
+    fn unify_all(&mut self, left: &[Type], right: &[Type]) -> bool {
+        left.len() == right.len() &&
+        left.iter().zip(right).all(|(left, right)| self.unify(left, right))
+    }
+
+    fn unsolved(&self) -> Vec<String> {
+        self.solutions.iter()
+                      .filter(|&&(ref solution, _)| solution.is_none())
+                      .map(|&(_, ref origin)| format!("Cannot infer the type of {}.", origin))
+                      .collect()
+    }
+}
+
+// Whether the metavariable `meta` appears in `ty`, which is zonked.
+fn occurs(meta: u32, ty: &Type) -> bool {
+    use type_check::Type::*;
+    match *ty {
+        Meta(other) => other == meta,
+        TupleTy(ref elems) => elems.iter().any(|elem| occurs(meta, elem)),
+        ArrayTy(ref elem, _) | Ref(ref elem) | RefMut(ref elem) => occurs(meta, elem),
+        FunctionTy(_, ref args_types, ref ret_ty) =>
+            args_types.iter().any(|arg_ty| occurs(meta, arg_ty)) || occurs(meta, ret_ty),
+        _ => false,
+    }
+}
+
+// Checks with the bindings of another env, making metavariables for the types left out.
+#[derive(Clone)]
+pub struct InferEnv<E: TypeEnv> {
+    inner: E,
+    // Shared by all the copies made while checking.
+    metas: Rc<RefCell<Metas>>,
+    // The names bound in this copy, whose types may mention metavariables.
+    bound: Vec<String>,
+}
+
+impl<E: TypeEnv> InferEnv<E> {
+    pub fn new(inner: E) -> InferEnv<E> {
+        InferEnv {
+            inner: inner,
+            metas: Rc::new(RefCell::new(Metas { solutions: Vec::new() })),
+            bound: Vec::new(),
+        }
+    }
+
+    // Replaces the metavariables in `block`, which was checked in this env, by their solutions.
+    pub fn solve_block(&self,
+                       mut block: TaggedBlock<Type>) -> Result<TaggedBlock<Type>, Vec<String>> {
+        let metas = self.metas.borrow();
+        let errors = metas.unsolved();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        zonk_block(&metas, &mut block);
+        Ok(block)
+    }
+
+    // The env this one was made from, with the bindings made in this one added and their types
+    // solved.
+    pub fn into_inner(self) -> E {
+        let mut inner = self.inner;
+        let metas = self.metas.borrow();
+        for name in self.bound {
+            let binding = inner.binding(&name).cloned();
+            if let Some(binding) = binding {
+                let solved = metas.zonk(&binding.ty);
+                if solved != binding.ty {
+                    inner.bind(name, Binding { ty: solved, ..binding });
+                }
+            }
+        }
+        inner
+    }
+}
+
+impl<E: TypeEnv> TypeEnv for InferEnv<E> {
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.inner.binding(name)
+    }
+
+    fn bind(&mut self, name: String, binding: Binding) {
+        self.bound.push(name.clone());
+        self.inner.bind(name, binding);
+    }
+
+    fn declared_type(&self, kind: TypeKind, name: &str) -> Option<&Type> {
+        self.inner.declared_type(kind, name)
+    }
+
+    fn declare_type(&mut self, kind: TypeKind, name: String, ty: Type) {
+        self.inner.declare_type(kind, name, ty);
+    }
+
+    fn operator(&self, op: Operator, left: &Type, right: &Type) -> Option<&Type> {
+        self.inner.operator(op, left, right)
+    }
+
+    fn add_operator(&mut self, op: Operator, left: Type, right: Type, result: Type) {
+        self.inner.add_operator(op, left, right, result);
+    }
+
+    fn used(&self, name: &str, binding: &Binding, position: &Position) {
+        self.inner.used(name, binding, position);
+    }
+
+    fn checked_item(&self, done: usize, total: usize) {
+        self.inner.checked_item(done, total);
+    }
+
+    fn strictness(&self) -> Strictness {
+        self.inner.strictness()
+    }
+
+    fn warn(&self, warning: String) {
+        self.inner.warn(warning)
+    }
+
+    fn unify(&self, left: &Type, right: &Type) -> bool {
+        self.metas.borrow_mut().unify(left, right)
+    }
+
+    fn zonk(&self, ty: &Type) -> Type {
+        self.metas.borrow().zonk(ty)
+    }
+
+    fn fresh_meta(&self, origin: String) -> Option<Type> {
+        Some(self.metas.borrow_mut().fresh(origin))
+    }
+}
+
+struct Holes {
+    found: bool,
+}
+
+impl Visitor<Position> for Holes {
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Position>) {
+        if let TaggedStatement::Declare(_, _, Type::Hole) = *stmt {
+            self.found = true;
+        }
+        walk_statement(self, stmt);
+    }
+
+    fn visit_term(&mut self, term: &TaggedTerm<Position>) {
+        if let TaggedTerm::Lambda(_, ref params, _) = *term {
+            if params.iter().any(|&(_, ref ty)| *ty == Type::Hole) {
+                self.found = true;
+            }
+        }
+        walk_term(self, term);
+    }
+}
+
+// Whether `program` leaves out any types. Only those programs are checked with inference, so
+// the others are checked in the env they're given.
+pub fn has_holes(program: &TaggedProgram<Position>) -> bool {
+    let mut holes = Holes { found: false };
+    holes.visit_program(program);
+    holes.found
+}
+
+fn zonk_block(metas: &Metas, block: &mut TaggedBlock<Type>) {
+    block.tag = metas.zonk(&block.tag);
+    for stmt in &mut block.stmts {
+        zonk_statement(metas, stmt);
+    }
+    if let Some(ref mut end) = *block.end {
+        zonk_term(metas, end);
+    }
+}
+
+fn zonk_statement(metas: &Metas, stmt: &mut TaggedStatement<Type>) {
+    use type_check::TaggedStatement::*;
+    match *stmt {
+        TermSemicolon(ref mut tag, ref mut term) | Mutate(ref mut tag, _, ref mut term) => {
+            *tag = metas.zonk(tag);
+            zonk_term(metas, term);
+        }
+        Let(ref mut tag, _, ref mut ty, ref mut term) |
+        LetMut(ref mut tag, _, ref mut ty, ref mut term) => {
+            *tag = metas.zonk(tag);
+            *ty = metas.zonk(ty);
+            zonk_term(metas, term);
+        }
+        Extern(ref mut tag, _, ref mut ty) | Declare(ref mut tag, _, ref mut ty) |
+        TypeAlias(ref mut tag, _, ref mut ty) => {
+            *tag = metas.zonk(tag);
+            *ty = metas.zonk(ty);
+        }
+        StructDecl(ref mut tag, _, _) | EnumDecl(ref mut tag, _, _) => *tag = metas.zonk(tag),
+        MutateThrough(ref mut tag, ref mut target, ref mut rhs) => {
+            *tag = metas.zonk(tag);
+            zonk_term(metas, target);
+            zonk_term(metas, rhs);
+        }
+    }
+}
+
+fn zonk_term(metas: &Metas, term: &mut TaggedTerm<Type>) {
+    use type_check::TaggedTerm::*;
+    match *term {
+        Literal(ref mut tag, _) | Var(ref mut tag, _) | BoolLiteral(ref mut tag, _) |
+        SuffixedLiteral(ref mut tag, _, _) | FloatLiteral(ref mut tag, _) |
+        CharLit(ref mut tag, _) | StrLit(ref mut tag, _) | Null(ref mut tag) =>
+            *tag = metas.zonk(tag),
+        Infix(ref mut tag, ref mut left, _, ref mut right) |
+        Index(ref mut tag, ref mut left, ref mut right) |
+        PtrEq(ref mut tag, ref mut left, ref mut right) => {
+            *tag = metas.zonk(tag);
+            zonk_term(metas, left);
+            zonk_term(metas, right);
+        }
+        Call(ref mut tag, ref mut func, ref mut args) => {
+            *tag = metas.zonk(tag);
+            func.tag = metas.zonk(&func.tag);
+            zonk_terms(metas, args);
+        }
+        Scope(ref mut tag, ref mut block) => {
+            *tag = metas.zonk(tag);
+            zonk_block(metas, block);
+        }
+        If(ref mut tag, ref mut cond, ref mut if_true, ref mut if_false) => {
+            *tag = metas.zonk(tag);
+            zonk_term(metas, cond);
+            zonk_term(metas, if_true);
+            if let Some(ref mut if_false) = *if_false {
+                zonk_term(metas, if_false);
+            }
+        }
+        While(ref mut tag, ref mut cond, ref mut block) => {
+            *tag = metas.zonk(tag);
+            zonk_term(metas, cond);
+            zonk_block(metas, block);
+        }
+        Stmt(ref mut stmt) => zonk_statement(metas, stmt),
+        Cast(ref mut tag, ref mut term, _) | Ascribe(ref mut tag, ref mut term, _) |
+        Project(ref mut tag, ref mut term, _) |
+        Field(ref mut tag, ref mut term, _) | Borrow(ref mut tag, ref mut term) |
+        Deref(ref mut tag, ref mut term) | BorrowMut(ref mut tag, ref mut term) |
+        Neg(ref mut tag, ref mut term) | Not(ref mut tag, ref mut term) => {
+            *tag = metas.zonk(tag);
+            zonk_term(metas, term);
+        }
+        Tuple(ref mut tag, ref mut elems) | ArrayLit(ref mut tag, ref mut elems) |
+        Variant(ref mut tag, _, _, ref mut elems) => {
+            *tag = metas.zonk(tag);
+            zonk_terms(metas, elems);
+        }
+        StructLit(ref mut tag, _, ref mut inits) => {
+            *tag = metas.zonk(tag);
+            for &mut (_, ref mut term) in inits {
+                zonk_term(metas, term);
+            }
+        }
+        Match(ref mut tag, ref mut scrutinee, ref mut arms) => {
+            *tag = metas.zonk(tag);
+            zonk_term(metas, scrutinee);
+            for &mut (_, ref mut body) in arms {
+                zonk_term(metas, body);
+            }
+        }
+        Lambda(ref mut tag, ref mut params, ref mut body) => {
+            *tag = metas.zonk(tag);
+            for &mut (_, ref mut ty) in params {
+                *ty = metas.zonk(ty);
+            }
+            zonk_block(metas, body);
+        }
+        Apply(ref mut tag, ref mut callee, ref mut args) |
+        MethodCall(ref mut tag, ref mut callee, _, ref mut args) => {
+            *tag = metas.zonk(tag);
+            zonk_term(metas, callee);
+            zonk_terms(metas, args);
+        }
+    }
+}
+
+fn zonk_terms(metas: &Metas, terms: &mut [TaggedTerm<Type>]) {
+    for term in terms {
+        zonk_term(metas, term);
+    }
+}
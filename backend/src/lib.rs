@@ -1,17 +1,84 @@
 extern crate llvm_sys;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
 
+// `api` is the surface that stays source compatible. The other modules are public for the
+// compiler's own binary and tests, but they change with the compiler, so they're hidden.
+pub mod api;
+#[doc(hidden)]
 pub mod ast;
+#[doc(hidden)]
 pub mod type_check;
+#[doc(hidden)]
 pub mod codegen;
+#[doc(hidden)]
 pub mod trans;
+#[doc(hidden)]
+pub mod visit;
+#[doc(hidden)]
+pub mod usage;
+#[doc(hidden)]
+pub mod externs;
+#[doc(hidden)]
+pub mod minimize;
+#[doc(hidden)]
+pub mod typed_ast;
+#[doc(hidden)]
+pub mod definite;
+#[doc(hidden)]
+pub mod validate;
+#[doc(hidden)]
+pub mod trace;
+#[doc(hidden)]
+pub mod runtime;
+#[doc(hidden)]
+pub mod semantics;
+#[doc(hidden)]
+pub mod cost;
+#[doc(hidden)]
+pub mod limits;
+#[doc(hidden)]
+pub mod ranges;
+#[doc(hidden)]
+pub mod lint;
+#[doc(hidden)]
+pub mod code_size;
+#[doc(hidden)]
+pub mod safe;
+#[doc(hidden)]
+pub mod env_trace;
+#[doc(hidden)]
+pub mod typed_builder;
+#[doc(hidden)]
+pub mod used;
+#[doc(hidden)]
+pub mod strictness;
+#[doc(hidden)]
+pub mod diagnostics;
+#[doc(hidden)]
+pub mod const_eval;
+#[doc(hidden)]
+pub mod print;
+#[doc(hidden)]
+pub mod canonical;
+#[doc(hidden)]
+pub mod captures;
+#[doc(hidden)]
+pub mod infer;
+#[doc(hidden)]
+pub mod progress;
+#[doc(hidden)]
 #[allow(dead_code, non_camel_case_types, non_snake_case)]
 pub mod Parsing {
     include!("../../frontend/parsing.rs");
 }
+#[doc(hidden)]
 #[allow(dead_code, non_camel_case_types, non_snake_case)]
 pub mod HsClosureFunc {
     include!("../../frontend/hs_closure_func.rs");
 }
+#[doc(hidden)]
 #[allow(dead_code)]
 pub mod inc {
     include!("../../inc.rs");
@@ -0,0 +1,79 @@
+use minimize::program_size;
+use type_check::*;
+use visit::*;
+
+struct LongestName {
+    length: usize,
+    name: String,
+}
+
+impl LongestName {
+    fn see(&mut self, name: &str) {
+        if name.len() > self.length {
+            self.length = name.len();
+            self.name = name.to_string();
+        }
+    }
+}
+
+impl<Tag> Visitor<Tag> for LongestName {
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Tag>) {
+        use type_check::TaggedStatement::*;
+        match *stmt {
+            TermSemicolon(..) | MutateThrough(..) => {}
+            Let(_, ref name, _, _) | LetMut(_, ref name, _, _) | Mutate(_, ref name, _) |
+            Extern(_, ref name, _) | Declare(_, ref name, _) | StructDecl(_, ref name, _) |
+            EnumDecl(_, ref name, _) | TypeAlias(_, ref name, _) => self.see(name),
+        }
+        walk_statement(self, stmt);
+    }
+
+    fn visit_term(&mut self, term: &TaggedTerm<Tag>) {
+        use type_check::TaggedTerm::*;
+        match *term {
+            Var(_, ref name) => self.see(name),
+            Call(_, ref func, _) | MethodCall(_, _, ref func, _) => self.see(&func.name),
+            _ => {}
+        }
+        walk_term(self, term);
+    }
+}
+
+// Refuses programs over the size limits of the options, each limit with its own error.
+pub fn check_size_limits<Tag>(program: &TaggedProgram<Tag>,
+                              options: &CheckOptions) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    if let Some(limit) = options.max_statements {
+        let statements = program.main.stmts.len();
+        if statements > limit {
+            errors.push(format!("The program has {} top-level statements, \
+                                 but at most {} are allowed.", statements, limit));
+        }
+    }
+    if let Some(limit) = options.max_nodes {
+        let nodes = program_size(program);
+        if nodes > limit {
+            errors.push(format!("The program has {} terms and statements, \
+                                 but at most {} are allowed.", nodes, limit));
+        }
+    }
+    if let Some(limit) = options.max_identifier_length {
+        let mut longest = LongestName { length: 0, name: String::new() };
+        longest.visit_program(program);
+        if longest.length > limit {
+            // The name itself could be huge, so only its start is shown.
+            let mut shown: String = longest.name.chars().take(16).collect();
+            if shown.len() < longest.name.len() {
+                shown.push_str("...");
+            }
+            errors.push(format!("The name {} is {} bytes long, \
+                                 but names can be at most {} bytes long.",
+                                shown, longest.length, limit));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,198 @@
+// Lints are extra rules over checked programs, e.g. project-specific restrictions.
+// Each one is allowed, warned about or denied by name.
+
+use std::collections::HashSet;
+
+use ast::Pattern;
+use codegen::Map;
+use type_check::*;
+use usage::{FeatureUsage, feature_usage};
+use visit::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+// What lints get besides the program.
+pub struct LintContext {
+    pub usage: FeatureUsage,
+    // Every name a statement binds, in program order.
+    pub bindings: Vec<String>,
+    // Names read by statements that failed to check, which the program lints see leaves out.
+    // See `diagnostics`.
+    pub unchecked_reads: HashSet<String>,
+}
+
+pub struct DiagnosticSink {
+    messages: Vec<String>,
+}
+
+impl DiagnosticSink {
+    pub fn report(&mut self, message: String) {
+        self.messages.push(message);
+    }
+}
+
+pub trait Lint {
+    fn name(&self) -> &str;
+    fn check(&self, program: &TaggedProgram<Type>, ctx: &LintContext, sink: &mut DiagnosticSink);
+}
+
+struct NameCollector {
+    bindings: Vec<String>,
+    // The bindings that are variables rather than externs.
+    variables: Vec<String>,
+    reads: HashSet<String>,
+}
+
+impl Visitor<Type> for NameCollector {
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Type>) {
+        use type_check::TaggedStatement::*;
+        match *stmt {
+            Let(_, ref name, _, _) | LetMut(_, ref name, _, _) | Declare(_, ref name, _) => {
+                self.bindings.push(name.clone());
+                self.variables.push(name.clone());
+            }
+            Extern(_, ref name, _) => self.bindings.push(name.clone()),
+            TermSemicolon(..) | Mutate(..) | StructDecl(..) | EnumDecl(..) | TypeAlias(..) |
+            MutateThrough(..) => {}
+        }
+        walk_statement(self, stmt);
+    }
+
+    fn visit_term(&mut self, term: &TaggedTerm<Type>) {
+        use type_check::TaggedTerm::*;
+        match *term {
+            Var(_, ref name) => {
+                self.reads.insert(name.clone());
+            }
+            Call(_, ref func, _) => {
+                self.reads.insert(func.name.clone());
+            }
+            _ => {}
+        }
+        walk_term(self, term);
+    }
+}
+
+fn collect_names(program: &TaggedProgram<Type>) -> NameCollector {
+    let mut collector = NameCollector {
+        bindings: Vec::new(),
+        variables: Vec::new(),
+        reads: HashSet::new(),
+    };
+    collector.visit_program(program);
+    collector
+}
+
+// Variables that are bound but never read.
+// Names are compared as strings, so a name read anywhere counts as read for every binding of it.
+pub struct UnusedVariable;
+
+impl Lint for UnusedVariable {
+    fn name(&self) -> &str {
+        "unused_variable"
+    }
+
+    fn check(&self, program: &TaggedProgram<Type>, ctx: &LintContext, sink: &mut DiagnosticSink) {
+        let names = collect_names(program);
+        let mut reported = HashSet::new();
+        for name in &names.variables {
+            let read = names.reads.contains(name) || ctx.unchecked_reads.contains(name);
+            if !read && reported.insert(name) {
+                sink.report(format!("Variable {} is never read.", name));
+            }
+        }
+    }
+}
+
+struct ArmCollector {
+    // Descriptions of the arms no value can reach.
+    unreachable: Vec<String>,
+}
+
+impl Visitor<Type> for ArmCollector {
+    fn visit_term(&mut self, term: &TaggedTerm<Type>) {
+        if let TaggedTerm::Match(_, _, ref arms) = *term {
+            let mut covered = HashSet::new();
+            let mut after_wildcard = false;
+            for &(ref pattern, _) in arms {
+                let reachable = match *pattern {
+                    Pattern::Constructor(_, ref variant, _) => covered.insert(variant.clone()),
+                    Pattern::Wildcard => true,
+                };
+                if after_wildcard || !reachable {
+                    self.unreachable.push(format!("{}", pattern));
+                }
+                if *pattern == Pattern::Wildcard {
+                    after_wildcard = true;
+                }
+            }
+        }
+        walk_term(self, term);
+    }
+}
+
+// Arms of a match that come after another arm for the same variant, or after a wildcard.
+pub struct UnreachableArm;
+
+impl Lint for UnreachableArm {
+    fn name(&self) -> &str {
+        "unreachable_arm"
+    }
+
+    fn check(&self, program: &TaggedProgram<Type>, _: &LintContext, sink: &mut DiagnosticSink) {
+        let mut collector = ArmCollector { unreachable: Vec::new() };
+        collector.visit_program(program);
+        for pattern in collector.unreachable {
+            sink.report(format!("The arm {} is never reached, since an earlier arm matches \
+                                 everything it does.", pattern));
+        }
+    }
+}
+
+// Runs the lints over a checked program.
+// Lints default to `Warn`; the warnings are returned, or all messages of denied lints if any
+// of those fired. Every message is prefixed with the name of its lint.
+pub fn run_lints(program: &TaggedProgram<Type>,
+                 lints: &[&Lint],
+                 levels: &Map<LintLevel>) -> Result<Vec<String>, Vec<String>> {
+    run_lints_with(program, HashSet::new(), lints, levels)
+}
+
+// `run_lints` over what checked of a program, with the names read by what didn't.
+pub fn run_lints_with(program: &TaggedProgram<Type>,
+                      unchecked_reads: HashSet<String>,
+                      lints: &[&Lint],
+                      levels: &Map<LintLevel>) -> Result<Vec<String>, Vec<String>> {
+    let ctx = LintContext {
+        usage: feature_usage(program),
+        bindings: collect_names(program).bindings,
+        unchecked_reads: unchecked_reads,
+    };
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    for lint in lints {
+        let level = *levels.get(lint.name()).unwrap_or(&LintLevel::Warn);
+        if level == LintLevel::Allow {
+            continue;
+        }
+        let mut sink = DiagnosticSink { messages: Vec::new() };
+        lint.check(program, &ctx, &mut sink);
+        let messages = sink.messages.into_iter()
+                                    .map(|message| format!("[{}] {}", lint.name(), message));
+        if level == LintLevel::Deny {
+            errors.extend(messages);
+        } else {
+            warnings.extend(messages);
+        }
+    }
+    if errors.is_empty() {
+        Ok(warnings)
+    } else {
+        Err(errors)
+    }
+}
@@ -1,38 +1,26 @@
 extern crate llvm_sys;
 extern crate getopts;
-extern crate core;
 #[macro_use]
 extern crate ende;
 
 use std::env;
 use std::fs::File;
 use std::io::Read;
+use std::process;
 use getopts::Options;
-use std::ffi::*;
 use llvm_sys::core::*;
-use core::mem::transmute;
-use std::os::raw::c_void;
-unsafe fn haskell_init() {
-    let filename : &[u8] = b"main\x00";
-    let mut argc : i32 = 1;
-    let mut argv : & [*const u8] = &[filename.as_ptr(),::core::ptr::null()];
-    ende::HsClosureFunc::hs_init(&mut argc, transmute(&mut argv));
-}
-unsafe fn haskell_exit() {
-    ende::HsClosureFunc::hs_exit();
-}
 
 pub fn main() {
-    use ende::codegen::*;
-    use ende::trans::*;
-    use ende::ast::Position;
-    use ende::type_check::{TypeCheck, TaggedProgram};
+    use ende::api::*;
 
     let args : Vec<String> = env::args().collect();
     let program = args[0].clone();
 
     let mut opts = Options::new();
     opts.optopt("o", "", "output file name", "OUTPUT");
+    opts.optflag("", "legacy", "accept what the checker used to accept, with warnings");
+    opts.optflag("", "eval-const-main", "compute a pure main ahead of time if it's cheap enough");
+    opts.optflag("", "dump-ir", "print the LLVM IR of the program to stderr");
     opts.optflag("h", "help", "print this help menu");
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => { m }
@@ -60,21 +48,33 @@ pub fn main() {
     let _ = input.read_to_string(&mut input_data);
 
     unsafe {
-        haskell_init();
-        let c_input = match CString::new(input_data) {
-            Ok(c_input) => c_input.into_raw(),
-            Err(err) => panic!("Failed to transform input data to c ptr: {}", err)
+        let options = CheckOptions {
+            strictness: if matches.opt_present("legacy") {
+                Strictness::Legacy
+            } else {
+                Strictness::Strict
+            },
+            ..CheckOptions::default()
+        };
+        let result = check_str_all(&input_data, &[], &options);
+        for diagnostic in &result.diagnostics {
+            println!("{}", diagnostic.message);
+        }
+        if result.has_errors() {
+            shutdown();
+            process::exit(1);
+        }
+        let compile_options = CompileOptions {
+            evaluate_const_main: matches.opt_present("eval-const-main"),
+            ..CompileOptions::default()
         };
-        let tree_prim = ende::Parsing::parseProgram(c_input as *mut c_void);
-        let block : TaggedProgram<Position> = FromHaskellRepr::from_haskell_repr(ende::HsClosureFunc::_deRefStablePtr(tree_prim) as *mut ende::HsClosureFunc::StgClosure);
-        let mut env = Map::new();
-        let result = block.type_check(&mut env).unwrap().gen_module();
-        println!("{:?}", result);
-        let module = result.ok().unwrap();
-        LLVMDumpModule(module.clone());
+        let (module, _) = compile_checked_with(&result, &compile_options).unwrap();
+        if matches.opt_present("dump-ir") {
+            LLVMDumpModule(module);
+        }
         emit_ir(module, output.clone());
-        emit_exe(output);
-        haskell_exit();
+        emit_exe_with(module, output, &LinkOptions::default()).unwrap();
+        shutdown();
     }
 }
 
@@ -0,0 +1,396 @@
+use type_check::*;
+use type_check::TaggedTerm::*;
+
+// Shrinks a program while `oracle` keeps returning true, to turn a large bug report into a
+// small reproducer.
+// Statements are dropped in halving chunks, block ends are removed, and terms are replaced by
+// `0` or by one of their own subterms. Every candidate is a well-formed tree, but it may not
+// type check; the oracle decides what counts as still failing.
+pub fn minimize<Tag, F>(program: &TaggedProgram<Tag>, oracle: F) -> TaggedProgram<Tag>
+    where Tag: Clone, F: Fn(&TaggedProgram<Tag>) -> bool {
+    let mut current = program.clone();
+    if !oracle(&current) {
+        return current;
+    }
+    loop {
+        let mut changed = false;
+        let mut blocks = preorder(&current).blocks;
+        let mut index = 0;
+        while index < blocks.len() {
+            if drop_statements(&mut current, &blocks[index], &oracle) {
+                changed = true;
+                blocks = preorder(&current).blocks;
+            }
+            index += 1;
+        }
+        let mut terms = preorder(&current).terms;
+        let mut index = 0;
+        while index < terms.len() {
+            if simplify_term(&mut current, &terms[index], &oracle) {
+                changed = true;
+                terms = preorder(&current).terms;
+            }
+            index += 1;
+        }
+        if !changed {
+            return current;
+        }
+    }
+}
+
+// The number of terms and statements in the program.
+pub fn program_size<Tag>(program: &TaggedProgram<Tag>) -> usize {
+    fn block_size<Tag>(block: &TaggedBlock<Tag>) -> usize {
+        let stmts: usize = block.stmts.iter().map(stmt_size).sum();
+        let end = match *block.end {
+            Some(ref term) => term_size(term),
+            None => 0,
+        };
+        stmts + end
+    }
+    fn stmt_size<Tag>(stmt: &TaggedStatement<Tag>) -> usize {
+        use type_check::TaggedStatement::*;
+        match *stmt {
+            TermSemicolon(_, ref term) => 1 + term_size(term),
+            Let(_, _, _, ref rhs) => 1 + term_size(rhs),
+            LetMut(_, _, _, ref rhs) => 1 + term_size(rhs),
+            Mutate(_, _, ref rhs) => 1 + term_size(rhs),
+            Extern(..) | Declare(..) | StructDecl(..) | EnumDecl(..) | TypeAlias(..) => 1,
+            MutateThrough(_, ref target, ref rhs) => 1 + term_size(target) + term_size(rhs),
+        }
+    }
+    fn term_size<Tag>(term: &TaggedTerm<Tag>) -> usize {
+        1 + match *term {
+            Literal(..) | Var(..) | BoolLiteral(..) | SuffixedLiteral(..) | FloatLiteral(..) |
+            CharLit(..) | StrLit(..) | Null(..) => 0,
+            Infix(_, ref left, _, ref right) | PtrEq(_, ref left, ref right) =>
+                term_size(left) + term_size(right),
+            Call(_, _, ref args) => args.iter().map(term_size).sum(),
+            Scope(_, ref block) | Lambda(_, _, ref block) => block_size(block),
+            Apply(_, ref callee, ref args) | MethodCall(_, ref callee, _, ref args) =>
+                term_size(callee) + args.iter().map(term_size).sum::<usize>(),
+            If(_, ref cond, ref if_true, ref if_false) =>
+                term_size(cond) + term_size(if_true) +
+                if_false.as_ref().map_or(0, |if_false| term_size(if_false)),
+            While(_, ref cond, ref block) => term_size(cond) + block_size(block),
+            Stmt(ref stmt) => stmt_size(stmt),
+            Cast(_, ref term, _) | Ascribe(_, ref term, _) | Neg(_, ref term) |
+            Not(_, ref term) => term_size(term),
+            Tuple(_, ref elems) => elems.iter().map(term_size).sum(),
+            Project(_, ref term, _) => term_size(term),
+            ArrayLit(_, ref elems) => elems.iter().map(term_size).sum(),
+            Index(_, ref array, ref index) => term_size(array) + term_size(index),
+            StructLit(_, _, ref inits) => inits.iter().map(|&(_, ref term)| term_size(term)).sum(),
+            Field(_, ref term, _) | Borrow(_, ref term) | Deref(_, ref term) |
+            BorrowMut(_, ref term) => term_size(term),
+            Variant(_, _, _, ref args) => args.iter().map(term_size).sum(),
+            Match(_, ref scrutinee, ref arms) =>
+                term_size(scrutinee) +
+                arms.iter().map(|&(_, ref body)| term_size(body)).sum::<usize>(),
+        }
+    }
+    block_size(&program.main)
+}
+
+fn drop_statements<Tag, F>(current: &mut TaggedProgram<Tag>, path: &[usize], oracle: &F) -> bool
+    where Tag: Clone, F: Fn(&TaggedProgram<Tag>) -> bool {
+    let mut changed = false;
+    let mut chunk = (block_at(current, path).stmts.len() + 1) / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < block_at(current, path).stmts.len() {
+            let mut candidate = current.clone();
+            {
+                let stmts = &mut block_at_mut(&mut candidate, path).stmts;
+                let end = if start + chunk < stmts.len() { start + chunk } else { stmts.len() };
+                stmts.drain(start..end);
+            }
+            if oracle(&candidate) {
+                *current = candidate;
+                changed = true;
+            } else {
+                start += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+    if block_at(current, path).end.is_some() {
+        let mut candidate = current.clone();
+        *block_at_mut(&mut candidate, path).end = None;
+        if oracle(&candidate) {
+            *current = candidate;
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn simplify_term<Tag, F>(current: &mut TaggedProgram<Tag>, path: &[usize], oracle: &F) -> bool
+    where Tag: Clone, F: Fn(&TaggedProgram<Tag>) -> bool {
+    let replacements = simplifications(term_at(current, path));
+    for replacement in replacements {
+        let mut candidate = current.clone();
+        *term_at_mut(&mut candidate, path) = replacement;
+        if oracle(&candidate) {
+            *current = candidate;
+            return true;
+        }
+    }
+    false
+}
+
+// Smaller terms which could stand in for `term`, the most aggressive first.
+fn simplifications<Tag: Clone>(term: &TaggedTerm<Tag>) -> Vec<TaggedTerm<Tag>> {
+    let mut result = Vec::new();
+    match *term {
+        // Literals are as small as terms get.
+        Literal(_, 0) | BoolLiteral(..) | SuffixedLiteral(_, 0, _) | FloatLiteral(_, 0) |
+        CharLit(..) | StrLit(..) | Null(..) => return result,
+        FloatLiteral(ref tag, _) => result.push(FloatLiteral(tag.clone(), 0)),
+        SuffixedLiteral(ref tag, _, ref ty) =>
+            result.push(SuffixedLiteral(tag.clone(), 0, ty.clone())),
+        Literal(ref tag, _) | Var(ref tag, _) | Infix(ref tag, _, _, _) | Call(ref tag, _, _) |
+        Scope(ref tag, _) | If(ref tag, _, _, _) | While(ref tag, _, _) | Cast(ref tag, _, _) |
+        Tuple(ref tag, _) | Project(ref tag, _, _) | ArrayLit(ref tag, _) |
+        Index(ref tag, _, _) | StructLit(ref tag, _, _) | Field(ref tag, _, _) |
+        Variant(ref tag, _, _, _) | Match(ref tag, _, _) | Borrow(ref tag, _) |
+        Deref(ref tag, _) | BorrowMut(ref tag, _) | PtrEq(ref tag, _, _) |
+        Lambda(ref tag, _, _) | Apply(ref tag, _, _) | Ascribe(ref tag, _, _) |
+        MethodCall(ref tag, _, _, _) | Neg(ref tag, _) | Not(ref tag, _) =>
+            result.push(Literal(tag.clone(), 0)),
+        Stmt(_) => {}
+    }
+    match *term {
+        Infix(_, ref left, _, ref right) | Index(_, ref left, ref right) => {
+            result.push(*left.clone());
+            result.push(*right.clone());
+        }
+        If(_, _, ref if_true, ref if_false) => {
+            result.push(*if_true.clone());
+            if let Some(ref if_false) = *if_false {
+                result.push(*if_false.clone());
+            }
+        }
+        Cast(_, ref term, _) | Project(_, ref term, _) | Field(_, ref term, _) |
+        Ascribe(_, ref term, _) | Neg(_, ref term) | Not(_, ref term) =>
+            result.push(*term.clone()),
+        StructLit(_, _, ref inits) =>
+            result.extend(inits.iter().map(|&(_, ref term)| term.clone())),
+        // An arm has the type of the whole match, which the scrutinee usually doesn't.
+        Match(_, _, ref arms) => result.extend(arms.iter().map(|&(_, ref body)| body.clone())),
+        Tuple(_, ref elems) | ArrayLit(_, ref elems) | Variant(_, _, _, ref elems) =>
+            result.extend(elems.iter().cloned()),
+        Scope(_, ref block) if block.stmts.is_empty() => {
+            if let Some(ref end) = *block.end {
+                result.push(end.clone());
+            }
+        }
+        _ => {}
+    }
+    result
+}
+
+// The child indices leading from main to a block or a term, as `children` numbers them.
+type Path = Vec<usize>;
+
+enum Node<'a, Tag: 'a> {
+    Block(&'a TaggedBlock<Tag>),
+    Term(&'a TaggedTerm<Tag>),
+}
+
+enum NodeMut<'a, Tag: 'a> {
+    Block(&'a mut TaggedBlock<Tag>),
+    Term(&'a mut TaggedTerm<Tag>),
+}
+
+// The paths of all blocks and of all terms, each in preorder.
+// Changing a node only moves the nodes inside it, which come after it, so a pass can go on from
+// the same index in the paths of the changed program.
+struct Preorder {
+    blocks: Vec<Path>,
+    terms: Vec<Path>,
+}
+
+fn preorder<Tag>(program: &TaggedProgram<Tag>) -> Preorder {
+    fn visit<Tag>(node: Node<Tag>, path: &mut Path, preorder: &mut Preorder) {
+        match node {
+            Node::Block(_) => preorder.blocks.push(path.clone()),
+            Node::Term(_) => preorder.terms.push(path.clone()),
+        }
+        for (index, child) in children(node).into_iter().enumerate() {
+            path.push(index);
+            visit(child, path, preorder);
+            path.pop();
+        }
+    }
+    let mut preorder = Preorder { blocks: Vec::new(), terms: Vec::new() };
+    visit(Node::Block(&program.main), &mut Vec::new(), &mut preorder);
+    preorder
+}
+
+fn node_at<'a, Tag>(program: &'a TaggedProgram<Tag>, path: &[usize]) -> Node<'a, Tag> {
+    let mut node = Node::Block(&program.main);
+    for &index in path {
+        node = children(node).into_iter().nth(index).unwrap();
+    }
+    node
+}
+
+fn node_at_mut<'a, Tag>(program: &'a mut TaggedProgram<Tag>, path: &[usize]) -> NodeMut<'a, Tag> {
+    let mut node = NodeMut::Block(&mut program.main);
+    for &index in path {
+        node = children_mut(node).into_iter().nth(index).unwrap();
+    }
+    node
+}
+
+// The paths come from `preorder`, so they lead to the kind of node they were collected for.
+
+fn block_at<'a, Tag>(program: &'a TaggedProgram<Tag>, path: &[usize]) -> &'a TaggedBlock<Tag> {
+    match node_at(program, path) {
+        Node::Block(block) => block,
+        Node::Term(_) => unreachable!(),
+    }
+}
+
+fn block_at_mut<'a, Tag>(program: &'a mut TaggedProgram<Tag>,
+                         path: &[usize]) -> &'a mut TaggedBlock<Tag> {
+    match node_at_mut(program, path) {
+        NodeMut::Block(block) => block,
+        NodeMut::Term(_) => unreachable!(),
+    }
+}
+
+fn term_at<'a, Tag>(program: &'a TaggedProgram<Tag>, path: &[usize]) -> &'a TaggedTerm<Tag> {
+    match node_at(program, path) {
+        Node::Term(term) => term,
+        Node::Block(_) => unreachable!(),
+    }
+}
+
+fn term_at_mut<'a, Tag>(program: &'a mut TaggedProgram<Tag>,
+                        path: &[usize]) -> &'a mut TaggedTerm<Tag> {
+    match node_at_mut(program, path) {
+        NodeMut::Term(term) => term,
+        NodeMut::Block(_) => unreachable!(),
+    }
+}
+
+// The blocks and terms right inside `node`, in evaluation order.
+// `children_mut` must number them the same way.
+fn children<'a, Tag>(node: Node<'a, Tag>) -> Vec<Node<'a, Tag>> {
+    let term = match node {
+        Node::Block(block) => {
+            let mut children: Vec<Node<Tag>> =
+                block.stmts.iter().filter_map(stmt_term).map(Node::Term).collect();
+            if let Some(ref end) = *block.end {
+                children.push(Node::Term(end));
+            }
+            return children;
+        }
+        Node::Term(term) => term,
+    };
+    match *term {
+        Literal(..) | Var(..) | BoolLiteral(..) | SuffixedLiteral(..) | FloatLiteral(..) |
+        CharLit(..) | StrLit(..) | Null(..) => Vec::new(),
+        Infix(_, ref left, _, ref right) | PtrEq(_, ref left, ref right) |
+        Index(_, ref left, ref right) => vec![Node::Term(left), Node::Term(right)],
+        Call(_, _, ref terms) | Tuple(_, ref terms) | ArrayLit(_, ref terms) |
+        Variant(_, _, _, ref terms) => terms.iter().map(Node::Term).collect(),
+        Scope(_, ref block) | Lambda(_, _, ref block) => vec![Node::Block(block)],
+        Apply(_, ref callee, ref args) | MethodCall(_, ref callee, _, ref args) => {
+            let mut children = vec![Node::Term(callee)];
+            children.extend(args.iter().map(Node::Term));
+            children
+        }
+        If(_, ref cond, ref if_true, ref if_false) => {
+            let mut children = vec![Node::Term(cond), Node::Term(if_true)];
+            if let Some(ref if_false) = *if_false {
+                children.push(Node::Term(if_false));
+            }
+            children
+        }
+        While(_, ref cond, ref block) => vec![Node::Term(cond), Node::Block(block)],
+        Stmt(ref stmt) => stmt_term(stmt).into_iter().map(Node::Term).collect(),
+        Cast(_, ref term, _) | Project(_, ref term, _) | Field(_, ref term, _) |
+        Ascribe(_, ref term, _) | Borrow(_, ref term) | Deref(_, ref term) |
+        BorrowMut(_, ref term) | Neg(_, ref term) | Not(_, ref term) => vec![Node::Term(term)],
+        Match(_, ref scrutinee, ref arms) => {
+            let mut children = vec![Node::Term(scrutinee)];
+            children.extend(arms.iter().map(|&(_, ref body)| Node::Term(body)));
+            children
+        }
+        StructLit(_, _, ref inits) => inits.iter().map(|&(_, ref term)| Node::Term(term)).collect(),
+    }
+}
+
+fn children_mut<'a, Tag>(node: NodeMut<'a, Tag>) -> Vec<NodeMut<'a, Tag>> {
+    let term = match node {
+        NodeMut::Block(block) => {
+            let mut children: Vec<NodeMut<Tag>> =
+                block.stmts.iter_mut().filter_map(stmt_term_mut).map(NodeMut::Term).collect();
+            if let Some(ref mut end) = *block.end {
+                children.push(NodeMut::Term(end));
+            }
+            return children;
+        }
+        NodeMut::Term(term) => term,
+    };
+    match *term {
+        Literal(..) | Var(..) | BoolLiteral(..) | SuffixedLiteral(..) | FloatLiteral(..) |
+        CharLit(..) | StrLit(..) | Null(..) => Vec::new(),
+        Infix(_, ref mut left, _, ref mut right) | PtrEq(_, ref mut left, ref mut right) |
+        Index(_, ref mut left, ref mut right) => vec![NodeMut::Term(left), NodeMut::Term(right)],
+        Call(_, _, ref mut terms) | Tuple(_, ref mut terms) | ArrayLit(_, ref mut terms) |
+        Variant(_, _, _, ref mut terms) => terms.iter_mut().map(NodeMut::Term).collect(),
+        Scope(_, ref mut block) | Lambda(_, _, ref mut block) => vec![NodeMut::Block(block)],
+        Apply(_, ref mut callee, ref mut args) |
+        MethodCall(_, ref mut callee, _, ref mut args) => {
+            let mut children = vec![NodeMut::Term(callee)];
+            children.extend(args.iter_mut().map(NodeMut::Term));
+            children
+        }
+        If(_, ref mut cond, ref mut if_true, ref mut if_false) => {
+            let mut children = vec![NodeMut::Term(cond), NodeMut::Term(if_true)];
+            if let Some(ref mut if_false) = *if_false {
+                children.push(NodeMut::Term(if_false));
+            }
+            children
+        }
+        While(_, ref mut cond, ref mut block) => vec![NodeMut::Term(cond), NodeMut::Block(block)],
+        Stmt(ref mut stmt) => stmt_term_mut(stmt).into_iter().map(NodeMut::Term).collect(),
+        Cast(_, ref mut term, _) | Project(_, ref mut term, _) | Field(_, ref mut term, _) |
+        Ascribe(_, ref mut term, _) | Borrow(_, ref mut term) | Deref(_, ref mut term) |
+        BorrowMut(_, ref mut term) | Neg(_, ref mut term) | Not(_, ref mut term) =>
+            vec![NodeMut::Term(term)],
+        Match(_, ref mut scrutinee, ref mut arms) => {
+            let mut children = vec![NodeMut::Term(scrutinee)];
+            children.extend(arms.iter_mut().map(|&mut (_, ref mut body)| NodeMut::Term(body)));
+            children
+        }
+        StructLit(_, _, ref mut inits) =>
+            inits.iter_mut().map(|&mut (_, ref mut term)| NodeMut::Term(term)).collect(),
+    }
+}
+
+fn stmt_term<Tag>(stmt: &TaggedStatement<Tag>) -> Option<&TaggedTerm<Tag>> {
+    use type_check::TaggedStatement::*;
+    match *stmt {
+        TermSemicolon(_, ref term) => Some(term),
+        Let(_, _, _, ref rhs) => Some(rhs),
+        LetMut(_, _, _, ref rhs) => Some(rhs),
+        Mutate(_, _, ref rhs) | MutateThrough(_, _, ref rhs) => Some(rhs),
+        Extern(..) | Declare(..) | StructDecl(..) | EnumDecl(..) | TypeAlias(..) => None,
+    }
+}
+
+fn stmt_term_mut<Tag>(stmt: &mut TaggedStatement<Tag>) -> Option<&mut TaggedTerm<Tag>> {
+    use type_check::TaggedStatement::*;
+    match *stmt {
+        TermSemicolon(_, ref mut term) => Some(term),
+        Let(_, _, _, ref mut rhs) => Some(rhs),
+        LetMut(_, _, _, ref mut rhs) => Some(rhs),
+        Mutate(_, _, ref mut rhs) | MutateThrough(_, _, ref mut rhs) => Some(rhs),
+        Extern(..) | Declare(..) | StructDecl(..) | EnumDecl(..) | TypeAlias(..) => None,
+    }
+}
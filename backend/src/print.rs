@@ -0,0 +1,112 @@
+// The prelude's `print`, which the checker and codegen handle themselves rather than calling a
+// function of that name.
+// `print("{} + {} = {}", a, b, a + b)` prints the format string with every `{}` replaced by the
+// next argument, and then a newline; `{{` and `}}` print a brace. The format string has to be a
+// literal, so that its directives can be counted when checking, and the arguments can be I32s
+// or Strs. Codegen lowers a call to one runtime call per piece instead of a varargs call.
+// A program that binds `print` itself, e.g. with an extern, calls that binding instead.
+
+use ast::Position;
+use type_check::*;
+
+pub const PRINT: &'static str = "print";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Segment {
+    Text(String),
+    // `{}`, replaced by the next argument.
+    Directive,
+}
+
+// The pieces of `format`, without empty text between them.
+pub fn parse_format(format: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().cloned()) {
+            ('{', Some('}')) => {
+                chars.next();
+                if !text.is_empty() {
+                    segments.push(Segment::Text(text.clone()));
+                    text.clear();
+                }
+                segments.push(Segment::Directive);
+            }
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                text.push(c);
+            }
+            ('{', _) | ('}', _) => {
+                return Err(format!("The format string {:?} has a {} that isn't part of {{}}, \
+                                    {{{{ or }}}}.", format, c));
+            }
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+pub fn directive_count(segments: &[Segment]) -> usize {
+    segments.iter().filter(|segment| **segment == Segment::Directive).count()
+}
+
+// The types `{}` can print.
+pub fn is_printable(ty: &Type) -> bool {
+    *ty == Type::I32Ty || *ty == Type::StrTy
+}
+
+// Whether a call to `name` is a call to the prelude's `print` in an env where `is_bound` tells
+// whether the name is bound.
+pub fn is_builtin_print(name: &str, is_bound: bool) -> bool {
+    name == PRINT && !is_bound
+}
+
+// Checks a call to `print` at `pos`. The call is tagged with (), and the function with the types
+// of the arguments it's given.
+pub fn check_print<E: TypeEnv>(pos: &Position,
+                               args: &[TaggedTerm<Position>],
+                               env: &mut E) -> Result<TaggedTerm<Type>, Vec<String>> {
+    let segments = match args.first() {
+        Some(&TaggedTerm::StrLit(_, ref format)) =>
+            try!(parse_format(format).map_err(|error| vec![error])),
+        Some(arg) => {
+            return Err(vec![format!("The format string at {} must be a string literal.",
+                                    show_position(arg.tag()))]);
+        }
+        None => {
+            return Err(vec![format!("The call to print at {} has no format string.",
+                                    show_position(pos))]);
+        }
+    };
+    let expected = directive_count(&segments);
+    let provided = args.len() - 1;
+    if expected != provided {
+        return Err(vec![format!("The format string at {} expects {} argument(s), but {} are \
+                                 provided.",
+                                show_position(args[0].tag()), expected, provided)]);
+    }
+    let mut tagged_args = Vec::new();
+    let mut errors = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        let tagged_arg = try!(arg.type_check(&mut env.clone()));
+        let ty = *tagged_arg.get_tag();
+        if i > 0 && !is_printable(&ty) {
+            errors.push(format!("The argument at {} has type {}, but print can only print I32s \
+                                 and Strs.", show_position(arg.tag()), ty));
+        }
+        tagged_args.push(tagged_arg);
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let args_types = tagged_args.iter().map(|arg| *arg.get_tag()).collect();
+    let func = TaggedFunctionCall {
+        tag: Type::FunctionTy(Vec::new(), args_types, Box::new(Type::UnitTy)),
+        name: PRINT.to_string(),
+    };
+    Ok(TaggedTerm::Call(Type::UnitTy, func, tagged_args))
+}
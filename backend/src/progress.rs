@@ -0,0 +1,144 @@
+// What compiling reports as it goes, e.g. for a UI to show the progress of a long compilation.
+// Events come in the order of the phases, and a phase that fails is never reported as finished.
+// Nothing is reported, or even made, when no callback is given.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use ast::{Operator, Position};
+use strictness::Strictness;
+use type_check::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Phase {
+    Parse,
+    Check,
+    Codegen,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProgressEvent {
+    PhaseStarted(Phase),
+    PhaseFinished(Phase),
+    // The top-level statements of main checked so far, out of all of them.
+    ItemsChecked { done: usize, total: usize },
+    // The functions built so far, out of all of them: one for each lambda, then main.
+    FunctionsEmitted { done: usize, total: usize },
+    // A pass of the current phase is about to run.
+    Pass(&'static str),
+}
+
+// The callback of `CompileOptions`. It's shared, so that the options can still be cloned and
+// sent to other threads; two are equal if they're the same callback.
+#[derive(Clone)]
+pub struct Progress(pub Arc<Fn(ProgressEvent) + Send + Sync>);
+
+impl Progress {
+    pub fn new<F: Fn(ProgressEvent) + Send + Sync + 'static>(callback: F) -> Progress {
+        Progress(Arc::new(callback))
+    }
+
+    pub fn report(&self, event: ProgressEvent) {
+        (self.0)(event);
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Progress({:p})", &*self.0)
+    }
+}
+
+impl PartialEq for Progress {
+    fn eq(&self, other: &Progress) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Progress {}
+
+impl Hash for Progress {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (&*self.0 as *const Fn(ProgressEvent) as *const u8 as usize).hash(state);
+    }
+}
+
+// Calls `progress` with the event `event` makes, if there's a callback.
+pub fn report<F: FnOnce() -> ProgressEvent>(progress: &Option<Progress>, event: F) {
+    if let Some(ref progress) = *progress {
+        progress.report(event());
+    }
+}
+
+// Checks with the bindings of another env, reporting the top-level statements checked.
+#[derive(Clone)]
+pub struct ProgressEnv<E: TypeEnv> {
+    inner: E,
+    progress: Progress,
+}
+
+impl<E: TypeEnv> ProgressEnv<E> {
+    pub fn new(inner: E, progress: Progress) -> ProgressEnv<E> {
+        ProgressEnv { inner: inner, progress: progress }
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: TypeEnv> TypeEnv for ProgressEnv<E> {
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.inner.binding(name)
+    }
+
+    fn bind(&mut self, name: String, binding: Binding) {
+        self.inner.bind(name, binding);
+    }
+
+    fn declared_type(&self, kind: TypeKind, name: &str) -> Option<&Type> {
+        self.inner.declared_type(kind, name)
+    }
+
+    fn declare_type(&mut self, kind: TypeKind, name: String, ty: Type) {
+        self.inner.declare_type(kind, name, ty);
+    }
+
+    fn operator(&self, op: Operator, left: &Type, right: &Type) -> Option<&Type> {
+        self.inner.operator(op, left, right)
+    }
+
+    fn add_operator(&mut self, op: Operator, left: Type, right: Type, result: Type) {
+        self.inner.add_operator(op, left, right, result);
+    }
+
+    fn used(&self, name: &str, binding: &Binding, position: &Position) {
+        self.inner.used(name, binding, position);
+    }
+
+    fn checked_item(&self, done: usize, total: usize) {
+        self.progress.report(ProgressEvent::ItemsChecked { done: done, total: total });
+        self.inner.checked_item(done, total);
+    }
+
+    fn strictness(&self) -> Strictness {
+        self.inner.strictness()
+    }
+
+    fn warn(&self, warning: String) {
+        self.inner.warn(warning);
+    }
+
+    fn unify(&self, left: &Type, right: &Type) -> bool {
+        self.inner.unify(left, right)
+    }
+
+    fn zonk(&self, ty: &Type) -> Type {
+        self.inner.zonk(ty)
+    }
+
+    fn fresh_meta(&self, origin: String) -> Option<Type> {
+        self.inner.fresh_meta(origin)
+    }
+}
@@ -0,0 +1,337 @@
+// Interval analysis over I32 terms.
+// Every term gets a range its value is guaranteed to lie in; anything the analysis can't see
+// through, like calls and mutable variables, gets the full range.
+// Nodes are numbered in preorder like in `trace`: blocks, statements and terms, except that a
+// statement in term position is numbered once.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::i32;
+
+use ast::{Operator, Pattern};
+use codegen::Map;
+use semantics;
+use trace::NodeId;
+use type_check::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Range {
+    pub lo: i32,
+    pub hi: i32,
+}
+
+impl Range {
+    pub fn full() -> Range {
+        Range { lo: i32::MIN, hi: i32::MAX }
+    }
+
+    pub fn singleton(i: i32) -> Range {
+        Range { lo: i, hi: i }
+    }
+
+    pub fn contains(&self, i: i32) -> bool {
+        self.lo <= i && i <= self.hi
+    }
+
+    fn join(&self, other: &Range) -> Range {
+        Range {
+            lo: if self.lo < other.lo { self.lo } else { other.lo },
+            hi: if self.hi > other.hi { self.hi } else { other.hi },
+        }
+    }
+}
+
+pub type RangeMap = HashMap<NodeId, Range>;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RangeWarning {
+    // The operation overflows whatever the values of its operands are.
+    Overflow(NodeId, Operator),
+    DivisionByZero(NodeId),
+}
+
+struct Analysis {
+    next_id: usize,
+    ranges: RangeMap,
+    warnings: Vec<RangeWarning>,
+}
+
+impl Analysis {
+    fn fresh(&mut self) -> NodeId {
+        let id = NodeId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    fn block(&mut self, block: &TaggedBlock<Type>, env: &mut Map<Range>) -> Range {
+        self.fresh();
+        for stmt in &block.stmts {
+            self.statement(stmt, env);
+        }
+        match *block.end {
+            Some(ref term) => self.term(term, env),
+            None => Range::full(),
+        }
+    }
+
+    fn statement(&mut self, stmt: &TaggedStatement<Type>, env: &mut Map<Range>) {
+        use type_check::TaggedStatement::*;
+        self.fresh();
+        match *stmt {
+            TermSemicolon(_, ref term) => {
+                self.term(term, &mut env.clone());
+            }
+            Let(_, ref name, _, ref rhs) => {
+                let range = self.term(rhs, &mut env.clone());
+                env.insert(name.clone(), range);
+            }
+            LetMut(_, ref name, _, ref rhs) => {
+                self.term(rhs, &mut env.clone());
+                env.insert(name.clone(), Range::full());
+            }
+            Mutate(_, _, ref rhs) => {
+                self.term(rhs, &mut env.clone());
+            }
+            MutateThrough(_, ref target, ref rhs) => {
+                self.term(target, &mut env.clone());
+                self.term(rhs, &mut env.clone());
+            }
+            Extern(_, ref name, _) | Declare(_, ref name, _) => {
+                env.insert(name.clone(), Range::full());
+            }
+            StructDecl(..) | EnumDecl(..) | TypeAlias(..) => {}
+        }
+    }
+
+    fn term(&mut self, term: &TaggedTerm<Type>, env: &mut Map<Range>) -> Range {
+        use type_check::TaggedTerm::*;
+        if let Stmt(ref stmt) = *term {
+            self.statement(stmt, &mut env.clone());
+            return Range::full();
+        }
+        let id = self.fresh();
+        let range = match *term {
+            Literal(ref ty, i) if *ty == Type::I32Ty => Range::singleton(i),
+            // Not I32s.
+            Literal(..) | BoolLiteral(..) | SuffixedLiteral(..) | FloatLiteral(..) | CharLit(..) |
+            StrLit(..) | Null(..) => Range::full(),
+            Var(_, ref name) => env.get(name).cloned().unwrap_or(Range::full()),
+            Infix(ref ty, ref left, op, ref right) => {
+                let left = self.term(left, &mut env.clone());
+                let right = self.term(right, &mut env.clone());
+                if *ty == Type::I32Ty {
+                    self.infix(id, &left, op, &right)
+                } else {
+                    Range::full()
+                }
+            }
+            Call(_, _, ref args) => {
+                for arg in args {
+                    self.term(arg, &mut env.clone());
+                }
+                Range::full()
+            }
+            Scope(_, ref block) => self.block(block, &mut env.clone()),
+            If(_, ref cond, ref if_true, ref if_false) => {
+                self.term(cond, &mut env.clone());
+                let if_true = self.term(if_true, &mut env.clone());
+                match *if_false {
+                    Some(ref if_false) => if_true.join(&self.term(if_false, &mut env.clone())),
+                    None => if_true,
+                }
+            }
+            While(_, ref cond, ref block) => {
+                self.term(cond, &mut env.clone());
+                self.block(block, &mut env.clone());
+                Range::full()
+            }
+            Stmt(_) => unreachable!(),
+            // Casts are rarely I32 to I32, and narrowing ones wrap around.
+            Cast(_, ref term, _) => {
+                self.term(term, &mut env.clone());
+                Range::full()
+            }
+            Tuple(_, ref elems) => {
+                for elem in elems {
+                    self.term(elem, &mut env.clone());
+                }
+                Range::full()
+            }
+            Project(_, ref term, _) => {
+                self.term(term, &mut env.clone());
+                Range::full()
+            }
+            ArrayLit(_, ref elems) => {
+                for elem in elems {
+                    self.term(elem, &mut env.clone());
+                }
+                Range::full()
+            }
+            Index(_, ref array, ref index) => {
+                self.term(array, &mut env.clone());
+                self.term(index, &mut env.clone());
+                Range::full()
+            }
+            PtrEq(_, ref left, ref right) => {
+                self.term(left, &mut env.clone());
+                self.term(right, &mut env.clone());
+                Range::full()
+            }
+            StructLit(_, _, ref inits) => {
+                for &(_, ref term) in inits {
+                    self.term(term, &mut env.clone());
+                }
+                Range::full()
+            }
+            Field(_, ref term, _) | Borrow(_, ref term) | Deref(_, ref term) |
+            BorrowMut(_, ref term) | Not(_, ref term) => {
+                self.term(term, &mut env.clone());
+                Range::full()
+            }
+            Variant(_, _, _, ref args) => {
+                for arg in args {
+                    self.term(arg, &mut env.clone());
+                }
+                Range::full()
+            }
+            Match(_, ref scrutinee, ref arms) => {
+                self.term(scrutinee, &mut env.clone());
+                let mut range: Option<Range> = None;
+                for &(ref pattern, ref body) in arms {
+                    let mut arm_env = env.clone();
+                    if let Pattern::Constructor(_, _, ref binds) = *pattern {
+                        for bind in binds {
+                            arm_env.insert(bind.clone(), Range::full());
+                        }
+                    }
+                    let arm = self.term(body, &mut arm_env);
+                    range = Some(match range {
+                        Some(range) => range.join(&arm),
+                        None => arm,
+                    });
+                }
+                range.unwrap_or(Range::full())
+            }
+            // The parameters could be anything.
+            Lambda(_, ref params, ref body) => {
+                let mut body_env = env.clone();
+                for &(ref name, _) in params {
+                    body_env.insert(name.clone(), Range::full());
+                }
+                self.block(body, &mut body_env);
+                Range::full()
+            }
+            Apply(_, ref callee, ref args) | MethodCall(_, ref callee, _, ref args) => {
+                self.term(callee, &mut env.clone());
+                for arg in args {
+                    self.term(arg, &mut env.clone());
+                }
+                Range::full()
+            }
+            Ascribe(_, ref term, _) => self.term(term, &mut env.clone()),
+            Neg(ref ty, ref term) => {
+                let range = self.term(term, &mut env.clone());
+                match **term {
+                    _ if *ty != Type::I32Ty => Range::full(),
+                    // Only `-2147483648` is suffixed.
+                    SuffixedLiteral(..) => Range::singleton(i32::MIN),
+                    // The negation of the minimum wraps around to itself.
+                    _ if range.lo == i32::MIN => Range::full(),
+                    _ => Range { lo: -range.hi, hi: -range.lo },
+                }
+            }
+        };
+        self.ranges.insert(id, range);
+        range
+    }
+
+    fn infix(&mut self, id: NodeId, left: &Range, op: Operator, right: &Range) -> Range {
+        let (left_lo, left_hi) = (left.lo as i64, left.hi as i64);
+        let (right_lo, right_hi) = (right.lo as i64, right.hi as i64);
+        let corners: Vec<i64> = match op {
+            // Only exact when both sides are known.
+            Operator::BitAnd | Operator::BitOr | Operator::BitXor => {
+                return match (left.lo == left.hi, right.lo == right.hi) {
+                    (true, true) =>
+                        Range::singleton(semantics::apply(op, left.lo, right.lo).unwrap()),
+                    _ => Range::full(),
+                };
+            }
+            Operator::Add => vec![left_lo + right_lo, left_hi + right_hi],
+            Operator::Sub => vec![left_lo - right_hi, left_hi - right_lo],
+            Operator::Mul => {
+                let mut corners = Vec::new();
+                for &l in &[left_lo, left_hi] {
+                    for &r in &[right_lo, right_hi] {
+                        corners.push(l * r);
+                    }
+                }
+                corners
+            }
+            Operator::Div => {
+                if *right == Range::singleton(0) {
+                    self.warnings.push(RangeWarning::DivisionByZero(id));
+                    return Range::full();
+                }
+                if right.contains(0) {
+                    return Range::full();
+                }
+                let mut corners = Vec::new();
+                for &l in &[left_lo, left_hi] {
+                    for &r in &[right_lo, right_hi] {
+                        // Truncates like `semantics::apply`.
+                        corners.push(l / r);
+                    }
+                }
+                corners
+            }
+            // Smaller than the divisor, and on the side of zero the dividend is on.
+            Operator::Rem => {
+                if *right == Range::singleton(0) {
+                    self.warnings.push(RangeWarning::DivisionByZero(id));
+                    return Range::full();
+                }
+                if right.contains(0) {
+                    return Range::full();
+                }
+                if left.lo == left.hi && right.lo == right.hi {
+                    return semantics::apply(op, left.lo, right.lo).map(Range::singleton)
+                                                                  .unwrap_or(Range::full());
+                }
+                let bound = cmp::max(right_lo.abs(), right_hi.abs()) - 1;
+                let lo = if left_lo >= 0 { 0 } else { cmp::max(left_lo, -bound) };
+                let hi = if left_hi <= 0 { 0 } else { cmp::min(left_hi, bound) };
+                vec![lo, hi]
+            }
+            // Comparisons and logical operators have type Bool, so they never get here.
+            Operator::Eq | Operator::Ne | Operator::Lt | Operator::Le | Operator::Gt |
+            Operator::Ge | Operator::And | Operator::Or => unreachable!(),
+        };
+        let lo = *corners.iter().min().unwrap();
+        let hi = *corners.iter().max().unwrap();
+        if lo > i32::MAX as i64 || hi < i32::MIN as i64 {
+            self.warnings.push(RangeWarning::Overflow(id, op));
+            Range::full()
+        } else if lo < i32::MIN as i64 || hi > i32::MAX as i64 {
+            // Some values wrap around, which could land anywhere.
+            Range::full()
+        } else {
+            Range { lo: lo as i32, hi: hi as i32 }
+        }
+    }
+}
+
+pub fn infer_ranges(block: &TaggedBlock<Type>) -> RangeMap {
+    analyze(block).0
+}
+
+// Operations that fail for every possible value of their operands.
+pub fn range_warnings(block: &TaggedBlock<Type>) -> Vec<RangeWarning> {
+    analyze(block).1
+}
+
+fn analyze(block: &TaggedBlock<Type>) -> (RangeMap, Vec<RangeWarning>) {
+    let mut analysis = Analysis { next_id: 0, ranges: HashMap::new(), warnings: Vec::new() };
+    analysis.block(block, &mut Map::new());
+    (analysis.ranges, analysis.warnings)
+}
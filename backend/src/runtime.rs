@@ -0,0 +1,135 @@
+// The support functions compiled programs can call, and how they get linked.
+// The runtime is only linked into executables whose module actually declares one of its
+// functions, so programs that don't use it stay freestanding.
+
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::Write;
+use std::process::Command;
+
+use llvm_sys::prelude::*;
+use llvm_sys::core::*;
+
+use codegen::emit_object;
+use externs::ExternSet;
+
+pub const RUNTIME_SOURCE: &'static str = r#"
+#include <stdio.h>
+#include <stdlib.h>
+
+int ende_panic(int code) {
+    fprintf(stderr, "panicked with code %d\n", code);
+    exit(101);
+}
+
+int ende_trap_div_zero(int dividend) {
+    fprintf(stderr, "attempted to divide %d by zero\n", dividend);
+    exit(101);
+}
+
+int ende_print_i32(int value) {
+    return printf("%d\n", value);
+}
+
+// The pieces `print` is lowered to, which unlike `ende_print_i32` don't end the line.
+int ende_write_i32(int value) {
+    return printf("%d", value);
+}
+
+int ende_print_str(const char *s) {
+    return fputs(s, stdout);
+}
+
+int ende_print_newline(void) {
+    return putchar('\n');
+}
+
+int ende_read_i32(void) {
+    int value = 0;
+    if (scanf("%d", &value) != 1) {
+        ende_panic(1);
+    }
+    return value;
+}
+"#;
+
+// The declarations matching `RUNTIME_SOURCE`, for hosts that want them in scope.
+pub fn runtime_extern_set() -> ExternSet {
+    use type_check::Type::*;
+    let to_i32 = |args_types| FunctionTy(Vec::new(), args_types, Box::new(I32Ty));
+    let i32_to_i32 = to_i32(vec![I32Ty]);
+    ExternSet {
+        name: "runtime".to_string(),
+        decls: vec![
+            ("ende_panic".to_string(), i32_to_i32.clone()),
+            ("ende_trap_div_zero".to_string(), i32_to_i32.clone()),
+            ("ende_print_i32".to_string(), i32_to_i32),
+            ("ende_read_i32".to_string(), to_i32(Vec::new())),
+            ("ende_write_i32".to_string(), to_i32(vec![I32Ty])),
+            ("ende_print_str".to_string(), to_i32(vec![StrTy])),
+            ("ende_print_newline".to_string(), to_i32(Vec::new())),
+        ],
+    }
+}
+
+fn is_runtime_symbol(name: &str) -> bool {
+    runtime_extern_set().decls.iter().any(|&(ref symbol, _)| symbol == name)
+}
+
+// The runtime functions the module declares without defining.
+pub unsafe fn referenced_runtime_symbols(module: LLVMModuleRef) -> Vec<String> {
+    let mut symbols = Vec::new();
+    let mut func = LLVMGetFirstFunction(module);
+    while !func.is_null() {
+        if LLVMIsDeclaration(func) != 0 {
+            let name = CStr::from_ptr(LLVMGetValueName(func)).to_string_lossy().into_owned();
+            if is_runtime_symbol(&name) {
+                symbols.push(name);
+            }
+        }
+        func = LLVMGetNextFunction(func);
+    }
+    symbols
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LinkOptions {
+    // For freestanding targets; referring to a runtime function is then an error.
+    pub no_runtime: bool,
+}
+
+// Links the object emitted for the module into an executable, adding the runtime if the
+// module needs it.
+pub unsafe fn emit_exe_with(module: LLVMModuleRef,
+                            output: String,
+                            options: &LinkOptions) -> Result<(), Vec<String>> {
+    let symbols = referenced_runtime_symbols(module);
+    if options.no_runtime && !symbols.is_empty() {
+        return Err(
+            symbols.iter()
+                   .map(|symbol| format!("{} is part of the runtime, \
+                                          but the runtime isn't linked.", symbol))
+                   .collect()
+        );
+    }
+    let o = try!(emit_object(output.clone()));
+    let mut gcc = Command::new("gcc");
+    gcc.arg("-o").arg(output.clone()).arg(o);
+    if !symbols.is_empty() {
+        let mut runtime = output.clone();
+        runtime.push_str(".runtime.c");
+        try!(File::create(&runtime).and_then(|mut file| file.write_all(RUNTIME_SOURCE.as_bytes()))
+                                   .map_err(|err| vec![format!("Failed to write {}: {}",
+                                                                runtime, err)]));
+        gcc.arg(runtime);
+    }
+    // Externs like `sqrt` come from libm.
+    gcc.arg("-lm");
+    let gcc_output = try!(gcc.output()
+                             .map_err(|err| vec![format!("Failed to execute gcc: {}", err)]));
+    if gcc_output.status.success() {
+        Ok(())
+    } else {
+        Err(vec![String::from_utf8_lossy(&*gcc_output.stderr).into_owned()])
+    }
+}
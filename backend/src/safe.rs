@@ -0,0 +1,49 @@
+// The safe subset of the language, for running untrusted programs.
+// Without externs, loops and assignments, a program always terminates and has no side effects.
+
+use type_check::*;
+use visit::*;
+
+struct SafeSubset<'a> {
+    allowed_externs: &'a [String],
+    errors: Vec<String>,
+}
+
+impl<'a, Tag> Visitor<Tag> for SafeSubset<'a> {
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Tag>) {
+        use type_check::TaggedStatement::*;
+        match *stmt {
+            Extern(_, ref name, _) if !self.allowed_externs.contains(name) => self.errors.push(
+                format!("`extern` declarations are not allowed in safe mode, \
+                         but {} is declared.", name)
+            ),
+            Mutate(_, ref name, _) => self.errors.push(
+                format!("Assignments are not allowed in safe mode, but {} is assigned.", name)
+            ),
+            _ => {}
+        }
+        walk_statement(self, stmt);
+    }
+
+    fn visit_term(&mut self, term: &TaggedTerm<Tag>) {
+        if let TaggedTerm::While(..) = *term {
+            self.errors.push("`while` loops are not allowed in safe mode.".to_string());
+        }
+        walk_term(self, term);
+    }
+}
+
+// Refuses everything outside the safe subset if the options ask for safe mode.
+pub fn check_safe_subset<Tag>(program: &TaggedProgram<Tag>,
+                              options: &CheckOptions) -> Result<(), Vec<String>> {
+    if !options.safe_mode {
+        return Ok(());
+    }
+    let mut safe_subset = SafeSubset { allowed_externs: &options.safe_externs, errors: Vec::new() };
+    safe_subset.visit_program(program);
+    if safe_subset.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(safe_subset.errors)
+    }
+}
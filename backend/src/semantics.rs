@@ -0,0 +1,93 @@
+// The evaluation order and the arithmetic of the language.
+// Codegen, and any other backend, must agree with these; tests/testcase_evaluation_order
+// observes the order through the side effects of externs.
+// This is the reference for everything that computes on values outside of compiled code, though
+// `apply`, `compare` and `logic` only cover I32 and Bool.
+
+use ast::Operator;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Order {
+    LeftToRight,
+}
+
+pub const CALL_ARGUMENTS: Order = Order::LeftToRight;
+pub const INFIX_OPERANDS: Order = Order::LeftToRight;
+pub const BLOCK_STATEMENTS: Order = Order::LeftToRight;
+
+// The condition of an if is evaluated before either branch, and the condition of a while before
+// every iteration of its body, including the first one.
+pub const CONDITION_BEFORE_BODY: bool = true;
+
+// The right-hand side of an assignment is evaluated before the variable is written, so it still
+// sees the old value.
+pub const RHS_BEFORE_STORE: bool = true;
+
+// Integer arithmetic wraps around on overflow at the width of its type, so `0u32 - 1u32` is
+// 4294967295. Negation wraps the same way, but a negated literal is checked as the negative
+// number, so the minimum value can be written `-2147483648`; it and the other boundary values
+// compute the same everywhere.
+pub const INTEGERS_WRAP: bool = true;
+
+// F64 arithmetic is IEEE 754 double arithmetic, rounding to nearest, so dividing by zero gives an
+// infinity or NaN.
+pub const F64_IS_IEEE_754: bool = true;
+
+// A cast to a narrower type keeps the low bits, so `300 as I8` is 44; a cast to a wider type
+// keeps the value, and a cast between I32 and U32 keeps the bits.
+// A cast from F64 to an integer type rounds toward zero, and is undefined for a float out of the
+// type's range; a cast to F64 rounds to the nearest float.
+pub const NARROWING_CASTS_KEEP_LOW_BITS: bool = true;
+
+// Indexing an array with an index outside of it, negative ones included, traps at runtime; an
+// out-of-bounds literal index is rejected by the checker instead.
+pub const OUT_OF_BOUNDS_INDEX_TRAPS: bool = true;
+
+// Signed division truncates toward zero like in C, so `-7 / 2` is -3 and `7 / -2` is -3. The
+// result of `%` takes the sign of the dividend, so `-7 % 2` is -1 and `7 % -2` is 1. Both are
+// unsigned on U32.
+// Division or remainder by zero, and of the minimum value by -1, have no value: compiled code
+// traps or is undefined there, so this has none either and nothing may fold them ahead of time.
+pub fn apply(op: Operator, left: i32, right: i32) -> Option<i32> {
+    match op {
+        Operator::Add => Some(left.wrapping_add(right)),
+        Operator::Sub => Some(left.wrapping_sub(right)),
+        Operator::Mul => Some(left.wrapping_mul(right)),
+        Operator::Div => left.checked_div(right),
+        Operator::Rem => left.checked_rem(right),
+        Operator::BitAnd => Some(left & right),
+        Operator::BitOr => Some(left | right),
+        Operator::BitXor => Some(left ^ right),
+        Operator::Eq | Operator::Ne | Operator::Lt | Operator::Le | Operator::Gt |
+        Operator::Ge | Operator::And | Operator::Or => None,
+    }
+}
+
+// Comparisons on U32 are unsigned. On F64, every comparison with a NaN is false except !=, which
+// is true. Two enum values are equal when they're the same variant and their payloads are equal.
+pub fn compare(op: Operator, left: i32, right: i32) -> Option<bool> {
+    match op {
+        Operator::Eq => Some(left == right),
+        Operator::Ne => Some(left != right),
+        Operator::Lt => Some(left < right),
+        Operator::Le => Some(left <= right),
+        Operator::Gt => Some(left > right),
+        Operator::Ge => Some(left >= right),
+        Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Rem |
+        Operator::And | Operator::Or | Operator::BitAnd | Operator::BitOr |
+        Operator::BitXor => None,
+    }
+}
+
+// `&&` and `||` evaluate both of their operands for now, in the order of `INFIX_OPERANDS`.
+pub fn logic(op: Operator, left: bool, right: bool) -> Option<bool> {
+    match op {
+        Operator::And => Some(left && right),
+        Operator::Or => Some(left || right),
+        _ => None,
+    }
+}
+
+// The value of main is the exit code of the program: main has type I32, or Unit, which exits
+// with 0. The code is kept as a whole i32 until the process exits, where a Unix OS keeps only
+// its lowest 8 bits.
@@ -0,0 +1,124 @@
+// Rules that reject programs the checker used to accept can be relaxed, so that old programs
+// keep compiling while they're migrated one rule at a time.
+// Each such rule is enforced through `gate` at the one place it's checked. In legacy mode the
+// error becomes a warning that names the code of the error strict mode reports.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ast::{Operator, Position};
+use type_check::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Strictness {
+    Legacy,
+    Strict,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StrictRule {
+    // `main` must have type I32 or Unit.
+    MainType,
+    // Externs must be declared at the top level of main.
+    ExternPlacement,
+    // Conditions must have type Bool; legacy mode still takes I32 ones, true when not zero.
+    BoolCondition,
+}
+
+impl StrictRule {
+    pub fn code(&self) -> &'static str {
+        use self::StrictRule::*;
+        match *self {
+            MainType => "E0001",
+            ExternPlacement => "E0002",
+            BoolCondition => "E0003",
+        }
+    }
+}
+
+// Reports `messages` as errors of `rule`, or only warns about them in legacy mode.
+pub fn gate<E: TypeEnv>(env: &E,
+                        rule: StrictRule,
+                        messages: Vec<String>) -> Result<(), Vec<String>> {
+    if messages.is_empty() {
+        return Ok(());
+    }
+    match env.strictness() {
+        Strictness::Strict => {
+            Err(messages.iter()
+                        .map(|message| format!("error[{}]: {}", rule.code(), message))
+                        .collect())
+        }
+        Strictness::Legacy => {
+            for message in messages {
+                env.warn(format!("warning: this is error {} in strict mode: {}",
+                                 rule.code(), message));
+            }
+            Ok(())
+        }
+    }
+}
+
+// Checks in legacy mode with the bindings of another env, collecting the warnings.
+#[derive(Clone)]
+pub struct LegacyEnv<E: TypeEnv> {
+    inner: E,
+    // Shared by all the copies made while checking.
+    warnings: Rc<RefCell<Vec<String>>>,
+}
+
+impl<E: TypeEnv> LegacyEnv<E> {
+    pub fn new(inner: E) -> LegacyEnv<E> {
+        LegacyEnv { inner: inner, warnings: Rc::new(RefCell::new(Vec::new())) }
+    }
+
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.borrow().clone()
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: TypeEnv> TypeEnv for LegacyEnv<E> {
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.inner.binding(name)
+    }
+
+    fn bind(&mut self, name: String, binding: Binding) {
+        self.inner.bind(name, binding);
+    }
+
+    fn declared_type(&self, kind: TypeKind, name: &str) -> Option<&Type> {
+        self.inner.declared_type(kind, name)
+    }
+
+    fn declare_type(&mut self, kind: TypeKind, name: String, ty: Type) {
+        self.inner.declare_type(kind, name, ty);
+    }
+
+    fn operator(&self, op: Operator, left: &Type, right: &Type) -> Option<&Type> {
+        self.inner.operator(op, left, right)
+    }
+
+    fn add_operator(&mut self, op: Operator, left: Type, right: Type, result: Type) {
+        self.inner.add_operator(op, left, right, result);
+    }
+
+    fn used(&self, name: &str, binding: &Binding, position: &Position) {
+        self.inner.used(name, binding, position);
+    }
+
+    fn checked_item(&self, done: usize, total: usize) {
+        self.inner.checked_item(done, total);
+    }
+
+    fn strictness(&self) -> Strictness {
+        Strictness::Legacy
+    }
+
+    fn warn(&self, warning: String) {
+        self.warnings.borrow_mut().push(warning);
+    }
+}
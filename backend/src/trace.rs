@@ -0,0 +1,283 @@
+// Typing derivations, for explaining why the checker gave a term its type.
+// The judgments are read off the checked tree afterwards, so checking without a trace costs
+// nothing extra.
+
+use ast::Position;
+use codegen::Map;
+use type_check::*;
+use validate::show_tag;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(pub usize);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Judgment {
+    pub node: NodeId,
+    pub position: Position,
+    pub rule: &'static str,
+    pub inputs: Vec<Type>,
+    pub output: Type,
+    // The judgments this one is derived from.
+    pub premises: Vec<NodeId>,
+}
+
+struct Tracer {
+    judgments: Vec<Judgment>,
+}
+
+impl Tracer {
+    // Reserves an id in preorder; the judgment is filled in once its premises are known.
+    fn reserve(&mut self, position: &Position) -> NodeId {
+        let id = NodeId(self.judgments.len());
+        self.judgments.push(Judgment {
+            node: id,
+            position: position.clone(),
+            rule: "",
+            inputs: Vec::new(),
+            output: Type::Forbidden,
+            premises: Vec::new(),
+        });
+        id
+    }
+
+    fn fill(&mut self,
+            id: NodeId,
+            rule: &'static str,
+            premises: Vec<NodeId>,
+            output: Type) {
+        let inputs = premises.iter().map(|premise| self.judgments[premise.0].output.clone())
+                                    .collect();
+        let judgment = &mut self.judgments[id.0];
+        judgment.rule = rule;
+        judgment.inputs = inputs;
+        judgment.output = output;
+        judgment.premises = premises;
+    }
+
+    fn block(&mut self, block: &TaggedBlock<Position>, typed: &TaggedBlock<Type>) -> NodeId {
+        let id = self.reserve(&block.tag);
+        let mut premises: Vec<NodeId> =
+            block.stmts.iter().zip(&typed.stmts)
+                              .map(|(stmt, typed_stmt)| self.statement(stmt, typed_stmt))
+                              .collect();
+        let rule = match (&*block.end, &*typed.end) {
+            (&Some(ref end), &Some(ref typed_end)) => {
+                premises.push(self.term(end, typed_end));
+                "Block-end"
+            }
+            _ => "Block-unit",
+        };
+        self.fill(id, rule, premises, typed.tag.clone());
+        id
+    }
+
+    fn statement(&mut self,
+                 stmt: &TaggedStatement<Position>,
+                 typed: &TaggedStatement<Type>) -> NodeId {
+        use type_check::TaggedStatement::*;
+        let id = match *stmt {
+            TermSemicolon(ref pos, _) | Let(ref pos, _, _, _) | LetMut(ref pos, _, _, _) |
+            Mutate(ref pos, _, _) | Extern(ref pos, _, _) | Declare(ref pos, _, _) |
+            StructDecl(ref pos, _, _) | EnumDecl(ref pos, _, _) | TypeAlias(ref pos, _, _) |
+            MutateThrough(ref pos, _, _) => self.reserve(pos),
+        };
+        let (rule, premises) = match (stmt, typed) {
+            (&TermSemicolon(_, ref term), &TermSemicolon(_, ref typed_term)) =>
+                ("TermSemicolon", vec![self.term(term, typed_term)]),
+            (&Let(_, _, _, ref rhs), &Let(_, _, _, ref typed_rhs)) =>
+                ("Let", vec![self.term(rhs, typed_rhs)]),
+            (&LetMut(_, _, _, ref rhs), &LetMut(_, _, _, ref typed_rhs)) =>
+                ("LetMut", vec![self.term(rhs, typed_rhs)]),
+            (&Mutate(_, _, ref rhs), &Mutate(_, _, ref typed_rhs)) =>
+                ("Mutate", vec![self.term(rhs, typed_rhs)]),
+            (&Extern(..), _) => ("Extern", Vec::new()),
+            (&Declare(..), _) => ("Declare", Vec::new()),
+            (&StructDecl(..), _) => ("StructDecl", Vec::new()),
+            (&EnumDecl(..), _) => ("EnumDecl", Vec::new()),
+            (&TypeAlias(..), _) => ("TypeAlias", Vec::new()),
+            (&MutateThrough(_, ref target, ref rhs),
+             &MutateThrough(_, ref typed_target, ref typed_rhs)) => {
+                let premises = vec![self.term(target, typed_target), self.term(rhs, typed_rhs)];
+                ("MutateThrough", premises)
+            }
+            _ => unreachable!(),
+        };
+        self.fill(id, rule, premises, *typed.get_tag());
+        id
+    }
+
+    fn term(&mut self, term: &TaggedTerm<Position>, typed: &TaggedTerm<Type>) -> NodeId {
+        use type_check::TaggedTerm::*;
+        let id = match *term {
+            Stmt(_) => None,
+            Literal(ref pos, _) | Var(ref pos, _) | Infix(ref pos, _, _, _) |
+            Call(ref pos, _, _) | Scope(ref pos, _) | If(ref pos, _, _, _) |
+            While(ref pos, _, _) | BoolLiteral(ref pos, _) | SuffixedLiteral(ref pos, _, _) |
+            Cast(ref pos, _, _) | FloatLiteral(ref pos, _) | CharLit(ref pos, _) |
+            StrLit(ref pos, _) | Tuple(ref pos, _) | Project(ref pos, _, _) |
+            ArrayLit(ref pos, _) | Index(ref pos, _, _) | StructLit(ref pos, _, _) |
+            Field(ref pos, _, _) | Variant(ref pos, _, _, _) | Match(ref pos, _, _) |
+            Borrow(ref pos, _) | Deref(ref pos, _) | BorrowMut(ref pos, _) | Null(ref pos) |
+            PtrEq(ref pos, _, _) | Lambda(ref pos, _, _) | Apply(ref pos, _, _) |
+            Ascribe(ref pos, _, _) | MethodCall(ref pos, _, _, _) | Neg(ref pos, _) |
+            Not(ref pos, _) => Some(self.reserve(pos)),
+        };
+        let (rule, premises) = match (term, typed) {
+            (&Literal(..), _) | (&BoolLiteral(..), _) | (&SuffixedLiteral(..), _) |
+            (&FloatLiteral(..), _) | (&CharLit(..), _) | (&StrLit(..), _) | (&Null(..), _) =>
+                ("Literal", Vec::new()),
+            (&Var(..), _) => ("Var-lookup", Vec::new()),
+            (&Infix(_, ref left, op, ref right), &Infix(_, ref typed_left, _, ref typed_right))
+                if op.is_comparison() =>
+                ("Compare", vec![self.term(left, typed_left), self.term(right, typed_right)]),
+            (&Infix(_, ref left, op, ref right), &Infix(_, ref typed_left, _, ref typed_right))
+                if op.is_logical() =>
+                ("Logic", vec![self.term(left, typed_left), self.term(right, typed_right)]),
+            (&Infix(_, ref left, _, ref right), &Infix(_, ref typed_left, _, ref typed_right)) =>
+                ("Infix-I32", vec![self.term(left, typed_left), self.term(right, typed_right)]),
+            (&Call(_, _, ref args), &Call(_, _, ref typed_args)) => {
+                let premises = args.iter().zip(typed_args)
+                                   .map(|(arg, typed_arg)| self.term(arg, typed_arg))
+                                   .collect();
+                ("Call", premises)
+            }
+            (&Scope(_, ref block), &Scope(_, ref typed_block)) =>
+                ("Scope", vec![self.block(block, typed_block)]),
+            (&If(_, ref cond, ref if_true, ref if_false),
+             &If(_, ref typed_cond, ref typed_true, ref typed_false)) => {
+                let mut premises = vec![self.term(cond, typed_cond),
+                                        self.term(if_true, typed_true)];
+                match (if_false, typed_false) {
+                    (&Some(ref if_false), &Some(ref typed_false)) => {
+                        premises.push(self.term(if_false, typed_false));
+                        ("If-branches-join", premises)
+                    }
+                    _ => ("If-then-Unit", premises),
+                }
+            }
+            (&While(_, ref cond, ref block), &While(_, ref typed_cond, ref typed_block)) => {
+                let premises = vec![self.term(cond, typed_cond), self.block(block, typed_block)];
+                ("While", premises)
+            }
+            (&Cast(_, ref term, _), &Cast(_, ref typed_term, _)) =>
+                ("Cast-integer", vec![self.term(term, typed_term)]),
+            (&Tuple(_, ref elems), &Tuple(_, ref typed_elems)) => {
+                let premises = elems.iter().zip(typed_elems)
+                                    .map(|(elem, typed_elem)| self.term(elem, typed_elem))
+                                    .collect();
+                ("Tuple", premises)
+            }
+            (&Project(_, ref term, _), &Project(_, ref typed_term, _)) =>
+                ("Project", vec![self.term(term, typed_term)]),
+            (&ArrayLit(_, ref elems), &ArrayLit(_, ref typed_elems)) => {
+                let premises = elems.iter().zip(typed_elems)
+                                    .map(|(elem, typed_elem)| self.term(elem, typed_elem))
+                                    .collect();
+                ("Array-elements-agree", premises)
+            }
+            (&Index(_, ref array, ref index), &Index(_, ref typed_array, ref typed_index)) => {
+                let premises = vec![self.term(array, typed_array), self.term(index, typed_index)];
+                ("Index", premises)
+            }
+            (&StructLit(_, _, ref inits), &StructLit(_, _, ref typed_inits)) => {
+                let premises = inits.iter().zip(typed_inits)
+                                    .map(|(&(_, ref term), &(_, ref typed_term))| {
+                                        self.term(term, typed_term)
+                                    })
+                                    .collect();
+                ("Struct-fields-initialized", premises)
+            }
+            (&Field(_, ref term, _), &Field(_, ref typed_term, _)) =>
+                ("Field", vec![self.term(term, typed_term)]),
+            (&Borrow(_, ref term), &Borrow(_, ref typed_term)) =>
+                ("Borrow-var", vec![self.term(term, typed_term)]),
+            (&Deref(_, ref term), &Deref(_, ref typed_term)) =>
+                ("Deref", vec![self.term(term, typed_term)]),
+            (&BorrowMut(_, ref term), &BorrowMut(_, ref typed_term)) =>
+                ("BorrowMut-var", vec![self.term(term, typed_term)]),
+            (&PtrEq(_, ref left, ref right), &PtrEq(_, ref typed_left, ref typed_right)) =>
+                ("PtrEq", vec![self.term(left, typed_left), self.term(right, typed_right)]),
+            (&Lambda(_, _, ref body), &Lambda(_, _, ref typed_body)) =>
+                ("Lambda-no-captures", vec![self.block(body, typed_body)]),
+            (&Apply(_, ref callee, ref args), &Apply(_, ref typed_callee, ref typed_args)) => {
+                let mut premises = vec![self.term(callee, typed_callee)];
+                for (arg, typed_arg) in args.iter().zip(typed_args) {
+                    premises.push(self.term(arg, typed_arg));
+                }
+                ("Apply", premises)
+            }
+            (&Ascribe(_, ref term, _), &Ascribe(_, ref typed_term, _)) =>
+                ("Ascribe", vec![self.term(term, typed_term)]),
+            (&Neg(_, ref term), &Neg(_, ref typed_term)) =>
+                ("Neg", vec![self.term(term, typed_term)]),
+            (&Not(_, ref term), &Not(_, ref typed_term)) =>
+                ("Not", vec![self.term(term, typed_term)]),
+            // The receiver is the first argument of the call it's checked as.
+            (&MethodCall(_, ref receiver, _, ref args), &Call(_, _, ref typed_args)) => {
+                let premises = Some(&**receiver).into_iter().chain(args).zip(typed_args)
+                                                .map(|(arg, typed_arg)| self.term(arg, typed_arg))
+                                                .collect();
+                ("MethodCall", premises)
+            }
+            (&Variant(_, _, _, ref args), &Variant(_, _, _, ref typed_args)) => {
+                let premises = args.iter().zip(typed_args)
+                                   .map(|(arg, typed_arg)| self.term(arg, typed_arg))
+                                   .collect();
+                ("Variant-payload-agrees", premises)
+            }
+            (&Match(_, ref scrutinee, ref arms),
+             &Match(_, ref typed_scrutinee, ref typed_arms)) => {
+                let mut premises = vec![self.term(scrutinee, typed_scrutinee)];
+                for (&(_, ref body), &(_, ref typed_body)) in arms.iter().zip(typed_arms) {
+                    premises.push(self.term(body, typed_body));
+                }
+                ("Match-arms-agree", premises)
+            }
+            // A statement in term position is its own judgment.
+            (&Stmt(ref stmt), &Stmt(ref typed_stmt)) => return self.statement(stmt, typed_stmt),
+            _ => unreachable!(),
+        };
+        let id = id.unwrap();
+        self.fill(id, rule, premises, *typed.get_tag());
+        id
+    }
+}
+
+// Checks the program like `type_check` and also records a judgment for every node, in preorder.
+pub fn type_check_traced(program: &TaggedProgram<Position>,
+                         env: &mut Env)
+                         -> Result<(TaggedProgram<Type>, Vec<Judgment>), Vec<String>> {
+    let typed = try!(program.type_check(env));
+    let mut tracer = Tracer { judgments: Vec::new() };
+    tracer.block(&program.main, &typed.main);
+    Ok((typed, tracer.judgments))
+}
+
+fn contains(pos: &Position, at: (u32, u32)) -> bool {
+    pos.start_pos <= at && at <= pos.end_pos
+}
+
+fn render_node(judgments: &[Judgment], id: NodeId, depth: usize, out: &mut String) {
+    let judgment = &judgments[id.0];
+    let inputs: Vec<String> = judgment.inputs.iter().map(show_tag).collect();
+    out.push_str(&format!("{}{}: ({}) => {} at line {}, column {}\n",
+                          "  ".repeat(depth), judgment.rule, inputs.join(", "),
+                          show_tag(&judgment.output),
+                          judgment.position.start_pos.0, judgment.position.start_pos.1));
+    for premise in &judgment.premises {
+        render_node(judgments, *premise, depth + 1, out);
+    }
+}
+
+// Renders the derivation of the innermost node spanning `at` (line, column), premises indented
+// below their conclusions.
+pub fn render_derivation(judgments: &[Judgment], at: (u32, u32)) -> Option<String> {
+    // Later judgments in preorder are nested deeper.
+    let node = match judgments.iter().rev().find(|judgment| contains(&judgment.position, at)) {
+        Some(judgment) => judgment.node,
+        None => return None,
+    };
+    let mut out = String::new();
+    render_node(judgments, node, 0, &mut out);
+    Some(out)
+}
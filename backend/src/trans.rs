@@ -110,10 +110,15 @@ impl<T1: FromHaskellRepr> FromHaskellRepr for TaggedStatement<T1> {
 
         match con_name.as_str() {
             "main:Ast.TermSemicolon" => TermSemicolon(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
-            "main:Ast.Let" => Let(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
-            "main:Ast.LetMut" => LetMut(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Let" => Let(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 3))),
+            "main:Ast.LetMut" => LetMut(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 3))),
             "main:Ast.Mutate" => Mutate(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
             "main:Ast.Extern" => Extern(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Declare" => Declare(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.StructDecl" => StructDecl(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.EnumDecl" => EnumDecl(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.TypeAlias" => TypeAlias(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.MutateThrough" => MutateThrough(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
             _ => panic!("from_haskell_repr TaggedStatement: unrecognized constructor name: {}", con_name)
         }
     }
@@ -133,6 +138,31 @@ impl<T1: FromHaskellRepr> FromHaskellRepr for TaggedTerm<T1> {
             "main:Ast.Scope" => Scope(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
             "main:Ast.If" => If(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 3))),
             "main:Ast.While" => While(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.BoolLiteral" => BoolLiteral(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.SuffixedLiteral" => SuffixedLiteral(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Cast" => Cast(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.FloatLiteral" => FloatLiteral(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.CharLit" => CharLit(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.StrLit" => StrLit(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Tuple" => Tuple(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Project" => Project(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.ArrayLit" => ArrayLit(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Index" => Index(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.StructLit" => StructLit(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Field" => Field(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Variant" => Variant(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 3))),
+            "main:Ast.Match" => Match(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Borrow" => Borrow(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Deref" => Deref(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.BorrowMut" => BorrowMut(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Null" => Null(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
+            "main:Ast.PtrEq" => PtrEq(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Lambda" => Lambda(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Apply" => Apply(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Ascribe" => Ascribe(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.MethodCall" => MethodCall(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 3))),
+            "main:Ast.Neg" => Neg(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Not" => Not(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
             _ => panic!("from_haskell_repr Term: unrecognized constructor name: {}", con_name)
         }
     }
@@ -176,10 +206,33 @@ impl FromHaskellRepr for Operator {
             "main:Ast.Sub" => Operator::Sub,
             "main:Ast.Mul" => Operator::Mul,
             "main:Ast.Div" => Operator::Div,
+            "main:Ast.Rem" => Operator::Rem,
+            "main:Ast.Eq" => Operator::Eq,
+            "main:Ast.Ne" => Operator::Ne,
+            "main:Ast.Lt" => Operator::Lt,
+            "main:Ast.Le" => Operator::Le,
+            "main:Ast.Gt" => Operator::Gt,
+            "main:Ast.Ge" => Operator::Ge,
+            "main:Ast.And" => Operator::And,
+            "main:Ast.Or" => Operator::Or,
+            "main:Ast.BitAnd" => Operator::BitAnd,
+            "main:Ast.BitOr" => Operator::BitOr,
+            "main:Ast.BitXor" => Operator::BitXor,
             _ => panic!("from_haskell_repr Operator: unrecognized constructor name: {}", name)
         }
     }
 }
+impl FromHaskellRepr for Pattern {
+    unsafe fn from_haskell_repr(i : *mut StgClosure) -> Pattern {
+        let input_ref = _UNTAG_CLOSURE(deRefStgInd(i));
+        let con_name = get_constructor_desc(input_ref);
+        match con_name.as_str() {
+            "main:Ast.Constructor" => Pattern::Constructor(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Wildcard" => Pattern::Wildcard,
+            _ => panic!("from_haskell_repr Pattern: unrecognized constructor name: {}", con_name)
+        }
+    }
+}
 impl FromHaskellRepr for Term {
     unsafe fn from_haskell_repr(i : *mut StgClosure) -> Term {
         use ast::Term::*;
@@ -192,8 +245,33 @@ impl FromHaskellRepr for Term {
             "main:Ast.Infix" => Infix(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2)))),
             "main:Ast.Call" => Call(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
             "main:Ast.Scope" => Scope(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
-            "main:Ast.If" => If(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))), Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2)))),
+            "main:Ast.If" => If(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
             "main:Ast.While" => While(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.BoolLiteral" => BoolLiteral(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
+            "main:Ast.SuffixedLiteral" => SuffixedLiteral(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Cast" => Cast(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.FloatLiteral" => FloatLiteral(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
+            "main:Ast.CharLit" => CharLit(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
+            "main:Ast.StrLit" => StrLit(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
+            "main:Ast.Tuple" => Tuple(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
+            "main:Ast.Project" => Project(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.ArrayLit" => ArrayLit(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
+            "main:Ast.Index" => Index(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)))),
+            "main:Ast.StructLit" => StructLit(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Field" => Field(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Variant" => Variant(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Match" => Match(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Borrow" => Borrow(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)))),
+            "main:Ast.Deref" => Deref(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)))),
+            "main:Ast.BorrowMut" => BorrowMut(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)))),
+            "main:Ast.Null" => Null,
+            "main:Ast.PtrEq" => PtrEq(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)))),
+            "main:Ast.Lambda" => Lambda(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Apply" => Apply(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Ascribe" => Ascribe(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.MethodCall" => MethodCall(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.Neg" => Neg(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)))),
+            "main:Ast.Not" => Not(Box::new(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)))),
             _ => panic!("from_haskell_repr Term: unrecognized constructor name: {}", con_name)
         }
     }
@@ -223,6 +301,44 @@ impl FromHaskellRepr for i32 {
     }
 }
 
+impl FromHaskellRepr for i64 {
+    unsafe fn from_haskell_repr(i : *mut StgClosure) -> i64 {
+        let input_ref = _UNTAG_CLOSURE(deRefStgInd(i));
+        let name = get_constructor_desc(input_ref);
+
+        match name.as_str() {
+            "ghc-prim:GHC.Types.I#" => get_nth_payload(input_ref, 0) as i64,
+            _ => panic!("from_haskell_repr i64: unrecognized constructor name: {}", name)
+        }
+    }
+}
+
+// A Double arrives as its bits; see `TaggedTerm::FloatLiteral`.
+impl FromHaskellRepr for u64 {
+    unsafe fn from_haskell_repr(i : *mut StgClosure) -> u64 {
+        let input_ref = _UNTAG_CLOSURE(deRefStgInd(i));
+        let name = get_constructor_desc(input_ref);
+
+        match name.as_str() {
+            "ghc-prim:GHC.Types.D#" => get_nth_payload(input_ref, 0) as u64,
+            _ => panic!("from_haskell_repr u64: unrecognized constructor name: {}", name)
+        }
+    }
+}
+
+impl FromHaskellRepr for bool {
+    unsafe fn from_haskell_repr(i : *mut StgClosure) -> bool {
+        let input_ref = _UNTAG_CLOSURE(deRefStgInd(i));
+        let name = get_constructor_desc(input_ref);
+
+        match name.as_str() {
+            "ghc-prim:GHC.Types.True" => true,
+            "ghc-prim:GHC.Types.False" => false,
+            _ => panic!("from_haskell_repr bool: unrecognized constructor name: {}", name)
+        }
+    }
+}
+
 impl FromHaskellRepr for String {
     unsafe fn from_haskell_repr(i : *mut StgClosure) -> String {
         let vec_char : Vec<char> = FromHaskellRepr::from_haskell_repr(i);
@@ -253,7 +369,22 @@ impl FromHaskellRepr for Type {
         match name.as_str() {
             "main:Ast.Forbidden" => Forbidden,
             "main:Ast.I32Ty" => I32Ty,
-            "main:Ast.FunctionTy" => FunctionTy(FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 1))),
+            "main:Ast.BoolTy" => BoolTy,
+            "main:Ast.I64Ty" => I64Ty,
+            "main:Ast.U32Ty" => U32Ty,
+            "main:Ast.I8Ty" => I8Ty,
+            "main:Ast.I16Ty" => I16Ty,
+            "main:Ast.F64Ty" => F64Ty,
+            "main:Ast.CharTy" => CharTy,
+            "main:Ast.StrTy" => StrTy,
+            "main:Ast.TupleTy" => TupleTy(FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 0))),
+            "main:Ast.ArrayTy" => ArrayTy(FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 1))),
+            "main:Ast.Named" => Named(FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 0))),
+            "main:Ast.Ref" => Ref(FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 0))),
+            "main:Ast.RefMut" => RefMut(FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 0))),
+            "main:Ast.Ptr" => Ptr,
+            "main:Ast.Hole" => Hole,
+            "main:Ast.FunctionTy" => FunctionTy(FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input, 2))),
             _ => panic!("from_haskell_repr Type: unrecognized constructor name: {}", name)
         }
     }
@@ -267,10 +398,15 @@ impl FromHaskellRepr for Statement {
 
         match con_name.as_str() {
             "main:Ast.TermSemicolon" => TermSemicolon(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0))),
-            "main:Ast.Let" => Let(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
-            "main:Ast.LetMut" => LetMut(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Let" => Let(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
+            "main:Ast.LetMut" => LetMut(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 2))),
             "main:Ast.Mutate" => Mutate(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
             "main:Ast.Extern" => Extern(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.Declare" => Declare(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.StructDecl" => StructDecl(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.EnumDecl" => EnumDecl(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.TypeAlias" => TypeAlias(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
+            "main:Ast.MutateThrough" => MutateThrough(FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 0)), FromHaskellRepr::from_haskell_repr(get_nth_payload(input_ref, 1))),
             _ => panic!("from_haskell_repr Statement: unrecognized constructor name: {}", con_name)
         }
     }
@@ -1,12 +1,215 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::fmt::Result as FmtResult;
 
 use ast::*;
+use captures::captures;
 use codegen::Map;
+use externs::check_extern_placement;
+use infer::{InferEnv, has_holes};
+use limits::check_size_limits;
+use print::{check_print, is_builtin_print};
+use safe::check_safe_subset;
+use strictness::{Strictness, StrictRule, LegacyEnv, gate};
 
+// Checking a term leaves `env` as it was.
+// Checking a statement or a block adds exactly the bindings the statements introduce themselves;
+// bindings inside nested scopes, branches and loop bodies never escape.
 pub trait TypeCheck {
     type Typed;
-    fn type_check(&self, env: &mut Map<Type>) -> Result<Self::Typed, Vec<String>>;
+    fn type_check<E: TypeEnv>(&self, env: &mut E) -> Result<Self::Typed, Vec<String>>;
+
+    // Like `type_check`, where a term of type `expected` is wanted. Only terms whose type depends
+    // on where they are, like integer literals, look at it.
+    fn type_check_against<E: TypeEnv>(&self,
+                                      env: &mut E,
+                                      _expected: &Type) -> Result<Self::Typed, Vec<String>> {
+        self.type_check(env)
+    }
+}
+
+// The bindings in scope while checking.
+// Checking clones the env at every nested scope, so cloning should be cheap.
+pub trait TypeEnv: Clone {
+    fn binding(&self, name: &str) -> Option<&Binding>;
+    fn bind(&mut self, name: String, binding: Binding);
+
+    fn get(&self, name: &str) -> Option<&Type> {
+        self.binding(name).map(|binding| &binding.ty)
+    }
+
+    // Binds a variable of the host's, like a function it provides.
+    fn insert(&mut self, name: String, ty: Type) {
+        self.bind(name, Binding { ty: ty, kind: BindingKind::Host, position: None });
+    }
+
+    // The type declared as `name` with the given kind.
+    fn declared_type(&self, kind: TypeKind, name: &str) -> Option<&Type>;
+    fn declare_type(&mut self, kind: TypeKind, name: String, ty: Type);
+
+    // The result type registered for `op` on operands of the given types.
+    fn operator(&self, op: Operator, left: &Type, right: &Type) -> Option<&Type>;
+    fn add_operator(&mut self, op: Operator, left: Type, right: Type, result: Type);
+
+    // Called with the binding each use of a variable resolves to: a read, a call, an assignment
+    // or a borrow.
+    fn used(&self, _name: &str, _binding: &Binding, _position: &Position) {}
+
+    // Called after each top-level statement of main is checked, with how many are done so far.
+    fn checked_item(&self, _done: usize, _total: usize) {}
+
+    // How the rules in `strictness` are enforced while checking in this env.
+    fn strictness(&self) -> Strictness {
+        Strictness::Strict
+    }
+
+    // Called with the warnings of the rules legacy mode relaxes.
+    fn warn(&self, _warning: String) {}
+
+    // Makes `left` and `right` the same type, solving metavariables if that's what it takes.
+    // Without inference, that's only possible if they already are, or if one of them is Never.
+    fn unify(&self, left: &Type, right: &Type) -> bool {
+        left == right || *left == Type::Never || *right == Type::Never
+    }
+
+    // `ty` with the metavariables solved so far replaced by their solutions.
+    fn zonk(&self, ty: &Type) -> Type {
+        ty.clone()
+    }
+
+    // A metavariable for a type left out, which is the type of `origin`. Only envs that infer
+    // types make them.
+    fn fresh_meta(&self, _origin: String) -> Option<Type> {
+        None
+    }
+}
+
+// What binds a variable.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BindingKind {
+    // The host, before the program is checked.
+    Host,
+    Let,
+    // `let mut`, or a declaration.
+    LetMut,
+    Extern,
+    // A parameter of a lambda.
+    Param,
+    // A pattern of a match arm.
+    Pattern,
+}
+
+impl BindingKind {
+    // Only these variables can be assigned and borrowed mutably.
+    pub fn is_mutable(self) -> bool {
+        self == BindingKind::LetMut
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub ty: Type,
+    pub kind: BindingKind,
+    // Where the program binds it; `None` for the host's variables.
+    pub position: Option<Position>,
+}
+
+impl Binding {
+    pub fn new(ty: Type, kind: BindingKind, position: &Position) -> Binding {
+        Binding { ty: ty, kind: kind, position: Some(position.clone()) }
+    }
+}
+
+// What a type is declared as. Each kind has a namespace of its own, so that e.g. a struct and an
+// alias can share a name.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TypeKind {
+    // Type aliases, and the type parameters of a generic function type inside it.
+    Alias,
+    Struct,
+    Enum,
+}
+
+// The env to check a program in: the variables in scope, the types declared by name, and the
+// operators registered at the types of their operands.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Env {
+    pub values: Map<Binding>,
+    pub types: HashMap<(TypeKind, String), Type>,
+    pub operators: HashMap<(Operator, Type, Type), Type>,
+}
+
+impl Env {
+    pub fn new() -> Env {
+        Env::default()
+    }
+}
+
+impl TypeEnv for Env {
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.values.get(name)
+    }
+
+    fn bind(&mut self, name: String, binding: Binding) {
+        self.values.insert(name, binding);
+    }
+
+    fn declared_type(&self, kind: TypeKind, name: &str) -> Option<&Type> {
+        self.types.get(&(kind, name.to_string()))
+    }
+
+    fn declare_type(&mut self, kind: TypeKind, name: String, ty: Type) {
+        self.types.insert((kind, name), ty);
+    }
+
+    fn operator(&self, op: Operator, left: &Type, right: &Type) -> Option<&Type> {
+        self.operators.get(&(op, left.clone(), right.clone()))
+    }
+
+    fn add_operator(&mut self, op: Operator, left: Type, right: Type, result: Type) {
+        self.operators.insert((op, left, right), result);
+    }
+}
+
+// A shared, read-only global env with local bindings on top.
+// Bindings go to the local layer and shadow global ones, so many checks can borrow the same
+// global env, even from several threads, without copying it.
+#[derive(Clone, Debug)]
+pub struct LayeredEnv<'a> {
+    pub global: &'a Env,
+    pub local: Env,
+}
+
+impl<'a> LayeredEnv<'a> {
+    pub fn new(global: &'a Env) -> LayeredEnv<'a> {
+        LayeredEnv { global: global, local: Env::new() }
+    }
+}
+
+impl<'a> TypeEnv for LayeredEnv<'a> {
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.local.binding(name).or_else(|| self.global.binding(name))
+    }
+
+    fn bind(&mut self, name: String, binding: Binding) {
+        self.local.bind(name, binding);
+    }
+
+    fn declared_type(&self, kind: TypeKind, name: &str) -> Option<&Type> {
+        self.local.declared_type(kind, name).or_else(|| self.global.declared_type(kind, name))
+    }
+
+    fn declare_type(&mut self, kind: TypeKind, name: String, ty: Type) {
+        self.local.declare_type(kind, name, ty);
+    }
+
+    fn operator(&self, op: Operator, left: &Type, right: &Type) -> Option<&Type> {
+        self.local.operator(op, left, right).or_else(|| self.global.operator(op, left, right))
+    }
+
+    fn add_operator(&mut self, op: Operator, left: Type, right: Type, result: Type) {
+        self.local.add_operator(op, left, right, result);
+    }
 }
 
 pub trait Tagged<Tag: Clone> {
@@ -19,7 +222,45 @@ pub enum Type {
     Forbidden,
     I32Ty,
     Enum(Enumeration),
-    FunctionTy(Vec<Type>, Box<Type>),
+    // The type parameters a generic function is instantiated with at each call, if it has any,
+    // then the argument types and the return type.
+    FunctionTy(Vec<String>, Vec<Type>, Box<Type>),
+    BoolTy,
+    I64Ty,
+    U32Ty,
+    I8Ty,
+    I16Ty,
+    F64Ty,
+    CharTy,
+    StrTy,
+    // The type of statements and of blocks without an end term.
+    UnitTy,
+    // At least two elements, none of them () or a function.
+    TupleTy(Vec<Type>),
+    // A fixed number of elements of the same type, which can't be () or a function either.
+    ArrayTy(Box<Type>, u32),
+    Struct(Structure),
+    // A type written by its name, like a type alias. The checker resolves names, so checked
+    // trees never contain one.
+    Named(String),
+    // `&I32`, which is a pointer once compiled. What it points to has to be storable.
+    Ref(Box<Type>),
+    // `&mut I32`, which can also be assigned through.
+    RefMut(Box<Type>),
+    // An opaque pointer from C, like a `FILE*`. Only externs make and use them, and they can only
+    // be compared with each other or with `null`.
+    Ptr,
+    // A type parameter of the generic function type it appears in. Calls instantiate them, so
+    // only the types of generic externs contain them.
+    Param(String),
+    // A type left out, like in `let mut x;`. Checking infers it, so checked trees never contain
+    // one.
+    Hole,
+    // A type that's still being inferred, see `infer`.
+    Meta(u32),
+    // The type of terms that never finish, like `while true {}`. Having no values, it fits
+    // wherever any other type is expected.
+    Never,
 }
 
 impl Display for Type {
@@ -29,23 +270,72 @@ impl Display for Type {
             Forbidden => unreachable!(),
             Enum(ref en) => format!("{}", en),
             I32Ty => format!("I32"),
-            FunctionTy(ref args_types, ref ret_type) => {
+            BoolTy => format!("Bool"),
+            I64Ty => format!("I64"),
+            U32Ty => format!("U32"),
+            I8Ty => format!("I8"),
+            I16Ty => format!("I16"),
+            F64Ty => format!("F64"),
+            CharTy => format!("Char"),
+            StrTy => format!("Str"),
+            UnitTy => format!("()"),
+            TupleTy(ref elems) => {
+                let elems: Vec<String> = elems.iter().map(|elem| format!("{}", elem)).collect();
+                format!("({})", elems.join(", "))
+            }
+            ArrayTy(ref elem, len) => format!("[{}; {}]", elem, len),
+            Named(ref name) => format!("{}", name),
+            Ref(ref ty) => format!("&{}", ty),
+            RefMut(ref ty) => format!("&mut {}", ty),
+            Ptr => format!("Ptr"),
+            Param(ref name) => format!("{}", name),
+            Hole | Meta(_) => format!("_"),
+            Never => format!("!"),
+            Struct(ref structure) => format!("{}", structure),
+            FunctionTy(ref params, ref args_types, ref ret_type) => {
                 let mut string = String::new();
                 for arg_ty in args_types {
                     string.push_str(&format!("{}", arg_ty));
                     string.push_str(", ")
                 }
-                format!("({}) -> {}", string, ret_type)
+                if params.is_empty() {
+                    format!("({}) -> {}", string, ret_type)
+                } else {
+                    format!("<{}>({}) -> {}", params.join(", "), string, ret_type)
+                }
             }
         };
         write!(f, "{}", ty_name)
     }
 }
 
+// A sum type, with its variants in the order they're declared. Each variant carries the values
+// of its payload types, which are empty for a variant without a payload.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Enumeration {
     pub name: String,
-    variants: Vec<String>,
+    variants: Vec<(String, Vec<Type>)>,
+}
+
+impl Enumeration {
+    pub fn new(name: String, variants: Vec<(String, Vec<Type>)>) -> Enumeration {
+        Enumeration { name: name, variants: variants }
+    }
+
+    pub fn variants(&self) -> &[(String, Vec<Type>)] {
+        &self.variants
+    }
+
+    // The index and the payload types of the variant called `name`.
+    pub fn variant(&self, name: &str) -> Option<(usize, &[Type])> {
+        self.variants.iter()
+                     .position(|&(ref variant, _)| variant == name)
+                     .map(|index| (index, &self.variants[index].1[..]))
+    }
+
+    pub fn variant_names(&self) -> Vec<&str> {
+        self.variants.iter().map(|&(ref variant, _)| &variant[..]).collect()
+    }
 }
 
 impl Display for Enumeration {
@@ -54,6 +344,230 @@ impl Display for Enumeration {
     }
 }
 
+// A struct type, with its fields in the order they're declared.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Structure {
+    pub name: String,
+    fields: Vec<(String, Type)>,
+}
+
+impl Structure {
+    pub fn new(name: String, fields: Vec<(String, Type)>) -> Structure {
+        Structure { name: name, fields: fields }
+    }
+
+    pub fn fields(&self) -> &[(String, Type)] {
+        &self.fields
+    }
+
+    // The index and the type of the field called `name`.
+    pub fn field(&self, name: &str) -> Option<(usize, &Type)> {
+        self.fields.iter()
+                   .position(|&(ref field, _)| field == name)
+                   .map(|index| (index, &self.fields[index].1))
+    }
+
+    pub fn field_names(&self) -> Vec<&str> {
+        self.fields.iter().map(|&(ref field, _)| &field[..]).collect()
+    }
+}
+
+impl Display for Structure {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.name)
+    }
+}
+
+// The operators every program can use, as (operator, left type, right type, result type):
+// arithmetic and comparisons on two numbers of the same type, `%` and bitwise operators on two
+// integers of the same type, equality on two Bools, Chars or Ptrs, and `&&` and `||` on two Bools.
+// Equality on enums is in `builtin_operator_type`, since there's no end to them.
+pub fn builtin_operators() -> Vec<(Operator, Type, Type, Type)> {
+    use ast::Operator::*;
+    let integers = [Type::I32Ty, Type::I64Ty, Type::U32Ty, Type::I8Ty, Type::I16Ty];
+    let numeric = [Type::I32Ty, Type::I64Ty, Type::U32Ty, Type::I8Ty, Type::I16Ty, Type::F64Ty];
+    let mut operators = Vec::new();
+    for &op in &[Add, Sub, Mul, Div] {
+        for ty in &numeric {
+            operators.push((op, ty.clone(), ty.clone(), ty.clone()));
+        }
+    }
+    for &op in &[Rem, BitAnd, BitOr, BitXor] {
+        for ty in &integers {
+            operators.push((op, ty.clone(), ty.clone(), ty.clone()));
+        }
+    }
+    for &op in &[Eq, Ne, Lt, Le, Gt, Ge] {
+        for ty in &numeric {
+            operators.push((op, ty.clone(), ty.clone(), Type::BoolTy));
+        }
+    }
+    for &op in &[Eq, Ne] {
+        for ty in &[Type::BoolTy, Type::CharTy, Type::Ptr] {
+            operators.push((op, ty.clone(), ty.clone(), Type::BoolTy));
+        }
+    }
+    for &op in &[And, Or] {
+        operators.push((op, Type::BoolTy, Type::BoolTy, Type::BoolTy));
+    }
+    operators
+}
+
+// Two values of the type can be compared with == and !=. Two values of the same enum can when
+// all of the payloads can.
+pub fn has_equality(ty: &Type) -> bool {
+    match *ty {
+        Type::Enum(ref enumeration) =>
+            enumeration.variants()
+                       .iter()
+                       .all(|&(_, ref payload)| payload.iter().all(has_equality)),
+        _ => builtin_operators().iter()
+                                .any(|&(op, ref left, _, _)| op == Operator::Eq && left == ty),
+    }
+}
+
+// The result type of the built-in `op` on operands of the given types, if there's one.
+pub fn builtin_operator_type(op: Operator, left: &Type, right: &Type) -> Option<Type> {
+    match (op, left) {
+        (Operator::Eq, &Type::Enum(_)) | (Operator::Ne, &Type::Enum(_)) =>
+            if left == right && has_equality(left) { Some(Type::BoolTy) } else { None },
+        _ => builtin_operators().into_iter()
+            .find(|&(builtin, ref l, ref r, _)| builtin == op && l == left && r == right)
+            .map(|(_, _, _, result)| result),
+    }
+}
+
+// The result type of `op` on operands of the given types, if it's defined: what's registered in
+// the env, or else the built-in one.
+pub fn operator_type<E: TypeEnv>(env: &E, op: Operator, left: &Type, right: &Type)
+                                 -> Option<Type> {
+    match env.operator(op, left, right) {
+        Some(result) => Some(result.clone()),
+        None => builtin_operator_type(op, left, right),
+    }
+}
+
+// Registers `operators`, replacing what's registered at the same types.
+pub fn add_operators<E: TypeEnv>(env: &mut E, operators: &[(Operator, Type, Type, Type)]) {
+    for &(op, ref left, ref right, ref result) in operators {
+        env.add_operator(op, left.clone(), right.clone(), result.clone());
+    }
+}
+
+// The enum every program can use without declaring it. Enums aren't generic, so `Some` carries
+// an I32.
+pub fn option_enum() -> Enumeration {
+    Enumeration::new("Option".to_string(),
+                     vec![("None".to_string(), Vec::new()),
+                          ("Some".to_string(), vec![Type::I32Ty])])
+}
+
+// The types every program starts with.
+pub fn prelude() -> Env {
+    let mut prelude = Env::new();
+    prelude.declare_type(TypeKind::Enum, "Option".to_string(), Type::Enum(option_enum()));
+    prelude
+}
+
+// Adds the prelude to `env`, keeping what the host already declared under the same names.
+pub fn add_prelude<E: TypeEnv>(env: &mut E) {
+    for ((kind, name), ty) in prelude().types {
+        if env.declared_type(kind, &name).is_none() {
+            env.declare_type(kind, name, ty);
+        }
+    }
+}
+
+// The types in the prelude can't be declared again, which would silently replace them.
+fn check_not_prelude_type(name: &str) -> Result<(), Vec<String>> {
+    if prelude().declared_type(TypeKind::Enum, name).is_some() {
+        Err(vec![format!("The type {} is built in, so it can't be declared again.", name)])
+    } else {
+        Ok(())
+    }
+}
+
+// Whether the innermost binding of `name` is mutable.
+pub fn is_mutable<E: TypeEnv>(env: &E, name: &str) -> bool {
+    env.binding(name).map_or(false, |binding| binding.kind.is_mutable())
+}
+
+// Whether the innermost binding of `name` is an extern. Lambdas can call externs without
+// capturing anything.
+pub fn is_extern<E: TypeEnv>(env: &E, name: &str) -> bool {
+    env.binding(name).map_or(false, |binding| binding.kind == BindingKind::Extern)
+}
+
+// Tells the env that the program uses `name` at `pos`, if it's bound.
+fn use_var<E: TypeEnv>(env: &E, name: &str, pos: &Position) {
+    if let Some(binding) = env.binding(name) {
+        env.used(name, binding, pos);
+    }
+}
+
+fn bind_var<E: TypeEnv>(env: &mut E, name: &str, ty: Type, kind: BindingKind, pos: &Position) {
+    env.bind(name.to_string(), Binding::new(ty, kind, pos));
+}
+
+// `ty` with every name in it resolved, to the type of an alias or else to a struct or an enum.
+// Aliases are resolved when they're declared, so an alias and its expansion are the same type.
+// Inside a generic function type, its type parameters shadow the other types.
+pub fn resolve_type<E: TypeEnv>(ty: &Type, env: &E) -> Result<Type, Vec<String>> {
+    use self::Type::*;
+    match *ty {
+        Named(ref name) => {
+            let found = env.declared_type(TypeKind::Alias, name)
+                           .or_else(|| env.declared_type(TypeKind::Struct, name))
+                           .or_else(|| env.declared_type(TypeKind::Enum, name));
+            match found {
+                Some(ty) => Ok(ty.clone()),
+                None => Err(vec![format!("Type {} is undeclared.", name)]),
+            }
+        }
+        TupleTy(ref elems) => Ok(TupleTy(try!(resolve_types(elems, env)))),
+        ArrayTy(ref elem, len) => Ok(ArrayTy(Box::new(try!(resolve_type(elem, env))), len)),
+        Ref(ref ty) => Ok(Ref(Box::new(try!(resolve_type(ty, env))))),
+        RefMut(ref ty) => Ok(RefMut(Box::new(try!(resolve_type(ty, env))))),
+        FunctionTy(ref params, ref args_types, ref ret_ty) => {
+            let mut inner = env.clone();
+            for (i, param) in params.iter().enumerate() {
+                if params[..i].contains(param) {
+                    return Err(vec![format!("The type parameter {} of {} is declared more than \
+                                             once.", param, ty)]);
+                }
+                inner.declare_type(TypeKind::Alias, param.clone(), Param(param.clone()));
+            }
+            let args_types = try!(resolve_types(args_types, &inner));
+            let ret_ty = try!(resolve_type(ret_ty, &inner));
+            Ok(FunctionTy(params.clone(), args_types, Box::new(ret_ty)))
+        }
+        _ => Ok(ty.clone()),
+    }
+}
+
+fn resolve_types<E: TypeEnv>(types: &[Type], env: &E) -> Result<Vec<Type>, Vec<String>> {
+    let mut resolved = Vec::new();
+    for ty in types {
+        resolved.push(try!(resolve_type(ty, env)));
+    }
+    Ok(resolved)
+}
+
+// Whether `ty` mentions the name `name`.
+fn mentions(ty: &Type, name: &str) -> bool {
+    use self::Type::*;
+    match *ty {
+        Named(ref other) => other == name,
+        TupleTy(ref elems) => elems.iter().any(|elem| mentions(elem, name)),
+        ArrayTy(ref elem, _) | Ref(ref elem) | RefMut(ref elem) => mentions(elem, name),
+        FunctionTy(ref params, ref args_types, ref ret_ty) => {
+            !params.iter().any(|param| param == name) &&
+            (args_types.iter().any(|arg_ty| mentions(arg_ty, name)) || mentions(ret_ty, name))
+        }
+        _ => false,
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TaggedFunctionCall<Tag> {
     pub tag: Tag,
@@ -62,7 +576,7 @@ pub struct TaggedFunctionCall<Tag> {
 
 impl TypeCheck for TaggedFunctionCall<Position> {
     type Typed = TaggedFunctionCall<Type>;
-    fn type_check(&self, env: &mut Map<Type>) -> Result<Self::Typed, Vec<String>> {
+    fn type_check<E: TypeEnv>(&self, env: &mut E) -> Result<Self::Typed, Vec<String>> {
         let ref name = self.name;
         let func_ty =
             try!(env.get(name).ok_or(vec![format!("Function {} is undeclared.", name)]));
@@ -96,52 +610,361 @@ pub enum TaggedTerm<Tag> {
     Infix(Tag, Box<TaggedTerm<Tag>>, Operator, Box<TaggedTerm<Tag>>),
     Call(Tag, TaggedFunctionCall<Tag>, Vec<TaggedTerm<Tag>>),
     Scope(Tag, TaggedBlock<Tag>),
-    If(Tag, Box<TaggedTerm<Tag>>, Box<TaggedTerm<Tag>>, Box<TaggedTerm<Tag>>),
+    If(Tag, Box<TaggedTerm<Tag>>, Box<TaggedTerm<Tag>>, Option<Box<TaggedTerm<Tag>>>),
     While(Tag, Box<TaggedTerm<Tag>>, TaggedBlock<Tag>),
     Stmt(Box<TaggedStatement<Tag>>),
+    BoolLiteral(Tag, bool),
+    // A literal with a suffix naming its type, like `42i64`.
+    SuffixedLiteral(Tag, i64, Type),
+    // `x as I8`. Once checked, the tag is the target type.
+    Cast(Tag, Box<TaggedTerm<Tag>>, Type),
+    // The bits of an f64, so that terms can still be compared and hashed.
+    FloatLiteral(Tag, u64),
+    CharLit(Tag, char),
+    StrLit(Tag, String),
+    Tuple(Tag, Vec<TaggedTerm<Tag>>),
+    Project(Tag, Box<TaggedTerm<Tag>>, u32),
+    ArrayLit(Tag, Vec<TaggedTerm<Tag>>),
+    // Once checked, the tag is the element type.
+    Index(Tag, Box<TaggedTerm<Tag>>, Box<TaggedTerm<Tag>>),
+    // `Point { x: 1, y: 2 }`, with the fields in the order they're written.
+    StructLit(Tag, String, Vec<(String, TaggedTerm<Tag>)>),
+    // `p.x`. Once checked, the tag is the type of the field.
+    Field(Tag, Box<TaggedTerm<Tag>>, String),
+    // `Shape::Circle(1)`, or `Shape::Empty` for a variant without a payload.
+    Variant(Tag, String, String, Vec<TaggedTerm<Tag>>),
+    // Once checked, the tag is the type every arm has.
+    Match(Tag, Box<TaggedTerm<Tag>>, Vec<(Pattern, TaggedTerm<Tag>)>),
+    // `&x`, which only borrows variables. Once checked, the tag is a reference to their type.
+    Borrow(Tag, Box<TaggedTerm<Tag>>),
+    // `*p`, which reads what a reference points to.
+    Deref(Tag, Box<TaggedTerm<Tag>>),
+    // `&mut x`, which only borrows variables bound by `let mut` or declared.
+    BorrowMut(Tag, Box<TaggedTerm<Tag>>),
+    // The null pointer, which is a Ptr.
+    Null(Tag),
+    // `p == null`, which compares two Ptrs. Once checked, the tag is Bool.
+    PtrEq(Tag, Box<TaggedTerm<Tag>>, Box<TaggedTerm<Tag>>),
+    // `fn(x: I32) { x + 1 }`, a function which can't capture variables. Once checked, the tag is
+    // a function type returning the type of the body.
+    Lambda(Tag, Vec<(String, Type)>, TaggedBlock<Tag>),
+    // A call to the value of a term, like `(fn(x: I32) { x })(1)`. Calls to names are `Call`s.
+    Apply(Tag, Box<TaggedTerm<Tag>>, Vec<TaggedTerm<Tag>>),
+    // `(1 : I64)`, a term which must have the type it's ascribed. Once checked, the tag is that
+    // type.
+    Ascribe(Tag, Box<TaggedTerm<Tag>>, Type),
+    // `x.f(1)`, which is checked as `f(x, 1)` and so becomes a `Call`.
+    MethodCall(Tag, Box<TaggedTerm<Tag>>, TaggedFunctionCall<Tag>, Vec<TaggedTerm<Tag>>),
+    // `-x`, which negates a signed number. A negated integer literal is checked as the negative
+    // number, so `-2147483648` is an I32.
+    Neg(Tag, Box<TaggedTerm<Tag>>),
+    // `!b`, which negates a Bool.
+    Not(Tag, Box<TaggedTerm<Tag>>),
+}
+
+impl<Tag> TaggedTerm<Tag> {
+    // The tag of the term, or of the statement it wraps.
+    pub fn tag(&self) -> &Tag {
+        use self::TaggedTerm::*;
+        match *self {
+            Literal(ref tag, _) | Var(ref tag, _) | Infix(ref tag, _, _, _) |
+            Call(ref tag, _, _) | Scope(ref tag, _) | If(ref tag, _, _, _) |
+            While(ref tag, _, _) | BoolLiteral(ref tag, _) | SuffixedLiteral(ref tag, _, _) |
+            Cast(ref tag, _, _) | FloatLiteral(ref tag, _) | CharLit(ref tag, _) |
+            StrLit(ref tag, _) | Tuple(ref tag, _) | Project(ref tag, _, _) |
+            ArrayLit(ref tag, _) | Index(ref tag, _, _) | StructLit(ref tag, _, _) |
+            Field(ref tag, _, _) | Variant(ref tag, _, _, _) | Match(ref tag, _, _) |
+            Borrow(ref tag, _) | Deref(ref tag, _) | BorrowMut(ref tag, _) | Null(ref tag) |
+            PtrEq(ref tag, _, _) | Lambda(ref tag, _, _) | Apply(ref tag, _, _) |
+            Ascribe(ref tag, _, _) | MethodCall(ref tag, _, _, _) | Neg(ref tag, _) |
+            Not(ref tag, _) => tag,
+            Stmt(ref stmt) => stmt.tag(),
+        }
+    }
+}
+
+// The types casts go between.
+pub fn is_integer(ty: &Type) -> bool {
+    match *ty {
+        Type::I32Ty | Type::I64Ty | Type::U32Ty | Type::I8Ty | Type::I16Ty => true,
+        _ => false,
+    }
+}
+
+// The types arithmetic works on. Both operands of an operator must have the same one.
+pub fn is_arithmetic(ty: &Type) -> bool {
+    is_integer(ty) || *ty == Type::F64Ty
+}
+
+// The types `-` negates; a U32 can't be negative.
+pub fn can_negate(ty: &Type) -> bool {
+    match *ty {
+        Type::I32Ty | Type::I64Ty | Type::I8Ty | Type::I16Ty | Type::F64Ty => true,
+        _ => false,
+    }
+}
+
+// A Char can also be cast to a numeric type, which gives its code point.
+pub fn can_cast(from: &Type, to: &Type) -> bool {
+    (is_arithmetic(from) || *from == Type::CharTy) && is_arithmetic(to)
+}
+
+// Whether a loop with the condition `cond` never stops, because it's always true. Legacy mode
+// lets a nonzero I32 literal be the condition too.
+pub fn loops_forever<Tag>(cond: &TaggedTerm<Tag>) -> bool {
+    match *cond {
+        TaggedTerm::BoolLiteral(_, b) => b,
+        TaggedTerm::Literal(_, i) => i != 0,
+        _ => false,
+    }
+}
+
+// Values of the other types have no place in memory to be stored in.
+pub fn is_storable(ty: &Type) -> bool {
+    match *ty {
+        Type::UnitTy | Type::FunctionTy(..) => false,
+        _ => true,
+    }
+}
+
+// Checks that the tuple and array types in a declared type only hold what they can.
+fn check_declared_type(ty: &Type) -> Result<(), Vec<String>> {
+    match *ty {
+        Type::TupleTy(ref elems) => {
+            for elem in elems {
+                if !is_storable(elem) {
+                    return Err(vec![format!("The tuple type {} can't hold a value of type {}.",
+                                            ty, elem)]);
+                }
+                try!(check_declared_type(elem));
+            }
+            Ok(())
+        }
+        Type::ArrayTy(ref elem, _) => {
+            if !is_storable(elem) {
+                return Err(vec![format!("The array type {} can't hold a value of type {}.",
+                                        ty, elem)]);
+            }
+            check_declared_type(elem)
+        }
+        Type::Ref(ref referent) | Type::RefMut(ref referent) => {
+            if !is_storable(referent) {
+                return Err(vec![format!("The reference type {} can't point to a value of type {}.",
+                                        ty, referent)]);
+            }
+            check_declared_type(referent)
+        }
+        Type::FunctionTy(ref params, ref args_types, ref ret_ty) => {
+            if !params.is_empty() {
+                return Err(vec![format!("The type {} is generic, but only externs can have \
+                                         generic types.", ty)]);
+            }
+            for arg_ty in args_types {
+                try!(check_declared_type(arg_ty));
+            }
+            check_declared_type(ret_ty)
+        }
+        _ => Ok(()),
+    }
+}
+
+// Whether a suffixed literal is a value of its type. U32 literals can't be negative.
+// The smallest and the largest value of an integer type.
+pub fn int_bounds(ty: &Type) -> Option<(i64, i64)> {
+    match *ty {
+        Type::I8Ty => Some((i8::min_value() as i64, i8::max_value() as i64)),
+        Type::I16Ty => Some((i16::min_value() as i64, i16::max_value() as i64)),
+        Type::I32Ty => Some((i32::min_value() as i64, i32::max_value() as i64)),
+        Type::U32Ty => Some((0, u32::max_value() as i64)),
+        Type::I64Ty => Some((i64::min_value(), i64::max_value())),
+        _ => None,
+    }
+}
+
+pub fn literal_fits(i: i64, ty: &Type) -> bool {
+    match int_bounds(ty) {
+        Some((min, max)) => i >= min && i <= max,
+        None => true,
+    }
+}
+
+pub fn show_position(pos: &Position) -> String {
+    format!("line {}, column {}", pos.start_pos.0, pos.start_pos.1)
+}
+
+// `-literal` at `pos`, where `literal` is the negated term, if it's an integer literal. An
+// unsuffixed one has the type `expected`, or else I32.
+fn check_negated_literal(pos: &Position,
+                         literal: &TaggedTerm<Position>,
+                         expected: Option<&Type>) -> Option<Result<TaggedTerm<Type>, Vec<String>>> {
+    let (i, ty) = match *literal {
+        TaggedTerm::Literal(_, i) => (i as i64, expected.cloned().unwrap_or(Type::I32Ty)),
+        TaggedTerm::SuffixedLiteral(_, i, ref ty) => (i, ty.clone()),
+        _ => return None,
+    };
+    if !can_negate(&ty) {
+        return Some(Err(vec![format!("The term at {} has type {}, which isn't a signed integer \
+                                      or an F64, so it can't be negated.",
+                                     show_position(literal.tag()), ty)]));
+    }
+    if !literal_fits(-i, &ty) {
+        let (min, max) = int_bounds(&ty).unwrap();
+        return Some(Err(vec![format!("The literal -{} at {} doesn't fit in type {}, whose \
+                                      values go from {} to {}.",
+                                     i, show_position(pos), ty, min, max)]));
+    }
+    let typed_literal = match *literal {
+        TaggedTerm::Literal(_, i) => TaggedTerm::Literal(ty.clone(), i),
+        _ => TaggedTerm::SuffixedLiteral(ty.clone(), i, ty.clone()),
+    };
+    Some(Ok(TaggedTerm::Neg(ty, Box::new(typed_literal))))
 }
 
 impl TypeCheck for TaggedTerm<Position> {
     type Typed = TaggedTerm<Type>;
-    fn type_check(&self, env: &mut Map<Type>) -> Result<Self::Typed, Vec<String>> {
+
+    // An integer literal where another integer type is wanted has that type, if it fits in it.
+    // Anywhere else it's an I32.
+    fn type_check_against<E: TypeEnv>(&self,
+                                      env: &mut E,
+                                      expected: &Type) -> Result<TaggedTerm<Type>, Vec<String>> {
+        let expected = env.zonk(expected);
+        match *self {
+            TaggedTerm::Literal(ref pos, i) if is_integer(&expected) => {
+                if !literal_fits(i as i64, &expected) {
+                    let (min, max) = int_bounds(&expected).unwrap();
+                    return Err(vec![format!("The literal {} at {} doesn't fit in type {}, whose \
+                                             values go from {} to {}.",
+                                            i, show_position(pos), expected, min, max)]);
+                }
+                Ok(TaggedTerm::Literal(expected, i))
+            }
+            TaggedTerm::Neg(ref pos, ref term) if is_integer(&expected) => {
+                match check_negated_literal(pos, term, Some(&expected)) {
+                    Some(result) => result,
+                    None => self.type_check(env),
+                }
+            }
+            _ => self.type_check(env),
+        }
+    }
+
+    fn type_check<E: TypeEnv>(&self, env: &mut E) -> Result<Self::Typed, Vec<String>> {
         use self::TaggedTerm::*;
         use self::Type::*;
         match *self {
             Literal(_, i) => Ok(TaggedTerm::Literal(I32Ty, i)),
-            Var(_, ref str) => match env.get(&str.clone()) {
-                Some(ty) => Ok(TaggedTerm::Var(ty.clone(), str.clone())),
+            BoolLiteral(_, b) => Ok(TaggedTerm::BoolLiteral(BoolTy, b)),
+            FloatLiteral(_, bits) => Ok(TaggedTerm::FloatLiteral(F64Ty, bits)),
+            CharLit(_, c) => Ok(TaggedTerm::CharLit(CharTy, c)),
+            StrLit(ref pos, ref s) => {
+                if s.contains('\0') {
+                    return Err(vec![format!("The string literal at {} contains a NUL character, \
+                                             which would end it early.", show_position(pos))]);
+                }
+                Ok(TaggedTerm::StrLit(StrTy, s.clone()))
+            }
+            SuffixedLiteral(ref pos, i, ref ty) => {
+                if !literal_fits(i, ty) {
+                    return Err(vec![format!("The literal {} at {} doesn't fit in type {}.",
+                                            i, show_position(pos), ty)]);
+                }
+                Ok(TaggedTerm::SuffixedLiteral(ty.clone(), i, ty.clone()))
+            }
+            Var(ref pos, ref str) => match env.get(str) {
+                Some(ty) if is_generic(ty) =>
+                    Err(vec![format!("The generic extern {} can only be called, not used as a \
+                                      value.", str)]),
+                Some(ty) => {
+                    use_var(env, str, pos);
+                    Ok(TaggedTerm::Var(env.zonk(ty), str.clone()))
+                }
                 None => Err(vec![format!("Undeclared variable {}.", str.clone())]),
             },
             Infix(_, ref left, ref op, ref right) => {
                 let tagged_left: TaggedTerm<Type> = try!(left.type_check(&mut env.clone()));
-                let tagged_right: TaggedTerm<Type> = try!(right.type_check(env));
-                let left_ty = *tagged_left.get_tag();
-                let right_ty = *tagged_right.get_tag();
-                if left_ty == I32Ty && right_ty == I32Ty {
-                    Ok(TaggedTerm::Infix(
-                        left_ty, Box::new(tagged_left), op.clone(), Box::new(tagged_right)
-                    ))
-                } else {
-                    return Err(
-                        vec![
-                            format!("The left-hand-side of {} has type {}, \
-                                    but the right-hand-side of it has type {}.",
-                                    op, left_ty, right_ty)
-                        ]
-                    );
+                let tagged_right: TaggedTerm<Type> = try!(right.type_check(&mut env.clone()));
+                // Each side of `&&` and `||` is a Bool on its own.
+                if op.is_logical() {
+                    let mut errors = Vec::new();
+                    for &(side, term, tagged_term) in &[("left", left, &tagged_left),
+                                                        ("right", right, &tagged_right)] {
+                        let ty = *tagged_term.get_tag();
+                        if !env.unify(&ty, &BoolTy) {
+                            errors.push(format!("The {}-hand side of {} at {} has type {}, but \
+                                                 it must be a Bool.",
+                                                side, op, show_position(term.tag()),
+                                                env.zonk(&ty)));
+                        }
+                    }
+                    if !errors.is_empty() {
+                        return Err(errors);
+                    }
+                    return Ok(TaggedTerm::Infix(
+                        BoolTy, Box::new(tagged_left), op.clone(), Box::new(tagged_right)
+                    ));
+                }
+                let left_ty = env.zonk(&tagged_left.get_tag());
+                let right_ty = env.zonk(&tagged_right.get_tag());
+                // Operands whose types aren't known yet, or one of which never finishes, get
+                // the type of the other one.
+                let result_ty = match operator_type(env, *op, &left_ty, &right_ty) {
+                    Some(result_ty) => Some(result_ty),
+                    None if env.unify(&left_ty, &right_ty) => {
+                        let ty = env.zonk(if left_ty == Never { &right_ty } else { &left_ty });
+                        operator_type(env, *op, &ty, &ty)
+                    }
+                    None => None,
+                };
+                let zero_divisor = match **right {
+                    Literal(_, 0) | SuffixedLiteral(_, 0, _) => true,
+                    _ => false,
+                };
+                match result_ty {
+                    Some(_) if *op == Operator::Rem && zero_divisor =>
+                        Err(vec![format!("The right-hand side of % at {} is 0, but there's no \
+                                          remainder of a division by zero.",
+                                         show_position(right.tag()))]),
+                    Some(ty) => Ok(TaggedTerm::Infix(
+                        ty, Box::new(tagged_left), op.clone(), Box::new(tagged_right)
+                    )),
+                    None if (left_ty == Ptr || right_ty == Ptr) &&
+                            (*op == Operator::Eq || *op == Operator::Ne) => {
+                        let mut errors = Vec::new();
+                        for &(term, ref ty) in &[(left, &left_ty), (right, &right_ty)] {
+                            if **ty != Ptr {
+                                errors.push(format!("The term at {} has type {}, but it's \
+                                                     compared with {} to a Ptr.",
+                                                    show_position(term.tag()), ty, op));
+                            }
+                        }
+                        Err(errors)
+                    }
+                    None if left_ty == Ptr || right_ty == Ptr =>
+                        Err(vec![format!("The operands of {} can't be Ptrs; pointers can only \
+                                          be compared with == and !=.", op)]),
+                    None => Err(vec![format!("The operator {} is not defined for types {} and {}.",
+                                             op, left_ty, right_ty)]),
                 }
             }
+            Call(ref pos, ref func, ref args)
+                if is_builtin_print(&func.name, env.get(&func.name).is_some()) =>
+                check_print(pos, args, env),
             Call(_, ref func, ref args) => {
                 let typed_func = try!(func.type_check(&mut env.clone()));
-                let (expected_args_types, expected_ret_ty) =
-                    if let Type::FunctionTy(args_types, ret_ty) = *typed_func.get_tag() {
-                        (args_types, *ret_ty)
+                use_var(env, &func.name, &func.tag);
+                let (params, expected_args_types, expected_ret_ty) =
+                    if let Type::FunctionTy(params, args_types, ret_ty) = *typed_func.get_tag() {
+                        (params, args_types, *ret_ty)
                     } else {
                         unreachable!()
                     };
                 let expected_arity = expected_args_types.len();
                 let actual_arity = args.len();
-                if expected_arity == actual_arity {
+                if expected_arity == actual_arity && !params.is_empty() {
+                    check_generic_call(&func.name, &params, &expected_args_types, &expected_ret_ty,
+                                       args, env)
+                } else if expected_arity == actual_arity {
                     let pairs = expected_args_types.iter().zip(args);
                     let mut has_error = false;
                     let mut tagged_args = Vec::new();
@@ -149,24 +972,26 @@ impl TypeCheck for TaggedTerm<Position> {
                     for (expected, actual) in pairs {
                         let expected_ty = expected.clone();
                         let tagged_arg: TaggedTerm<Type> =
-                            try!(actual.type_check(&mut env.clone()));
+                            try!(actual.type_check_against(&mut env.clone(), expected));
                         if !has_error {
                             tagged_args.push(tagged_arg.clone());
                         }
                         let actual_ty = tagged_arg.get_tag();
-                        if expected_ty != *actual_ty {
+                        if !env.unify(&expected_ty, &actual_ty) {
                             has_error = true;
                             errors.push(
                                 format!(
                                     "Expect term of type {}, found term of type {}.",
-                                    expected_ty, actual_ty
+                                    env.zonk(&expected_ty), env.zonk(&actual_ty)
                                 )
                             );
                         }
                     }
                     if errors.len() == 0 {
                         Ok(TaggedTerm::Call(
-                            expected_ret_ty, try!(func.type_check(env)), tagged_args.clone()
+                            env.zonk(&expected_ret_ty),
+                            try!(func.type_check(env)),
+                            tagged_args.clone()
                         ))
                     } else {
                         Err(errors)
@@ -180,20 +1005,68 @@ impl TypeCheck for TaggedTerm<Position> {
                     )
                 }
             }
+            // `x.f(a)` is checked as `f(x, a)`, but its errors say what was written.
+            MethodCall(ref pos, ref receiver, ref method, ref args) => {
+                let written = format!("the method call .{}(...) at {}",
+                                      method.name, show_position(pos));
+                if let Some(&FunctionTy(_, ref args_types, _)) = env.get(&method.name) {
+                    if args_types.len() != args.len() + 1 {
+                        return Err(vec![format!("In {}: {} takes {} argument(s), but the \
+                                                 receiver and {} more are given.",
+                                                written, method.name, args_types.len(),
+                                                args.len())]);
+                    }
+                }
+                let mut call_args = vec![(**receiver).clone()];
+                call_args.extend(args.iter().cloned());
+                let call = Call(pos.clone(), method.clone(), call_args);
+                call.type_check(env).map_err(|errors| {
+                    errors.into_iter()
+                          .map(|error| format!("In {}: {}", written, error))
+                          .collect()
+                })
+            }
             Scope(_, ref block) => {
-                let tagged_block = try!(block.type_check(env));
+                // Bindings inside the block mustn't leak into the enclosing term.
+                let tagged_block = try!(block.type_check(&mut env.clone()));
                 let ty = tagged_block.get_tag();
                 Ok(TaggedTerm::Scope(*ty, tagged_block))
             }
-            If(_, ref if_clause, ref then_clause, ref else_clause) => {
+            If(ref pos, ref if_clause, ref then_clause, ref else_clause) => {
                 let tagged_if = try!(if_clause.type_check(&mut env.clone()));
+                let if_ty = *tagged_if.get_tag();
+                if !env.unify(&if_ty, &BoolTy) {
+                    let message = format!("The condition of an if must have type Bool, \
+                                           but the condition at {} has type {}.",
+                                          show_position(if_clause.tag()), if_ty);
+                    if if_ty != I32Ty {
+                        return Err(vec![message]);
+                    }
+                    try!(gate(env, StrictRule::BoolCondition, vec![message]));
+                }
                 let tagged_then = try!(then_clause.type_check(&mut env.clone()));
-                let tagged_else = try!(else_clause.type_check(&mut env.clone()));
                 let then_ty = *tagged_then.get_tag().clone();
+                let else_clause = match *else_clause {
+                    Some(ref else_clause) => else_clause,
+                    // Nothing is left for the other case to evaluate to, so it's Unit.
+                    None => {
+                        if !env.unify(&then_ty, &UnitTy) {
+                            return Err(vec![format!("The if at {} has no else part, so its then \
+                                                     part must have type Unit, but it has type \
+                                                     {}. An else part is required to return a \
+                                                     value.",
+                                                    show_position(pos), then_ty)]);
+                        }
+                        return Ok(TaggedTerm::If(UnitTy, Box::new(tagged_if),
+                                                 Box::new(tagged_then), None));
+                    }
+                };
+                let tagged_else = try!(else_clause.type_check(&mut env.clone()));
                 let else_ty = *tagged_else.get_tag().clone();
-                if then_ty == else_ty {
+                if env.unify(&then_ty, &else_ty) {
+                    let ty = env.zonk(if then_ty == Never { &else_ty } else { &then_ty });
                     Ok(TaggedTerm::If(
-                        then_ty, Box::new(tagged_if), Box::new(tagged_then), Box::new(tagged_else)
+                        ty, Box::new(tagged_if), Box::new(tagged_then), Some(Box::new(tagged_else))
                     ))
                 } else {
                     Err(
@@ -210,22 +1083,711 @@ impl TypeCheck for TaggedTerm<Position> {
             While(_, ref cond, ref block) => {
                 let tagged_cond = try!(cond.type_check(&mut env.clone()));
                 let cond_ty = *tagged_cond.get_tag();
-                if cond_ty != I32Ty {
-                    Err(vec!["The condition of a while loop should be of type I32".to_string()])
-                } else {
-                    let tagged_block: TaggedBlock<Type> = try!(block.type_check(env));
-                    Ok(TaggedTerm::While(
-                        *tagged_block.get_tag(), Box::new(tagged_cond), tagged_block
-                    ))
+                if !env.unify(&cond_ty, &BoolTy) {
+                    let message = format!("The condition of a while loop must have type Bool, \
+                                           but the condition at {} has type {}.",
+                                          show_position(cond.tag()), cond_ty);
+                    if cond_ty != I32Ty {
+                        return Err(vec![message]);
+                    }
+                    try!(gate(env, StrictRule::BoolCondition, vec![message]));
                 }
+                let tagged_block: TaggedBlock<Type> = try!(block.type_check(&mut env.clone()));
+                let ty = if loops_forever(&tagged_cond) { Never } else { *tagged_block.get_tag() };
+                Ok(TaggedTerm::While(ty, Box::new(tagged_cond), tagged_block))
             }
             Stmt(ref stmt) => {
-                Ok(TaggedTerm::Stmt(Box::new(try!(stmt.type_check(env)))))
+                Ok(TaggedTerm::Stmt(Box::new(try!(stmt.type_check(&mut env.clone())))))
+            }
+            // Casts are the only way between numeric types; nothing converts implicitly.
+            Cast(ref pos, ref term, ref target) => {
+                let tagged_term = try!(term.type_check(&mut env.clone()));
+                let term_ty = env.zonk(&tagged_term.get_tag());
+                let target = try!(resolve_type(target, env));
+                if can_cast(&term_ty, &target) {
+                    Ok(TaggedTerm::Cast(target.clone(), Box::new(tagged_term), target))
+                } else {
+                    Err(vec![format!("Can't cast the term at {} from {} to {}; casts only go \
+                                      between numeric types, or from Char.",
+                                     show_position(pos), term_ty, target)])
+                }
+            }
+            Tuple(_, ref elems) => {
+                let mut tagged_elems = Vec::new();
+                let mut errors = Vec::new();
+                for elem in elems {
+                    let tagged_elem = try!(elem.type_check(&mut env.clone()));
+                    let elem_ty = *tagged_elem.get_tag();
+                    if !is_storable(&elem_ty) {
+                        errors.push(format!("The element at {} has type {}, which a tuple can't \
+                                             hold.", show_position(elem.tag()), elem_ty));
+                    }
+                    tagged_elems.push(tagged_elem);
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                let ty = TupleTy(tagged_elems.iter().map(|elem| *elem.get_tag()).collect());
+                Ok(TaggedTerm::Tuple(ty, tagged_elems))
+            }
+            Project(_, ref term, index) => {
+                let tagged_term = try!(term.type_check(&mut env.clone()));
+                let term_ty = env.zonk(&tagged_term.get_tag());
+                let elem_ty = match term_ty {
+                    TupleTy(ref elems) if (index as usize) < elems.len() =>
+                        elems[index as usize].clone(),
+                    TupleTy(_) => {
+                        return Err(vec![format!("The tuple at {} has type {}, which has no \
+                                                 element {}.",
+                                                show_position(term.tag()), term_ty, index)]);
+                    }
+                    _ => {
+                        return Err(vec![format!("The term at {} has type {}, which isn't a \
+                                                 tuple, so it has no element {}.",
+                                                show_position(term.tag()), term_ty, index)]);
+                    }
+                };
+                Ok(TaggedTerm::Project(elem_ty, Box::new(tagged_term), index))
+            }
+            // The first element decides the type. The parser never gives an empty array, but a
+            // tree built by hand can, and only an env that infers types can type one.
+            ArrayLit(ref pos, ref elems) if elems.is_empty() => {
+                let origin = format!("the elements of the empty array at {}", show_position(pos));
+                let elem_ty = try!(env.fresh_meta(origin.clone())
+                                      .ok_or(vec![format!("Cannot infer the type of {}.",
+                                                          origin)]));
+                Ok(TaggedTerm::ArrayLit(ArrayTy(Box::new(elem_ty), 0), Vec::new()))
+            }
+            ArrayLit(ref pos, ref elems) => {
+                let mut tagged_elems: Vec<TaggedTerm<Type>> = Vec::new();
+                for elem in elems {
+                    tagged_elems.push(try!(elem.type_check(&mut env.clone())));
+                }
+                let elem_ty = *tagged_elems[0].get_tag();
+                if !is_storable(&elem_ty) {
+                    return Err(vec![format!("The element at {} has type {}, which an array \
+                                             can't hold.", show_position(elems[0].tag()),
+                                            elem_ty)]);
+                }
+                for (elem, tagged_elem) in elems.iter().zip(&tagged_elems) {
+                    let ty = *tagged_elem.get_tag();
+                    if !env.unify(&ty, &elem_ty) {
+                        return Err(vec![format!("The elements of the array at {} have type {}, \
+                                                 but the element at {} has type {}.",
+                                                show_position(pos), elem_ty,
+                                                show_position(elem.tag()), ty)]);
+                    }
+                }
+                let ty = ArrayTy(Box::new(env.zonk(&elem_ty)), tagged_elems.len() as u32);
+                Ok(TaggedTerm::ArrayLit(ty, tagged_elems))
+            }
+            // An index out of bounds traps at runtime, unless it's a literal, which is caught
+            // here.
+            Index(_, ref array, ref index) => {
+                let tagged_array = try!(array.type_check(&mut env.clone()));
+                let tagged_index = try!(index.type_check(&mut env.clone()));
+                let (elem_ty, len) = match env.zonk(&tagged_array.get_tag()) {
+                    ArrayTy(elem_ty, len) => (*elem_ty, len),
+                    array_ty => {
+                        return Err(vec![format!("The term at {} has type {}, which isn't an \
+                                                 array, so it can't be indexed.",
+                                                show_position(array.tag()), array_ty)]);
+                    }
+                };
+                let index_ty = *tagged_index.get_tag();
+                if !env.unify(&index_ty, &I32Ty) {
+                    return Err(vec![format!("The index at {} has type {}, but indices must have \
+                                             type I32.", show_position(index.tag()), index_ty)]);
+                }
+                if let Literal(_, i) = **index {
+                    if i < 0 || i as u32 >= len {
+                        return Err(vec![format!("The index {} at {} is out of bounds for an \
+                                                 array of type {}.",
+                                                i, show_position(index.tag()),
+                                                tagged_array.get_tag())]);
+                    }
+                }
+                Ok(TaggedTerm::Index(elem_ty, Box::new(tagged_array), Box::new(tagged_index)))
+            }
+            // Every field is initialized exactly once, in any order.
+            StructLit(ref pos, ref name, ref inits) => {
+                let structure = match env.declared_type(TypeKind::Struct, name) {
+                    Some(&Struct(ref structure)) => structure.clone(),
+                    _ => return Err(vec![format!("Struct {} is undeclared.", name)]),
+                };
+                let mut tagged_inits = Vec::new();
+                let mut errors = Vec::new();
+                for (i, &(ref field, ref term)) in inits.iter().enumerate() {
+                    let tagged_term = try!(term.type_check(&mut env.clone()));
+                    let ty = *tagged_term.get_tag();
+                    if let Some((_, field_ty)) = structure.field(field) {
+                        if !env.unify(field_ty, &ty) {
+                            errors.push(format!("The field {} of struct {} has type {}, but the \
+                                                 term at {} has type {}.",
+                                                field, name, field_ty, show_position(term.tag()),
+                                                ty));
+                        }
+                    }
+                    if inits[..i].iter().any(|&(ref other, _)| other == field) {
+                        errors.push(format!("The struct literal at {} initializes the field {} \
+                                             more than once.", show_position(pos), field));
+                    }
+                    tagged_inits.push((field.clone(), tagged_term));
+                }
+                let unknown: Vec<&str> = inits.iter()
+                                              .map(|&(ref field, _)| &field[..])
+                                              .filter(|field| structure.field(field).is_none())
+                                              .collect();
+                if !unknown.is_empty() {
+                    errors.push(format!("The struct literal at {} initializes {}, but struct {} \
+                                         has no such field(s).",
+                                        show_position(pos), unknown.join(", "), name));
+                }
+                let missing: Vec<&str> =
+                    structure.field_names()
+                             .into_iter()
+                             .filter(|field| !inits.iter().any(|&(ref init, _)| init == field))
+                             .collect();
+                if !missing.is_empty() {
+                    errors.push(format!("The struct literal at {} is missing the field(s) {} of \
+                                         struct {}.",
+                                        show_position(pos), missing.join(", "), name));
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                Ok(TaggedTerm::StructLit(Struct(structure), name.clone(), tagged_inits))
+            }
+            Field(_, ref term, ref field) => {
+                let tagged_term = try!(term.type_check(&mut env.clone()));
+                let term_ty = env.zonk(&tagged_term.get_tag());
+                let field_ty = match term_ty {
+                    Struct(ref structure) => match structure.field(field) {
+                        Some((_, ty)) => ty.clone(),
+                        None => {
+                            return Err(vec![format!("The term at {} has type {}, which has no \
+                                                     field {}; its fields are {}.",
+                                                    show_position(term.tag()), term_ty, field,
+                                                    structure.field_names().join(", "))]);
+                        }
+                    },
+                    _ => {
+                        return Err(vec![format!("The term at {} has type {}, which isn't a \
+                                                 struct, so it has no field {}.",
+                                                show_position(term.tag()), term_ty, field)]);
+                    }
+                };
+                Ok(TaggedTerm::Field(field_ty, Box::new(tagged_term), field.clone()))
+            }
+            // The payload has to match the variant's payload types one by one.
+            Variant(ref pos, ref name, ref variant, ref args) => {
+                let enumeration = match env.declared_type(TypeKind::Enum, name) {
+                    Some(&Enum(ref enumeration)) => enumeration.clone(),
+                    _ => return Err(vec![format!("Enum {} is undeclared.", name)]),
+                };
+                let payload = match enumeration.variant(variant) {
+                    Some((_, payload)) => payload.to_vec(),
+                    None => {
+                        return Err(vec![format!("Enum {} has no variant {}; its variants are {}.",
+                                                name, variant,
+                                                enumeration.variant_names().join(", "))]);
+                    }
+                };
+                if payload.len() != args.len() {
+                    return Err(vec![format!("The variant {}::{} at {} carries {} value(s), but {} \
+                                             are given.",
+                                            name, variant, show_position(pos), payload.len(),
+                                            args.len())]);
+                }
+                let mut tagged_args = Vec::new();
+                let mut errors = Vec::new();
+                for (expected, arg) in payload.iter().zip(args) {
+                    let tagged_arg = try!(arg.type_check_against(&mut env.clone(), expected));
+                    let ty = *tagged_arg.get_tag();
+                    if !env.unify(expected, &ty) {
+                        errors.push(format!("The payload of {}::{} at {} should have type {}, \
+                                             but it has type {}.",
+                                            name, variant, show_position(arg.tag()), expected,
+                                            ty));
+                    }
+                    tagged_args.push(tagged_arg);
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                Ok(TaggedTerm::Variant(Enum(enumeration), name.clone(), variant.clone(),
+                                       tagged_args))
+            }
+            // Every arm has the same type, like the parts of an if, and together the arms cover
+            // every variant. An arm's pattern binds the payload in the arm only.
+            Match(ref pos, ref scrutinee, ref arms) => {
+                let tagged_scrutinee = try!(scrutinee.type_check(&mut env.clone()));
+                let enumeration = match env.zonk(&tagged_scrutinee.get_tag()) {
+                    Enum(enumeration) => enumeration,
+                    ty => {
+                        return Err(vec![format!("The term at {} has type {}, which isn't an \
+                                                 enum, so it can't be matched on.",
+                                                show_position(scrutinee.tag()), ty)]);
+                    }
+                };
+                if arms.is_empty() {
+                    return Err(vec![format!("The match at {} has no arms.", show_position(pos))]);
+                }
+                let mut tagged_arms = Vec::new();
+                let mut errors = Vec::new();
+                for &(ref pattern, ref body) in arms {
+                    let mut arm_env = env.clone();
+                    if let Pattern::Constructor(ref name, ref variant, ref binds) = *pattern {
+                        if *name != enumeration.name {
+                            errors.push(format!("The pattern {} in the match at {} is of enum \
+                                                 {}, but the term matched on has type {}.",
+                                                pattern, show_position(pos), name,
+                                                enumeration));
+                            continue;
+                        }
+                        let payload = match enumeration.variant(variant) {
+                            Some((_, payload)) => payload,
+                            None => {
+                                errors.push(format!("Enum {} has no variant {}; its variants \
+                                                     are {}.",
+                                                    name, variant,
+                                                    enumeration.variant_names().join(", ")));
+                                continue;
+                            }
+                        };
+                        if payload.len() != binds.len() {
+                            errors.push(format!("The pattern {} in the match at {} binds {} \
+                                                 value(s), but the variant carries {}.",
+                                                pattern, show_position(pos), binds.len(),
+                                                payload.len()));
+                            continue;
+                        }
+                        for (bind, ty) in binds.iter().zip(payload) {
+                            try!(check_identifier(bind));
+                            bind_var(&mut arm_env, bind, ty.clone(), BindingKind::Pattern, pos);
+                        }
+                    }
+                    let tagged_body = try!(body.type_check(&mut arm_env));
+                    tagged_arms.push((pattern.clone(), tagged_body));
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                // Arms that never finish don't decide the type.
+                let ty = tagged_arms.iter()
+                                    .map(|&(_, ref body)| *body.get_tag())
+                                    .find(|ty| *ty != Never)
+                                    .unwrap_or(Never);
+                for (&(_, ref body), &(_, ref tagged_body)) in arms.iter().zip(&tagged_arms) {
+                    let body_ty = *tagged_body.get_tag();
+                    if !env.unify(&body_ty, &ty) {
+                        errors.push(format!("The first arm of the match at {} has type {}, but \
+                                             the arm at {} has type {}.",
+                                            show_position(pos), ty, show_position(body.tag()),
+                                            body_ty));
+                    }
+                }
+                let has_wildcard = arms.iter().any(|&(ref pattern, _)| {
+                    *pattern == Pattern::Wildcard
+                });
+                let missing: Vec<&str> =
+                    enumeration.variant_names()
+                               .into_iter()
+                               .filter(|variant| !arms.iter().any(|&(ref pattern, _)| {
+                                   match *pattern {
+                                       Pattern::Constructor(_, ref covered, _) =>
+                                           covered == variant,
+                                       Pattern::Wildcard => false,
+                                   }
+                               }))
+                               .collect();
+                if !has_wildcard && !missing.is_empty() {
+                    errors.push(format!("The match at {} doesn't cover the variant(s) {} of enum \
+                                         {}.",
+                                        show_position(pos), missing.join(", "), enumeration));
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                Ok(TaggedTerm::Match(env.zonk(&ty), Box::new(tagged_scrutinee), tagged_arms))
+            }
+            Borrow(_, ref term) => {
+                let (name, ty) = try!(borrowed_var(term, env));
+                let tagged_var = TaggedTerm::Var(ty.clone(), name.clone());
+                Ok(TaggedTerm::Borrow(Ref(Box::new(ty)), Box::new(tagged_var)))
+            }
+            Deref(_, ref term) => {
+                let tagged_term = try!(term.type_check(&mut env.clone()));
+                match env.zonk(&tagged_term.get_tag()) {
+                    Ref(referent) | RefMut(referent) =>
+                        Ok(TaggedTerm::Deref(*referent, Box::new(tagged_term))),
+                    ty => Err(vec![format!("The term at {} has type {}, which isn't a reference, \
+                                            so it can't be dereferenced.",
+                                           show_position(term.tag()), ty)]),
+                }
+            }
+            Neg(ref pos, ref term) => {
+                if let Some(result) = check_negated_literal(pos, term, None) {
+                    return result;
+                }
+                let tagged_term = try!(term.type_check(&mut env.clone()));
+                match env.zonk(&tagged_term.get_tag()) {
+                    ty if can_negate(&ty) || ty == Never =>
+                        Ok(TaggedTerm::Neg(ty, Box::new(tagged_term))),
+                    ty => Err(vec![format!("The term at {} has type {}, which isn't a signed \
+                                            integer or an F64, so it can't be negated.",
+                                           show_position(term.tag()), ty)]),
+                }
+            }
+            Not(_, ref term) => {
+                let tagged_term = try!(term.type_check(&mut env.clone()));
+                match env.zonk(&tagged_term.get_tag()) {
+                    BoolTy | Never => Ok(TaggedTerm::Not(BoolTy, Box::new(tagged_term))),
+                    ty => Err(vec![format!("The term at {} has type {}, but ! only takes a Bool.",
+                                           show_position(term.tag()), ty)]),
+                }
+            }
+            BorrowMut(_, ref term) => {
+                let (name, ty) = try!(borrowed_var(term, env));
+                if !is_mutable(env, name) {
+                    return Err(vec![format!("The variable {} at {} is immutable, so it can't be \
+                                             borrowed mutably; bind it with `let mut {}` \
+                                             instead.", name, show_position(term.tag()), name)]);
+                }
+                let tagged_var = TaggedTerm::Var(ty.clone(), name.clone());
+                Ok(TaggedTerm::BorrowMut(RefMut(Box::new(ty)), Box::new(tagged_var)))
+            }
+            Null(_) => Ok(TaggedTerm::Null(Ptr)),
+            PtrEq(_, ref left, ref right) => {
+                let tagged_left = try!(left.type_check(&mut env.clone()));
+                let tagged_right = try!(right.type_check(&mut env.clone()));
+                let mut errors = Vec::new();
+                for &(term, tagged_term) in &[(left, &tagged_left), (right, &tagged_right)] {
+                    let ty = *tagged_term.get_tag();
+                    if !env.unify(&ty, &Ptr) {
+                        errors.push(format!("The term at {} has type {}, but only Ptrs can be \
+                                             compared with ==.",
+                                            show_position(term.tag()), ty));
+                    }
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                Ok(TaggedTerm::PtrEq(BoolTy, Box::new(tagged_left), Box::new(tagged_right)))
+            }
+            Lambda(ref pos, ref params, ref body) => {
+                let mut errors = Vec::new();
+                let mut body_env = env.clone();
+                let mut typed_params = Vec::new();
+                for (i, &(ref name, ref ty)) in params.iter().enumerate() {
+                    if let Err(name_errors) = check_identifier(name) {
+                        errors.extend(name_errors);
+                    }
+                    if params[..i].iter().any(|&(ref other, _)| other == name) {
+                        errors.push(format!("The lambda at {} has more than one parameter named \
+                                             {}.", show_position(pos), name));
+                    }
+                    let resolved = if *ty == Hole {
+                        let origin = format!("the parameter {} of the lambda at {}",
+                                             name, show_position(pos));
+                        env.fresh_meta(origin.clone())
+                           .ok_or(vec![format!("Cannot infer the type of {}.", origin)])
+                    } else {
+                        resolve_type(ty, env)
+                    };
+                    let ty = match resolved {
+                        Ok(ty) => ty,
+                        Err(type_errors) => {
+                            errors.extend(type_errors);
+                            continue;
+                        }
+                    };
+                    if ty == UnitTy {
+                        errors.push(format!("The parameter {} of the lambda at {} has type (), \
+                                             which a function can't take.",
+                                            name, show_position(pos)));
+                    } else if let Err(type_errors) = check_declared_type(&ty) {
+                        errors.extend(type_errors);
+                    }
+                    bind_var(&mut body_env, name, ty.clone(), BindingKind::Param, pos);
+                    typed_params.push((name.clone(), ty));
+                }
+                // Externs are the only bindings outside a lambda that outlive the call that
+                // makes it.
+                for name in captures(params, body) {
+                    if env.get(&name).is_some() && !is_extern(env, &name) {
+                        errors.push(format!("The lambda at {} captures the variable {}, but \
+                                             closures are not supported yet.",
+                                            show_position(pos), name));
+                    }
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                let tagged_body = try!(body.type_check(&mut body_env));
+                let args_types = typed_params.iter().map(|&(_, ref ty)| ty.clone()).collect();
+                let ty = FunctionTy(Vec::new(), args_types, Box::new(tagged_body.tag.clone()));
+                Ok(TaggedTerm::Lambda(ty, typed_params, tagged_body))
+            }
+            Apply(ref pos, ref callee, ref args) => {
+                let tagged_callee = try!(callee.type_check(&mut env.clone()));
+                let (args_types, ret_ty) = match env.zonk(&tagged_callee.get_tag()) {
+                    FunctionTy(_, args_types, ret_ty) => (args_types, *ret_ty),
+                    ty => return Err(vec![format!("The term at {} is called as a function, but \
+                                                   it has type {}.",
+                                                  show_position(callee.tag()), ty)]),
+                };
+                if args_types.len() != args.len() {
+                    return Err(vec![format!("The function called at {} expects {} argument(s), \
+                                             but {} are provided.",
+                                            show_position(pos), args_types.len(), args.len())]);
+                }
+                let mut tagged_args = Vec::new();
+                let mut errors = Vec::new();
+                for (expected, arg) in args_types.iter().zip(args) {
+                    let tagged_arg = try!(arg.type_check_against(&mut env.clone(), expected));
+                    let actual = *tagged_arg.get_tag();
+                    if !env.unify(&actual, expected) {
+                        errors.push(format!("The argument at {} has type {}, but the function \
+                                             expects {}.",
+                                            show_position(arg.tag()), env.zonk(&actual),
+                                            env.zonk(expected)));
+                    }
+                    tagged_args.push(tagged_arg);
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                Ok(TaggedTerm::Apply(env.zonk(&ret_ty), Box::new(tagged_callee), tagged_args))
+            }
+            // The term is checked where a term of the ascribed type is wanted, like the
+            // right-hand side of a let with an annotation.
+            Ascribe(ref pos, ref term, ref ty) => {
+                let ty = try!(resolve_type(ty, env));
+                try!(check_declared_type(&ty));
+                let tagged_term = try!(term.type_check_against(&mut env.clone(), &ty));
+                let term_ty = *tagged_term.get_tag();
+                if !env.unify(&ty, &term_ty) {
+                    return Err(vec![format!("The term at {} has type {}, but it's ascribed type \
+                                             {}.",
+                                            show_position(pos), env.zonk(&term_ty), ty)]);
+                }
+                Ok(TaggedTerm::Ascribe(ty.clone(), Box::new(tagged_term), ty))
             }
         }
     }
 }
 
+// The annotation of a let, resolved, and its right-hand side checked against it.
+fn check_let<E: TypeEnv>(pos: &Position,
+                         name: &str,
+                         annotation: &Type,
+                         rhs: &TaggedTerm<Position>,
+                         env: &mut E) -> Result<(Type, TaggedTerm<Type>), Vec<String>> {
+    if *annotation == Type::Hole {
+        return Ok((Type::Hole, try!(rhs.type_check(&mut env.clone()))));
+    }
+    let in_annotation = |errors: Vec<String>| {
+        errors.into_iter()
+              .map(|error| format!("In the type of {} at {}: {}", name, show_position(pos), error))
+              .collect::<Vec<_>>()
+    };
+    let annotation = try!(resolve_type(annotation, env).map_err(&in_annotation));
+    try!(check_declared_type(&annotation).map_err(&in_annotation));
+    let typed_rhs = try!(rhs.type_check_against(&mut env.clone(), &annotation));
+    let rhs_ty = *typed_rhs.get_tag();
+    if !env.unify(&annotation, &rhs_ty) {
+        return Err(vec![format!("The variable {} is declared with type {}, but its initializer at \
+                                 {} has type {}.",
+                                name, annotation, show_position(rhs.tag()), env.zonk(&rhs_ty))]);
+    }
+    Ok((annotation, typed_rhs))
+}
+
+// The type a let binds its variable to: the annotation, or the type of the right-hand side if
+// there's none.
+pub fn let_type(annotation: &Type, rhs: &TaggedTerm<Type>) -> Type {
+    if *annotation == Type::Hole {
+        *rhs.get_tag()
+    } else {
+        annotation.clone()
+    }
+}
+
+// A call to a generic extern, tagged with the instantiation it calls.
+fn check_generic_call<E: TypeEnv>(name: &str,
+                                  params: &[String],
+                                  args_types: &[Type],
+                                  ret_ty: &Type,
+                                  args: &[TaggedTerm<Position>],
+                                  env: &mut E) -> Result<TaggedTerm<Type>, Vec<String>> {
+    let mut tagged_args = Vec::new();
+    for arg in args {
+        tagged_args.push(try!(arg.type_check(&mut env.clone())));
+    }
+    let actual: Vec<Type> = tagged_args.iter().map(|arg| *arg.get_tag()).collect();
+    let (args_types, ret_ty) = try!(instantiate(name, params, args_types, ret_ty, &actual));
+    let mut errors = Vec::new();
+    for (expected, actual) in args_types.iter().zip(actual) {
+        if *expected != actual {
+            errors.push(format!("Expect term of type {}, found term of type {}.",
+                                expected, actual));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let func = TaggedFunctionCall {
+        tag: Type::FunctionTy(Vec::new(), args_types, Box::new(ret_ty.clone())),
+        name: name.to_string(),
+    };
+    Ok(TaggedTerm::Call(ret_ty, func, tagged_args))
+}
+
+// The argument types and the return type of the generic function `name` called with arguments
+// of the types `actual`. Each type parameter is instantiated with the type of what it's matched
+// against in the arguments, which has to be exactly one type. Whether the arguments then have
+// the instantiated types is up to the caller.
+pub fn instantiate(name: &str,
+                   params: &[String],
+                   args_types: &[Type],
+                   ret_ty: &Type,
+                   actual: &[Type]) -> Result<(Vec<Type>, Type), Vec<String>> {
+    let mut candidates: Vec<(String, Vec<Type>)> =
+        params.iter().map(|param| (param.clone(), Vec::new())).collect();
+    for (expected, actual) in args_types.iter().zip(actual) {
+        match_params(expected, actual, &mut candidates);
+    }
+    let mut errors = Vec::new();
+    let mut instantiation = Vec::new();
+    for (param, types) in candidates {
+        match types.len() {
+            0 => errors.push(format!("The type parameter {} of {} can't be inferred from the \
+                                      arguments.", param, name)),
+            1 if !is_storable(&types[0]) =>
+                errors.push(format!("The type parameter {} of {} can't be instantiated with \
+                                     type {}.", param, name, types[0])),
+            1 => instantiation.push((param, types[0].clone())),
+            _ => errors.push(format!("The type parameter {} of {} is instantiated with both {} \
+                                      and {}.", param, name, types[0], types[1])),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let args_types = args_types.iter().map(|ty| substitute(ty, &instantiation)).collect();
+    Ok((args_types, substitute(ret_ty, &instantiation)))
+}
+
+// Matches `expected`, which mentions type parameters, against the type `actual`, adding what
+// each parameter is matched with to its candidates. Where the shapes differ nothing is matched;
+// the instantiated type won't be `actual` then, which is reported once it's known.
+fn match_params(expected: &Type, actual: &Type, candidates: &mut [(String, Vec<Type>)]) {
+    use self::Type::*;
+    match (expected, actual) {
+        (&Param(ref name), _) => {
+            for &mut (ref param, ref mut types) in candidates.iter_mut() {
+                if param == name && !types.contains(actual) {
+                    types.push(actual.clone());
+                }
+            }
+        }
+        (&TupleTy(ref expected), &TupleTy(ref actual)) if expected.len() == actual.len() => {
+            for (expected, actual) in expected.iter().zip(actual) {
+                match_params(expected, actual, candidates);
+            }
+        }
+        (&ArrayTy(ref expected, _), &ArrayTy(ref actual, _)) |
+        (&Ref(ref expected), &Ref(ref actual)) |
+        (&RefMut(ref expected), &RefMut(ref actual)) =>
+            match_params(expected, actual, candidates),
+        (&FunctionTy(_, ref expected_args, ref expected_ret),
+         &FunctionTy(_, ref actual_args, ref actual_ret))
+            if expected_args.len() == actual_args.len() => {
+            for (expected, actual) in expected_args.iter().zip(actual_args) {
+                match_params(expected, actual, candidates);
+            }
+            match_params(expected_ret, actual_ret, candidates);
+        }
+        _ => {}
+    }
+}
+
+// `ty` with the type parameters in `instantiation` replaced by the types they're instantiated
+// with.
+pub fn substitute(ty: &Type, instantiation: &[(String, Type)]) -> Type {
+    use self::Type::*;
+    let all = |types: &[Type]| types.iter().map(|ty| substitute(ty, instantiation)).collect();
+    match *ty {
+        Param(ref name) => {
+            match instantiation.iter().find(|&&(ref param, _)| param == name) {
+                Some(&(_, ref ty)) => ty.clone(),
+                None => ty.clone(),
+            }
+        }
+        TupleTy(ref elems) => TupleTy(all(elems)),
+        ArrayTy(ref elem, len) => ArrayTy(Box::new(substitute(elem, instantiation)), len),
+        Ref(ref ty) => Ref(Box::new(substitute(ty, instantiation))),
+        RefMut(ref ty) => RefMut(Box::new(substitute(ty, instantiation))),
+        FunctionTy(ref params, ref args_types, ref ret_ty) =>
+            FunctionTy(params.clone(),
+                       all(args_types),
+                       Box::new(substitute(ret_ty, instantiation))),
+        _ => ty.clone(),
+    }
+}
+
+pub fn is_generic(ty: &Type) -> bool {
+    match *ty {
+        Type::FunctionTy(ref params, _, _) => !params.is_empty(),
+        _ => false,
+    }
+}
+
+// Whether `ty` is the type of the generic function type `generic` at some instantiation of its
+// type parameters.
+pub fn is_instance(generic: &Type, ty: &Type) -> bool {
+    match *generic {
+        Type::FunctionTy(ref params, ref args_types, ref ret_ty) if is_generic(generic) => {
+            let generic = Type::FunctionTy(Vec::new(), args_types.clone(), ret_ty.clone());
+            let mut candidates: Vec<(String, Vec<Type>)> =
+                params.iter().map(|param| (param.clone(), Vec::new())).collect();
+            match_params(&generic, ty, &mut candidates);
+            if candidates.iter().any(|&(_, ref types)| types.len() > 1) {
+                return false;
+            }
+            let instantiation: Vec<(String, Type)> =
+                candidates.into_iter()
+                          .filter_map(|(param, mut types)| types.pop().map(|ty| (param, ty)))
+                          .collect();
+            substitute(&generic, &instantiation) == *ty
+        }
+        _ => generic == ty,
+    }
+}
+
+// The variable a borrow borrows and its type. Only variables have a place in memory to point to.
+fn borrowed_var<'a, E: TypeEnv>(term: &'a TaggedTerm<Position>,
+                                env: &E) -> Result<(&'a String, Type), Vec<String>> {
+    let name = match *term {
+        TaggedTerm::Var(_, ref name) => name,
+        _ => {
+            return Err(vec![format!("The term at {} can't be borrowed; only variables can.",
+                                    show_position(term.tag()))]);
+        }
+    };
+    let ty = match env.get(name) {
+        Some(ty) => ty.clone(),
+        None => return Err(vec![format!("Undeclared variable {}.", name)]),
+    };
+    use_var(env, name, term.tag());
+    if !is_storable(&ty) {
+        return Err(vec![format!("The variable {} at {} has type {}, which can't be borrowed.",
+                                name, show_position(term.tag()), ty)]);
+    }
+    Ok((name, ty))
+}
+
 // TODO: write a procedural macro for tagged whatever.
 impl Tagged<Type> for TaggedTerm<Type> {
     type Untagged = Term;
@@ -239,13 +1801,32 @@ impl Tagged<Type> for TaggedTerm<Type> {
             Scope(ref tag, _) => Box::new(tag.clone()),
             If(ref tag, _, _, _) => Box::new(tag.clone()),
             While(ref tag, _, _) => Box::new(tag.clone()),
-            Stmt(_) => {
-                let unit_enum = Enumeration {
-                    name: "Unit".to_string(),
-                    variants: vec!["unit".to_string()]
-                };
-                Box::new(Type::Enum(unit_enum))
-            }
+            BoolLiteral(ref tag, _) => Box::new(tag.clone()),
+            SuffixedLiteral(ref tag, _, _) => Box::new(tag.clone()),
+            Cast(ref tag, _, _) => Box::new(tag.clone()),
+            FloatLiteral(ref tag, _) => Box::new(tag.clone()),
+            CharLit(ref tag, _) => Box::new(tag.clone()),
+            StrLit(ref tag, _) => Box::new(tag.clone()),
+            Tuple(ref tag, _) => Box::new(tag.clone()),
+            Project(ref tag, _, _) => Box::new(tag.clone()),
+            ArrayLit(ref tag, _) => Box::new(tag.clone()),
+            Index(ref tag, _, _) => Box::new(tag.clone()),
+            StructLit(ref tag, _, _) => Box::new(tag.clone()),
+            Field(ref tag, _, _) => Box::new(tag.clone()),
+            Variant(ref tag, _, _, _) => Box::new(tag.clone()),
+            Match(ref tag, _, _) => Box::new(tag.clone()),
+            Borrow(ref tag, _) => Box::new(tag.clone()),
+            Deref(ref tag, _) => Box::new(tag.clone()),
+            BorrowMut(ref tag, _) => Box::new(tag.clone()),
+            Null(ref tag) => Box::new(tag.clone()),
+            PtrEq(ref tag, _, _) => Box::new(tag.clone()),
+            Lambda(ref tag, _, _) => Box::new(tag.clone()),
+            Apply(ref tag, _, _) => Box::new(tag.clone()),
+            MethodCall(ref tag, _, _, _) => Box::new(tag.clone()),
+            Ascribe(ref tag, _, _) => Box::new(tag.clone()),
+            Neg(ref tag, _) => Box::new(tag.clone()),
+            Not(ref tag, _) => Box::new(tag.clone()),
+            Stmt(_) => Box::new(Type::UnitTy),
         }
     }
 }
@@ -253,43 +1834,233 @@ impl Tagged<Type> for TaggedTerm<Type> {
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TaggedStatement<Tag> {
     TermSemicolon(Tag, TaggedTerm<Tag>),
-    Let(Tag, String, TaggedTerm<Tag>),
-    LetMut(Tag, String, TaggedTerm<Tag>),
+    // The type is the annotation, or Hole if it's left out.
+    Let(Tag, String, Type, TaggedTerm<Tag>),
+    LetMut(Tag, String, Type, TaggedTerm<Tag>),
     Mutate(Tag, String, TaggedTerm<Tag>),
     Extern(Tag, String, Type),
+    Declare(Tag, String, Type),
+    // `struct Point { x: I32, y: I32 };`
+    StructDecl(Tag, String, Vec<(String, Type)>),
+    EnumDecl(Tag, String, Vec<(String, Vec<Type>)>),
+    // `type Pair = (I32, I32);`. Once checked, the type is the expansion.
+    TypeAlias(Tag, String, Type),
+    // `*p = e;`, where the first term is the reference.
+    MutateThrough(Tag, TaggedTerm<Tag>, TaggedTerm<Tag>),
+}
+
+impl<Tag> TaggedStatement<Tag> {
+    pub fn tag(&self) -> &Tag {
+        use self::TaggedStatement::*;
+        match *self {
+            TermSemicolon(ref tag, _) | Let(ref tag, _, _, _) | LetMut(ref tag, _, _, _) |
+            Mutate(ref tag, _, _) | Extern(ref tag, _, _) | Declare(ref tag, _, _) |
+            StructDecl(ref tag, _, _) | EnumDecl(ref tag, _, _) | TypeAlias(ref tag, _, _) |
+            MutateThrough(ref tag, _, _) => tag,
+        }
+    }
 }
 
 impl TypeCheck for TaggedStatement<Position> {
     type Typed = TaggedStatement<Type>;
-    fn type_check(&self, mut env: &mut Map<Type>) -> Result<Self::Typed, Vec<String>> {
+    fn type_check<E: TypeEnv>(&self, env: &mut E) -> Result<Self::Typed, Vec<String>> {
         use self::TaggedStatement::*;
         use self::Type::*;
         match *self {
             TermSemicolon(_, ref term) => {
-                let unit_enum = Enumeration {
-                    name: "Unit".to_string(),
-                    variants: vec!["unit".to_string()]
-                };
                 let typed_term = try!(term.type_check(&mut env.clone()));
-                Ok(TermSemicolon(Enum(unit_enum), typed_term))
+                Ok(TermSemicolon(UnitTy, typed_term))
             }
-            Let(_, ref name, ref term) => {
-                let typed_term: TaggedTerm<Type> = try!(term.type_check(&mut env.clone()));
-                env.insert(name.clone(), *typed_term.get_tag());
-                Ok(Let(Forbidden, name.clone(), typed_term))
+            Let(ref pos, ref name, ref ty, ref term) => {
+                try!(check_identifier(name));
+                let (ty, typed_term) = try!(check_let(pos, name, ty, term, env));
+                bind_var(env, name, let_type(&ty, &typed_term), BindingKind::Let, pos);
+                Ok(Let(Forbidden, name.clone(), ty, typed_term))
             }
-            LetMut(_, ref name, ref term) => {
-                let typed_term: TaggedTerm<Type> = try!(term.type_check(&mut env.clone()));
-                env.insert(name.clone(), *typed_term.get_tag());
-                Ok(LetMut(Forbidden, name.clone(), typed_term))
+            LetMut(ref pos, ref name, ref ty, ref term) => {
+                try!(check_identifier(name));
+                let (ty, typed_term) = try!(check_let(pos, name, ty, term, env));
+                bind_var(env, name, let_type(&ty, &typed_term), BindingKind::LetMut, pos);
+                Ok(LetMut(Forbidden, name.clone(), ty, typed_term))
             }
-            Mutate(_, ref name, ref term) => {
-                let typed_term = try!(term.type_check(&mut env.clone()));
+            // Assigning a variable whose type was left out is what infers it.
+            Mutate(ref pos, ref name, ref term) => {
+                use_var(env, name, pos);
+                let typed_term = match env.get(name).cloned() {
+                    Some(var_ty) => try!(term.type_check_against(&mut env.clone(), &var_ty)),
+                    None => try!(term.type_check(&mut env.clone())),
+                };
+                if let Some(var_ty) = env.get(name).cloned() {
+                    let ty = *typed_term.get_tag();
+                    if !env.unify(&var_ty, &ty) {
+                        return Err(vec![format!("The variable {} has type {}, but it's assigned \
+                                                 a term of type {} at {}.",
+                                                name, env.zonk(&var_ty), env.zonk(&ty),
+                                                show_position(pos))]);
+                    }
+                }
                 Ok(Mutate(Forbidden, name.clone(), typed_term))
             }
-            Extern(_, ref name, ref ty) => {
-                env.insert(name.clone(), ty.clone());
-                Ok(Extern(Forbidden, name.clone(), ty.clone()))
+            Extern(ref pos, ref name, ref ty) => {
+                try!(check_identifier(name));
+                let ty = try!(resolve_type(ty, env));
+                // An extern's own type is the only one that can be generic.
+                match ty {
+                    FunctionTy(_, ref args_types, ref ret_ty) => {
+                        let signature = FunctionTy(Vec::new(), args_types.clone(), ret_ty.clone());
+                        try!(check_declared_type(&signature));
+                    }
+                    _ => try!(check_declared_type(&ty)),
+                }
+                bind_var(env, name, ty.clone(), BindingKind::Extern, pos);
+                Ok(Extern(Forbidden, name.clone(), ty))
+            }
+            Declare(ref pos, ref name, ref ty) => {
+                try!(check_identifier(name));
+                let ty = if *ty == Hole {
+                    let origin = format!("the variable {} declared at {}",
+                                         name, show_position(pos));
+                    try!(env.fresh_meta(origin.clone())
+                            .ok_or(vec![format!("Cannot infer the type of {}.", origin)]))
+                } else {
+                    try!(resolve_type(ty, env))
+                };
+                try!(check_declared_type(&ty));
+                bind_var(env, name, ty.clone(), BindingKind::LetMut, pos);
+                Ok(Declare(Forbidden, name.clone(), ty))
+            }
+            // An alias can only mention the types declared before it, so the only cycle it can
+            // make is through its own name.
+            TypeAlias(_, ref name, ref ty) => {
+                try!(check_identifier(name));
+                try!(check_not_prelude_type(name));
+                if env.declared_type(TypeKind::Alias, name).is_some() {
+                    return Err(vec![format!("The type alias {} is already declared.", name)]);
+                }
+                if mentions(ty, name) {
+                    return Err(vec![format!("The type alias {} is cyclic: {} -> {}.",
+                                            name, name, name)]);
+                }
+                let expansion = try!(resolve_type(ty, env));
+                try!(check_declared_type(&expansion));
+                env.declare_type(TypeKind::Alias, name.clone(), expansion.clone());
+                Ok(TypeAlias(Forbidden, name.clone(), expansion))
+            }
+            StructDecl(_, ref name, ref fields) => {
+                try!(check_identifier(name));
+                try!(check_not_prelude_type(name));
+                if env.declared_type(TypeKind::Struct, name).is_some() {
+                    return Err(vec![format!("The struct {} is already declared.", name)]);
+                }
+                let mut errors = Vec::new();
+                let mut resolved_fields = Vec::new();
+                for (i, &(ref field, ref ty)) in fields.iter().enumerate() {
+                    if let Err(field_errors) = check_identifier(field) {
+                        errors.extend(field_errors);
+                    }
+                    if fields[..i].iter().any(|&(ref other, _)| other == field) {
+                        errors.push(format!("The struct {} declares the field {} more than once.",
+                                            name, field));
+                    }
+                    let ty = match resolve_type(ty, env) {
+                        Ok(ty) => ty,
+                        Err(type_errors) => {
+                            errors.extend(type_errors);
+                            continue;
+                        }
+                    };
+                    if !is_storable(&ty) {
+                        errors.push(format!("The field {} of struct {} has type {}, which a \
+                                             struct can't hold.", field, name, ty));
+                    } else if let Err(type_errors) = check_declared_type(&ty) {
+                        errors.extend(type_errors);
+                    }
+                    resolved_fields.push((field.clone(), ty));
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                let structure = Structure::new(name.clone(), resolved_fields.clone());
+                env.declare_type(TypeKind::Struct, name.clone(), Struct(structure));
+                Ok(StructDecl(Forbidden, name.clone(), resolved_fields))
+            }
+            // An enum can't hold itself, which would make it infinitely large, so the payloads
+            // only see the types declared before it.
+            EnumDecl(_, ref name, ref variants) => {
+                try!(check_identifier(name));
+                try!(check_not_prelude_type(name));
+                if env.declared_type(TypeKind::Enum, name).is_some() {
+                    return Err(vec![format!("The enum {} is already declared.", name)]);
+                }
+                let mut errors = Vec::new();
+                let mut resolved_variants = Vec::new();
+                for (i, &(ref variant, ref payload)) in variants.iter().enumerate() {
+                    if let Err(variant_errors) = check_identifier(variant) {
+                        errors.extend(variant_errors);
+                    }
+                    if variants[..i].iter().any(|&(ref other, _)| other == variant) {
+                        errors.push(format!("The enum {} declares the variant {} more than once.",
+                                            name, variant));
+                    }
+                    let mut resolved_payload = Vec::new();
+                    for ty in payload {
+                        let ty = match resolve_type(ty, env) {
+                            Ok(ty) => ty,
+                            Err(_) if mentions(ty, name) => {
+                                errors.push(format!("The variant {} of enum {} holds the enum \
+                                                     itself, which would make it infinitely \
+                                                     large.", variant, name));
+                                continue;
+                            }
+                            Err(type_errors) => {
+                                errors.extend(type_errors);
+                                continue;
+                            }
+                        };
+                        if !is_storable(&ty) {
+                            errors.push(format!("The variant {} of enum {} holds a value of type \
+                                                 {}, which an enum can't hold.",
+                                                variant, name, ty));
+                        } else if let Err(type_errors) = check_declared_type(&ty) {
+                            errors.extend(type_errors);
+                        }
+                        resolved_payload.push(ty);
+                    }
+                    resolved_variants.push((variant.clone(), resolved_payload));
+                }
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                let enumeration = Enumeration::new(name.clone(), resolved_variants.clone());
+                env.declare_type(TypeKind::Enum, name.clone(), Enum(enumeration));
+                Ok(EnumDecl(Forbidden, name.clone(), resolved_variants))
+            }
+            // Only a mutable reference can be assigned through, with a value of the type it
+            // points to.
+            MutateThrough(_, ref target, ref term) => {
+                let typed_target = try!(target.type_check(&mut env.clone()));
+                let typed_term = try!(term.type_check(&mut env.clone()));
+                let referent = match env.zonk(&typed_target.get_tag()) {
+                    RefMut(referent) => *referent,
+                    ty @ Ref(_) => {
+                        return Err(vec![format!("The reference at {} has type {}, which can't be \
+                                                 assigned through; only a &mut reference can.",
+                                                show_position(target.tag()), ty)]);
+                    }
+                    ty => {
+                        return Err(vec![format!("The term at {} has type {}, which isn't a \
+                                                 reference, so it can't be assigned through.",
+                                                show_position(target.tag()), ty)]);
+                    }
+                };
+                let ty = *typed_term.get_tag();
+                if !env.unify(&ty, &referent) {
+                    return Err(vec![format!("The reference at {} points to a value of type {}, \
+                                             but the term at {} has type {}.",
+                                            show_position(target.tag()), referent,
+                                            show_position(term.tag()), ty)]);
+                }
+                Ok(MutateThrough(Forbidden, typed_target, typed_term))
             }
         }
     }
@@ -302,10 +2073,15 @@ impl Tagged<Type> for TaggedStatement<Type> {
         use self::TaggedStatement::*;
         match *self {
             TermSemicolon(ref ty, _) => Box::new(ty.clone()),
-            Let(ref ty, _, _) => Box::new(ty.clone()),
-            LetMut(ref ty, _, _) => Box::new(ty.clone()),
+            Let(ref ty, _, _, _) => Box::new(ty.clone()),
+            LetMut(ref ty, _, _, _) => Box::new(ty.clone()),
             Mutate(ref ty, _, _) => Box::new(ty.clone()),
             Extern(ref ty, _, _) => Box::new(ty.clone()),
+            Declare(ref ty, _, _) => Box::new(ty.clone()),
+            StructDecl(ref ty, _, _) => Box::new(ty.clone()),
+            EnumDecl(ref ty, _, _) => Box::new(ty.clone()),
+            TypeAlias(ref ty, _, _) => Box::new(ty.clone()),
+            MutateThrough(ref ty, _, _) => Box::new(ty.clone()),
         }
     }
 }
@@ -319,11 +2095,23 @@ pub struct TaggedBlock<Tag> {
 
 impl TypeCheck for TaggedBlock<Position> {
     type Typed = TaggedBlock<Type>;
-    fn type_check(&self, mut env: &mut Map<Type>) -> Result<Self::Typed, Vec<String>> {
+    fn type_check<E: TypeEnv>(&self, env: &mut E) -> Result<Self::Typed, Vec<String>> {
+        self.type_check_items(env, false)
+    }
+}
+
+impl TaggedBlock<Position> {
+    // Checks the block, telling `env` after each statement if it's main, see `checked_item`.
+    fn type_check_items<E: TypeEnv>(&self,
+                                    env: &mut E,
+                                    is_main: bool) -> Result<TaggedBlock<Type>, Vec<String>> {
         let mut tagged_stmts = Vec::new();
-        for stmt in &self.stmts {
+        for (done, stmt) in self.stmts.iter().enumerate() {
             let tagged_stmt = try!(stmt.type_check(env));
             tagged_stmts.push(tagged_stmt);
+            if is_main {
+                env.checked_item(done + 1, self.stmts.len());
+            }
         }
         let end = match *self.end {
             Some(ref term) => Some(try!(term.type_check(env))),
@@ -331,13 +2119,7 @@ impl TypeCheck for TaggedBlock<Position> {
         };
         let ty = match end.clone() {
             Some(tagged) => tagged.get_tag(),
-            None => {
-                let unit_enum = Enumeration {
-                    name: "Unit".to_string(),
-                    variants: vec!["unit".to_string()]
-                };
-                Box::new(Type::Enum(unit_enum))
-            }
+            None => Box::new(Type::UnitTy),
         };
         Ok(
             TaggedBlock {
@@ -362,18 +2144,135 @@ pub struct TaggedProgram<Tag> {
     pub main: TaggedBlock<Tag>,
 }
 
+// The exit code rule in `semantics`.
+pub fn check_main_type<E: TypeEnv>(env: &E, ty: &Type) -> Result<(), Vec<String>> {
+    match *ty {
+        Type::I32Ty => Ok(()),
+        Type::UnitTy => Ok(()),
+        // A main that never finishes never exits either.
+        Type::Never => Ok(()),
+        ref ty => {
+            let message = format!("The value of main is the exit code of the program, \
+                                   so it must have type I32, or Unit for exit code 0, \
+                                   but it has type {}.", ty);
+            gate(env, StrictRule::MainType, vec![message])
+        }
+    }
+}
+
+// The type `options` requires of main, if any. A main that never finishes has every type, like
+// any other term of type Never.
+pub fn check_expected_type(options: &CheckOptions, ty: &Type) -> Result<(), Vec<String>> {
+    match options.expected_program_type {
+        Some(ref expected) if *ty != Type::Never && ty != expected => {
+            Err(vec![format!("This script must produce {} as required by the host, \
+                              but it produces {}.", expected, ty)])
+        }
+        _ => Ok(()),
+    }
+}
+
 impl TypeCheck for TaggedProgram<Position> {
     type Typed = TaggedProgram<Type>;
-    fn type_check(&self, env: &mut Map<Type>) -> Result<Self::Typed, Vec<String>> {
+    fn type_check<E: TypeEnv>(&self, env: &mut E) -> Result<Self::Typed, Vec<String>> {
+        add_prelude(env);
+        if let Err(errors) = check_extern_placement(self) {
+            try!(gate(env, StrictRule::ExternPlacement, errors));
+        }
+        let main = if has_holes(self) {
+            let mut infer = InferEnv::new(env.clone());
+            let main = try!(self.main.type_check_items(&mut infer, true));
+            let main = try!(infer.solve_block(main));
+            *env = infer.into_inner();
+            main
+        } else {
+            try!(self.main.type_check_items(env, true))
+        };
+        try!(self.main.check_definite_assignment());
+        try!(check_main_type(env, &main.tag));
         Ok(
             TaggedProgram {
                 tag: Type::Forbidden,
-                main: try!(self.main.type_check(env))
+                main: main
             }
         )
     }
 }
 
+// What the host embedding the compiler requires of a program, on top of the typing rules.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CheckOptions {
+    // The type `main` must produce; any type is accepted when it's `None`.
+    pub expected_program_type: Option<Type>,
+    // Size limits, checked before anything else so huge inputs are refused cheaply.
+    // `None` disables a limit.
+    pub max_statements: Option<usize>,
+    pub max_nodes: Option<usize>,
+    pub max_identifier_length: Option<usize>,
+    // Rejects externs, loops and assignments, so the program terminates without side effects.
+    // Externs named in `safe_externs`, e.g. pure host functions, are still allowed.
+    pub safe_mode: bool,
+    pub safe_externs: Vec<String>,
+    // See `strictness`.
+    pub strictness: Strictness,
+    // Operators on top of the built-in ones, as (operator, left type, right type, result type),
+    // e.g. to try out new typing rules. This is a hook for checking only: codegen has no code
+    // for them, so `validate` refuses to compile a program that uses them.
+    pub check_only_operators: Vec<(Operator, Type, Type, Type)>,
+    // Turns a panic inside the checker into a bug diagnostic instead of unwinding into the host.
+    // Debug builds let panics through by default, so that the compiler's own tests see them.
+    pub catch_panics: bool,
+}
+
+impl Default for CheckOptions {
+    fn default() -> CheckOptions {
+        CheckOptions {
+            expected_program_type: None,
+            max_statements: Some(100_000),
+            max_nodes: Some(10_000_000),
+            max_identifier_length: Some(1024),
+            safe_mode: false,
+            safe_externs: Vec::new(),
+            strictness: Strictness::Strict,
+            check_only_operators: Vec::new(),
+            catch_panics: !cfg!(debug_assertions),
+        }
+    }
+}
+
+impl TaggedProgram<Position> {
+    pub fn type_check_with<E: TypeEnv>(&self,
+                                       env: &mut E,
+                                       options: &CheckOptions)
+                                       -> Result<TaggedProgram<Type>, Vec<String>> {
+        self.type_check_with_warnings(env, options).map(|(typed, _)| typed)
+    }
+
+    // Like `type_check_with`, but also returns the warnings of legacy mode.
+    pub fn type_check_with_warnings<E: TypeEnv>(&self,
+                                                env: &mut E,
+                                                options: &CheckOptions)
+                                                -> Result<(TaggedProgram<Type>, Vec<String>),
+                                                          Vec<String>> {
+        try!(check_size_limits(self, options));
+        try!(check_safe_subset(self, options));
+        add_operators(env, &options.check_only_operators);
+        let (typed, warnings) = match options.strictness {
+            Strictness::Strict => (try!(self.type_check(env)), Vec::new()),
+            // The program's bindings end up in `env` like in strict mode, even if it fails.
+            Strictness::Legacy => {
+                let mut legacy = LegacyEnv::new(env.clone());
+                let checked = self.type_check(&mut legacy);
+                let warnings = legacy.warnings();
+                *env = legacy.into_inner();
+                (try!(checked), warnings)
+            }
+        };
+        try!(check_expected_type(options, &typed.main.tag));
+        Ok((typed, warnings))
+    }
+}
+
 impl Tagged<Type> for TaggedProgram<Type> {
     type Untagged = Program;
     fn get_tag(&self) -> Box<Type> {
@@ -0,0 +1,821 @@
+// A versioned binary format for typed programs, so external tools can consume them without
+// linking this crate.
+//
+// The file starts with `MAGIC` and `FORMAT_VERSION` (a little-endian u32). After that every
+// node is a one-byte variant number followed by its fields in declaration order:
+// integers are little-endian, strings and vectors are prefixed by their u32 length, and
+// options are a 0 or 1 byte followed by the value if present.
+//
+// Bump `FORMAT_VERSION` whenever `Type`, `Operator` or any of the tagged trees change,
+// including adding a variant; readers reject every version but their own.
+
+use std::io::{self, Read, Write, Error, ErrorKind};
+
+use ast::{Operator, Pattern};
+use type_check::*;
+
+pub const MAGIC: &'static [u8; 4] = b"ENDE";
+pub const FORMAT_VERSION: u32 = 33;
+
+pub fn write_typed_ast<W: Write>(mut writer: W, program: &TaggedProgram<Type>) -> io::Result<()> {
+    try!(writer.write_all(MAGIC));
+    try!(write_u32(&mut writer, FORMAT_VERSION));
+    try!(write_type(&mut writer, &program.tag));
+    write_block(&mut writer, &program.main)
+}
+
+pub fn read_typed_ast<R: Read>(mut reader: R) -> io::Result<TaggedProgram<Type>> {
+    let mut magic = [0; 4];
+    try!(reader.read_exact(&mut magic));
+    if &magic != MAGIC {
+        return Err(invalid("not a typed Ende AST: wrong magic number".to_string()));
+    }
+    let version = try!(read_u32(&mut reader));
+    if version != FORMAT_VERSION {
+        return Err(invalid(format!("unsupported typed AST format version {}, expected {}",
+                                   version, FORMAT_VERSION)));
+    }
+    let tag = try!(read_type(&mut reader));
+    let main = try!(read_block(&mut reader));
+    Ok(TaggedProgram { tag: tag, main: main })
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn write_u8<W: Write>(writer: &mut W, byte: u8) -> io::Result<()> {
+    writer.write_all(&[byte])
+}
+
+fn write_u32<W: Write>(writer: &mut W, int: u32) -> io::Result<()> {
+    writer.write_all(&[int as u8, (int >> 8) as u8, (int >> 16) as u8, (int >> 24) as u8])
+}
+
+fn write_i32<W: Write>(writer: &mut W, int: i32) -> io::Result<()> {
+    write_u32(writer, int as u32)
+}
+
+fn write_i64<W: Write>(writer: &mut W, int: i64) -> io::Result<()> {
+    try!(write_u32(writer, int as u32));
+    write_u32(writer, (int >> 32) as u32)
+}
+
+fn write_str<W: Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    try!(write_u32(writer, string.len() as u32));
+    writer.write_all(string.as_bytes())
+}
+
+fn write_fields<W: Write>(writer: &mut W, fields: &[(String, Type)]) -> io::Result<()> {
+    try!(write_u32(writer, fields.len() as u32));
+    for &(ref field, ref ty) in fields {
+        try!(write_str(writer, field));
+        try!(write_type(writer, ty));
+    }
+    Ok(())
+}
+
+fn write_variants<W: Write>(writer: &mut W, variants: &[(String, Vec<Type>)]) -> io::Result<()> {
+    try!(write_u32(writer, variants.len() as u32));
+    for &(ref variant, ref payload) in variants {
+        try!(write_str(writer, variant));
+        try!(write_u32(writer, payload.len() as u32));
+        for ty in payload {
+            try!(write_type(writer, ty));
+        }
+    }
+    Ok(())
+}
+
+fn write_type<W: Write>(writer: &mut W, ty: &Type) -> io::Result<()> {
+    use type_check::Type::*;
+    match *ty {
+        Forbidden => write_u8(writer, 0),
+        I32Ty => write_u8(writer, 1),
+        Enum(ref en) => {
+            try!(write_u8(writer, 2));
+            try!(write_str(writer, &en.name));
+            write_variants(writer, en.variants())
+        }
+        FunctionTy(ref params, ref args_types, ref ret_type) => {
+            try!(write_u8(writer, 3));
+            try!(write_u32(writer, params.len() as u32));
+            for param in params {
+                try!(write_str(writer, param));
+            }
+            try!(write_u32(writer, args_types.len() as u32));
+            for arg_ty in args_types {
+                try!(write_type(writer, arg_ty));
+            }
+            write_type(writer, ret_type)
+        }
+        BoolTy => write_u8(writer, 4),
+        I64Ty => write_u8(writer, 5),
+        U32Ty => write_u8(writer, 6),
+        I8Ty => write_u8(writer, 7),
+        I16Ty => write_u8(writer, 8),
+        F64Ty => write_u8(writer, 9),
+        CharTy => write_u8(writer, 10),
+        StrTy => write_u8(writer, 11),
+        UnitTy => write_u8(writer, 12),
+        TupleTy(ref elems) => {
+            try!(write_u8(writer, 13));
+            try!(write_u32(writer, elems.len() as u32));
+            for elem in elems {
+                try!(write_type(writer, elem));
+            }
+            Ok(())
+        }
+        ArrayTy(ref elem, len) => {
+            try!(write_u8(writer, 14));
+            try!(write_type(writer, elem));
+            write_u32(writer, len)
+        }
+        Struct(ref structure) => {
+            try!(write_u8(writer, 15));
+            try!(write_str(writer, &structure.name));
+            write_fields(writer, structure.fields())
+        }
+        Named(ref name) => {
+            try!(write_u8(writer, 16));
+            write_str(writer, name)
+        }
+        Ref(ref referent) => {
+            try!(write_u8(writer, 17));
+            write_type(writer, referent)
+        }
+        RefMut(ref referent) => {
+            try!(write_u8(writer, 18));
+            write_type(writer, referent)
+        }
+        Ptr => write_u8(writer, 19),
+        Param(ref name) => {
+            try!(write_u8(writer, 20));
+            write_str(writer, name)
+        }
+        Hole => write_u8(writer, 21),
+        Meta(meta) => {
+            try!(write_u8(writer, 22));
+            write_u32(writer, meta)
+        }
+        Never => write_u8(writer, 23),
+    }
+}
+
+fn write_operator<W: Write>(writer: &mut W, op: Operator) -> io::Result<()> {
+    use ast::Operator::*;
+    write_u8(writer, match op {
+        Add => 0,
+        Sub => 1,
+        Mul => 2,
+        Div => 3,
+        Eq => 4,
+        Ne => 5,
+        Lt => 6,
+        Le => 7,
+        Gt => 8,
+        Ge => 9,
+        And => 10,
+        Or => 11,
+        BitAnd => 12,
+        BitOr => 13,
+        BitXor => 14,
+        Rem => 15,
+    })
+}
+
+fn write_term<W: Write>(writer: &mut W, term: &TaggedTerm<Type>) -> io::Result<()> {
+    use type_check::TaggedTerm::*;
+    match *term {
+        Literal(ref tag, i) => {
+            try!(write_u8(writer, 0));
+            try!(write_type(writer, tag));
+            write_i32(writer, i)
+        }
+        Var(ref tag, ref name) => {
+            try!(write_u8(writer, 1));
+            try!(write_type(writer, tag));
+            write_str(writer, name)
+        }
+        Infix(ref tag, ref left, op, ref right) => {
+            try!(write_u8(writer, 2));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, left));
+            try!(write_operator(writer, op));
+            write_term(writer, right)
+        }
+        Call(ref tag, ref func, ref args) => {
+            try!(write_u8(writer, 3));
+            try!(write_type(writer, tag));
+            try!(write_type(writer, &func.tag));
+            try!(write_str(writer, &func.name));
+            try!(write_u32(writer, args.len() as u32));
+            for arg in args {
+                try!(write_term(writer, arg));
+            }
+            Ok(())
+        }
+        Scope(ref tag, ref block) => {
+            try!(write_u8(writer, 4));
+            try!(write_type(writer, tag));
+            write_block(writer, block)
+        }
+        If(ref tag, ref cond, ref if_true, ref if_false) => {
+            try!(write_u8(writer, 5));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, cond));
+            try!(write_term(writer, if_true));
+            match *if_false {
+                Some(ref term) => {
+                    try!(write_u8(writer, 1));
+                    write_term(writer, term)
+                }
+                None => write_u8(writer, 0),
+            }
+        }
+        While(ref tag, ref cond, ref block) => {
+            try!(write_u8(writer, 6));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, cond));
+            write_block(writer, block)
+        }
+        Stmt(ref stmt) => {
+            try!(write_u8(writer, 7));
+            write_statement(writer, stmt)
+        }
+        BoolLiteral(ref tag, b) => {
+            try!(write_u8(writer, 8));
+            try!(write_type(writer, tag));
+            write_u8(writer, b as u8)
+        }
+        SuffixedLiteral(ref tag, i, ref ty) => {
+            try!(write_u8(writer, 9));
+            try!(write_type(writer, tag));
+            try!(write_i64(writer, i));
+            write_type(writer, ty)
+        }
+        Cast(ref tag, ref term, ref ty) => {
+            try!(write_u8(writer, 10));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, term));
+            write_type(writer, ty)
+        }
+        FloatLiteral(ref tag, bits) => {
+            try!(write_u8(writer, 11));
+            try!(write_type(writer, tag));
+            write_i64(writer, bits as i64)
+        }
+        CharLit(ref tag, c) => {
+            try!(write_u8(writer, 12));
+            try!(write_type(writer, tag));
+            write_u32(writer, c as u32)
+        }
+        StrLit(ref tag, ref s) => {
+            try!(write_u8(writer, 13));
+            try!(write_type(writer, tag));
+            write_str(writer, s)
+        }
+        Tuple(ref tag, ref elems) => {
+            try!(write_u8(writer, 14));
+            try!(write_type(writer, tag));
+            try!(write_u32(writer, elems.len() as u32));
+            for elem in elems {
+                try!(write_term(writer, elem));
+            }
+            Ok(())
+        }
+        Project(ref tag, ref term, index) => {
+            try!(write_u8(writer, 15));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, term));
+            write_u32(writer, index)
+        }
+        ArrayLit(ref tag, ref elems) => {
+            try!(write_u8(writer, 16));
+            try!(write_type(writer, tag));
+            try!(write_u32(writer, elems.len() as u32));
+            for elem in elems {
+                try!(write_term(writer, elem));
+            }
+            Ok(())
+        }
+        Index(ref tag, ref array, ref index) => {
+            try!(write_u8(writer, 17));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, array));
+            write_term(writer, index)
+        }
+        StructLit(ref tag, ref name, ref inits) => {
+            try!(write_u8(writer, 18));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            try!(write_u32(writer, inits.len() as u32));
+            for &(ref field, ref term) in inits {
+                try!(write_str(writer, field));
+                try!(write_term(writer, term));
+            }
+            Ok(())
+        }
+        Field(ref tag, ref term, ref field) => {
+            try!(write_u8(writer, 19));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, term));
+            write_str(writer, field)
+        }
+        Variant(ref tag, ref name, ref variant, ref args) => {
+            try!(write_u8(writer, 20));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            try!(write_str(writer, variant));
+            try!(write_u32(writer, args.len() as u32));
+            for arg in args {
+                try!(write_term(writer, arg));
+            }
+            Ok(())
+        }
+        Match(ref tag, ref scrutinee, ref arms) => {
+            try!(write_u8(writer, 21));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, scrutinee));
+            try!(write_u32(writer, arms.len() as u32));
+            for &(ref pattern, ref body) in arms {
+                try!(write_pattern(writer, pattern));
+                try!(write_term(writer, body));
+            }
+            Ok(())
+        }
+        Borrow(ref tag, ref term) => {
+            try!(write_u8(writer, 22));
+            try!(write_type(writer, tag));
+            write_term(writer, term)
+        }
+        Deref(ref tag, ref term) => {
+            try!(write_u8(writer, 23));
+            try!(write_type(writer, tag));
+            write_term(writer, term)
+        }
+        BorrowMut(ref tag, ref term) => {
+            try!(write_u8(writer, 24));
+            try!(write_type(writer, tag));
+            write_term(writer, term)
+        }
+        Null(ref tag) => {
+            try!(write_u8(writer, 25));
+            write_type(writer, tag)
+        }
+        PtrEq(ref tag, ref left, ref right) => {
+            try!(write_u8(writer, 26));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, left));
+            write_term(writer, right)
+        }
+        Lambda(ref tag, ref params, ref body) => {
+            try!(write_u8(writer, 27));
+            try!(write_type(writer, tag));
+            try!(write_fields(writer, params));
+            write_block(writer, body)
+        }
+        Apply(ref tag, ref callee, ref args) => {
+            try!(write_u8(writer, 28));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, callee));
+            try!(write_u32(writer, args.len() as u32));
+            for arg in args {
+                try!(write_term(writer, arg));
+            }
+            Ok(())
+        }
+        Ascribe(ref tag, ref term, ref ty) => {
+            try!(write_u8(writer, 29));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, term));
+            write_type(writer, ty)
+        }
+        Neg(ref tag, ref term) => {
+            try!(write_u8(writer, 30));
+            try!(write_type(writer, tag));
+            write_term(writer, term)
+        }
+        Not(ref tag, ref term) => {
+            try!(write_u8(writer, 31));
+            try!(write_type(writer, tag));
+            write_term(writer, term)
+        }
+        // Checking turns them into calls.
+        MethodCall(..) => Err(invalid("method calls can't be in a typed tree".to_string())),
+    }
+}
+
+fn write_pattern<W: Write>(writer: &mut W, pattern: &Pattern) -> io::Result<()> {
+    match *pattern {
+        Pattern::Constructor(ref name, ref variant, ref binds) => {
+            try!(write_u8(writer, 0));
+            try!(write_str(writer, name));
+            try!(write_str(writer, variant));
+            try!(write_u32(writer, binds.len() as u32));
+            for bind in binds {
+                try!(write_str(writer, bind));
+            }
+            Ok(())
+        }
+        Pattern::Wildcard => write_u8(writer, 1),
+    }
+}
+
+fn write_statement<W: Write>(writer: &mut W, stmt: &TaggedStatement<Type>) -> io::Result<()> {
+    use type_check::TaggedStatement::*;
+    match *stmt {
+        TermSemicolon(ref tag, ref term) => {
+            try!(write_u8(writer, 0));
+            try!(write_type(writer, tag));
+            write_term(writer, term)
+        }
+        Let(ref tag, ref name, ref ty, ref rhs) => {
+            try!(write_u8(writer, 1));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            try!(write_type(writer, ty));
+            write_term(writer, rhs)
+        }
+        LetMut(ref tag, ref name, ref ty, ref rhs) => {
+            try!(write_u8(writer, 2));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            try!(write_type(writer, ty));
+            write_term(writer, rhs)
+        }
+        Mutate(ref tag, ref name, ref rhs) => {
+            try!(write_u8(writer, 3));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            write_term(writer, rhs)
+        }
+        Extern(ref tag, ref name, ref ty) => {
+            try!(write_u8(writer, 4));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            write_type(writer, ty)
+        }
+        Declare(ref tag, ref name, ref ty) => {
+            try!(write_u8(writer, 5));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            write_type(writer, ty)
+        }
+        StructDecl(ref tag, ref name, ref fields) => {
+            try!(write_u8(writer, 6));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            write_fields(writer, fields)
+        }
+        TypeAlias(ref tag, ref name, ref ty) => {
+            try!(write_u8(writer, 7));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            write_type(writer, ty)
+        }
+        MutateThrough(ref tag, ref target, ref rhs) => {
+            try!(write_u8(writer, 8));
+            try!(write_type(writer, tag));
+            try!(write_term(writer, target));
+            write_term(writer, rhs)
+        }
+        EnumDecl(ref tag, ref name, ref variants) => {
+            try!(write_u8(writer, 9));
+            try!(write_type(writer, tag));
+            try!(write_str(writer, name));
+            write_variants(writer, variants)
+        }
+    }
+}
+
+fn write_block<W: Write>(writer: &mut W, block: &TaggedBlock<Type>) -> io::Result<()> {
+    try!(write_type(writer, &block.tag));
+    try!(write_u32(writer, block.stmts.len() as u32));
+    for stmt in &block.stmts {
+        try!(write_statement(writer, stmt));
+    }
+    match *block.end {
+        Some(ref term) => {
+            try!(write_u8(writer, 1));
+            write_term(writer, term)
+        }
+        None => write_u8(writer, 0),
+    }
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0; 1];
+    try!(reader.read_exact(&mut byte));
+    Ok(byte[0])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    try!(reader.read_exact(&mut bytes));
+    Ok(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 |
+       (bytes[3] as u32) << 24)
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> io::Result<i32> {
+    read_u32(reader).map(|int| int as i32)
+}
+
+fn read_i64<R: Read>(reader: &mut R) -> io::Result<i64> {
+    let low = try!(read_u32(reader)) as u64;
+    let high = try!(read_u32(reader)) as u64;
+    Ok((low | high << 32) as i64)
+}
+
+fn read_str<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len = try!(read_u32(reader)) as usize;
+    let mut bytes = Vec::new();
+    try!(reader.take(len as u64).read_to_end(&mut bytes));
+    if bytes.len() != len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "truncated string"));
+    }
+    String::from_utf8(bytes).map_err(|_| invalid("string isn't valid UTF-8".to_string()))
+}
+
+fn read_vec<R: Read, T, F>(reader: &mut R, read_element: F) -> io::Result<Vec<T>>
+    where F: Fn(&mut R) -> io::Result<T> {
+    let len = try!(read_u32(reader));
+    let mut vec = Vec::new();
+    for _ in 0..len {
+        vec.push(try!(read_element(reader)));
+    }
+    Ok(vec)
+}
+
+fn read_field<R: Read>(reader: &mut R) -> io::Result<(String, Type)> {
+    let field = try!(read_str(reader));
+    Ok((field, try!(read_type(reader))))
+}
+
+fn read_variant<R: Read>(reader: &mut R) -> io::Result<(String, Vec<Type>)> {
+    let variant = try!(read_str(reader));
+    Ok((variant, try!(read_vec(reader, read_type))))
+}
+
+fn read_type<R: Read>(reader: &mut R) -> io::Result<Type> {
+    use type_check::Type::*;
+    match try!(read_u8(reader)) {
+        0 => Ok(Forbidden),
+        1 => Ok(I32Ty),
+        2 => {
+            let name = try!(read_str(reader));
+            let variants = try!(read_vec(reader, read_variant));
+            Ok(Enum(Enumeration::new(name, variants)))
+        }
+        3 => {
+            let params = try!(read_vec(reader, read_str));
+            let args_types = try!(read_vec(reader, read_type));
+            let ret_type = try!(read_type(reader));
+            Ok(FunctionTy(params, args_types, Box::new(ret_type)))
+        }
+        4 => Ok(BoolTy),
+        5 => Ok(I64Ty),
+        6 => Ok(U32Ty),
+        7 => Ok(I8Ty),
+        8 => Ok(I16Ty),
+        9 => Ok(F64Ty),
+        10 => Ok(CharTy),
+        11 => Ok(StrTy),
+        12 => Ok(UnitTy),
+        13 => Ok(TupleTy(try!(read_vec(reader, read_type)))),
+        14 => {
+            let elem = try!(read_type(reader));
+            Ok(ArrayTy(Box::new(elem), try!(read_u32(reader))))
+        }
+        15 => {
+            let name = try!(read_str(reader));
+            Ok(Struct(Structure::new(name, try!(read_vec(reader, read_field)))))
+        }
+        16 => Ok(Named(try!(read_str(reader)))),
+        17 => Ok(Ref(Box::new(try!(read_type(reader))))),
+        18 => Ok(RefMut(Box::new(try!(read_type(reader))))),
+        19 => Ok(Ptr),
+        20 => Ok(Param(try!(read_str(reader)))),
+        21 => Ok(Hole),
+        22 => Ok(Meta(try!(read_u32(reader)))),
+        23 => Ok(Never),
+        other => Err(invalid(format!("unknown type variant {}", other))),
+    }
+}
+
+fn read_operator<R: Read>(reader: &mut R) -> io::Result<Operator> {
+    use ast::Operator::*;
+    match try!(read_u8(reader)) {
+        0 => Ok(Add),
+        1 => Ok(Sub),
+        2 => Ok(Mul),
+        3 => Ok(Div),
+        4 => Ok(Eq),
+        5 => Ok(Ne),
+        6 => Ok(Lt),
+        7 => Ok(Le),
+        8 => Ok(Gt),
+        9 => Ok(Ge),
+        10 => Ok(And),
+        11 => Ok(Or),
+        12 => Ok(BitAnd),
+        13 => Ok(BitOr),
+        14 => Ok(BitXor),
+        15 => Ok(Rem),
+        other => Err(invalid(format!("unknown operator {}", other))),
+    }
+}
+
+fn read_term<R: Read>(reader: &mut R) -> io::Result<TaggedTerm<Type>> {
+    use type_check::TaggedTerm::*;
+    let variant = try!(read_u8(reader));
+    if variant == 7 {
+        return Ok(Stmt(Box::new(try!(read_statement(reader)))));
+    }
+    let tag = try!(read_type(reader));
+    match variant {
+        0 => Ok(Literal(tag, try!(read_i32(reader)))),
+        1 => Ok(Var(tag, try!(read_str(reader)))),
+        2 => {
+            let left = try!(read_term(reader));
+            let op = try!(read_operator(reader));
+            let right = try!(read_term(reader));
+            Ok(Infix(tag, Box::new(left), op, Box::new(right)))
+        }
+        3 => {
+            let func_tag = try!(read_type(reader));
+            let name = try!(read_str(reader));
+            let args = try!(read_vec(reader, read_term));
+            Ok(Call(tag, TaggedFunctionCall { tag: func_tag, name: name }, args))
+        }
+        4 => Ok(Scope(tag, try!(read_block(reader)))),
+        5 => {
+            let cond = try!(read_term(reader));
+            let if_true = try!(read_term(reader));
+            let if_false = match try!(read_u8(reader)) {
+                0 => None,
+                1 => Some(Box::new(try!(read_term(reader)))),
+                other => return Err(invalid(format!("invalid option marker {}", other))),
+            };
+            Ok(If(tag, Box::new(cond), Box::new(if_true), if_false))
+        }
+        6 => {
+            let cond = try!(read_term(reader));
+            let block = try!(read_block(reader));
+            Ok(While(tag, Box::new(cond), block))
+        }
+        8 => match try!(read_u8(reader)) {
+            0 => Ok(BoolLiteral(tag, false)),
+            1 => Ok(BoolLiteral(tag, true)),
+            other => Err(invalid(format!("invalid boolean {}", other))),
+        },
+        9 => {
+            let i = try!(read_i64(reader));
+            Ok(SuffixedLiteral(tag, i, try!(read_type(reader))))
+        }
+        10 => {
+            let term = try!(read_term(reader));
+            Ok(Cast(tag, Box::new(term), try!(read_type(reader))))
+        }
+        11 => Ok(FloatLiteral(tag, try!(read_i64(reader)) as u64)),
+        12 => {
+            let code = try!(read_u32(reader));
+            match ::std::char::from_u32(code) {
+                Some(c) => Ok(CharLit(tag, c)),
+                None => Err(invalid(format!("invalid code point {}", code))),
+            }
+        }
+        13 => Ok(StrLit(tag, try!(read_str(reader)))),
+        14 => Ok(Tuple(tag, try!(read_vec(reader, read_term)))),
+        15 => {
+            let term = try!(read_term(reader));
+            Ok(Project(tag, Box::new(term), try!(read_u32(reader))))
+        }
+        16 => Ok(ArrayLit(tag, try!(read_vec(reader, read_term)))),
+        17 => {
+            let array = try!(read_term(reader));
+            let index = try!(read_term(reader));
+            Ok(Index(tag, Box::new(array), Box::new(index)))
+        }
+        18 => {
+            let name = try!(read_str(reader));
+            let inits = try!(read_vec(reader, |reader| {
+                let field = try!(read_str(reader));
+                Ok((field, try!(read_term(reader))))
+            }));
+            Ok(StructLit(tag, name, inits))
+        }
+        19 => {
+            let term = try!(read_term(reader));
+            Ok(Field(tag, Box::new(term), try!(read_str(reader))))
+        }
+        20 => {
+            let name = try!(read_str(reader));
+            let variant = try!(read_str(reader));
+            Ok(Variant(tag, name, variant, try!(read_vec(reader, read_term))))
+        }
+        21 => {
+            let scrutinee = try!(read_term(reader));
+            let arms = try!(read_vec(reader, |reader| {
+                let pattern = try!(read_pattern(reader));
+                Ok((pattern, try!(read_term(reader))))
+            }));
+            Ok(Match(tag, Box::new(scrutinee), arms))
+        }
+        22 => Ok(Borrow(tag, Box::new(try!(read_term(reader))))),
+        23 => Ok(Deref(tag, Box::new(try!(read_term(reader))))),
+        24 => Ok(BorrowMut(tag, Box::new(try!(read_term(reader))))),
+        25 => Ok(Null(tag)),
+        26 => {
+            let left = try!(read_term(reader));
+            let right = try!(read_term(reader));
+            Ok(PtrEq(tag, Box::new(left), Box::new(right)))
+        }
+        27 => {
+            let params = try!(read_vec(reader, read_field));
+            Ok(Lambda(tag, params, try!(read_block(reader))))
+        }
+        28 => {
+            let callee = try!(read_term(reader));
+            Ok(Apply(tag, Box::new(callee), try!(read_vec(reader, read_term))))
+        }
+        29 => {
+            let term = try!(read_term(reader));
+            Ok(Ascribe(tag, Box::new(term), try!(read_type(reader))))
+        }
+        30 => Ok(Neg(tag, Box::new(try!(read_term(reader))))),
+        31 => Ok(Not(tag, Box::new(try!(read_term(reader))))),
+        other => Err(invalid(format!("unknown term variant {}", other))),
+    }
+}
+
+fn read_pattern<R: Read>(reader: &mut R) -> io::Result<Pattern> {
+    match try!(read_u8(reader)) {
+        0 => {
+            let name = try!(read_str(reader));
+            let variant = try!(read_str(reader));
+            Ok(Pattern::Constructor(name, variant, try!(read_vec(reader, read_str))))
+        }
+        1 => Ok(Pattern::Wildcard),
+        other => Err(invalid(format!("unknown pattern variant {}", other))),
+    }
+}
+
+fn read_statement<R: Read>(reader: &mut R) -> io::Result<TaggedStatement<Type>> {
+    use type_check::TaggedStatement::*;
+    let variant = try!(read_u8(reader));
+    let tag = try!(read_type(reader));
+    match variant {
+        0 => Ok(TermSemicolon(tag, try!(read_term(reader)))),
+        1 => {
+            let name = try!(read_str(reader));
+            let ty = try!(read_type(reader));
+            Ok(Let(tag, name, ty, try!(read_term(reader))))
+        }
+        2 => {
+            let name = try!(read_str(reader));
+            let ty = try!(read_type(reader));
+            Ok(LetMut(tag, name, ty, try!(read_term(reader))))
+        }
+        3 => {
+            let name = try!(read_str(reader));
+            Ok(Mutate(tag, name, try!(read_term(reader))))
+        }
+        4 => {
+            let name = try!(read_str(reader));
+            Ok(Extern(tag, name, try!(read_type(reader))))
+        }
+        5 => {
+            let name = try!(read_str(reader));
+            Ok(Declare(tag, name, try!(read_type(reader))))
+        }
+        6 => {
+            let name = try!(read_str(reader));
+            Ok(StructDecl(tag, name, try!(read_vec(reader, read_field))))
+        }
+        7 => {
+            let name = try!(read_str(reader));
+            Ok(TypeAlias(tag, name, try!(read_type(reader))))
+        }
+        8 => {
+            let target = try!(read_term(reader));
+            Ok(MutateThrough(tag, target, try!(read_term(reader))))
+        }
+        9 => {
+            let name = try!(read_str(reader));
+            Ok(EnumDecl(tag, name, try!(read_vec(reader, read_variant))))
+        }
+        other => Err(invalid(format!("unknown statement variant {}", other))),
+    }
+}
+
+fn read_block<R: Read>(reader: &mut R) -> io::Result<TaggedBlock<Type>> {
+    let tag = try!(read_type(reader));
+    let stmts = try!(read_vec(reader, read_statement));
+    let end = match try!(read_u8(reader)) {
+        0 => None,
+        1 => Some(try!(read_term(reader))),
+        other => return Err(invalid(format!("invalid option marker {}", other))),
+    };
+    Ok(TaggedBlock { tag: tag, stmts: stmts, end: Box::new(end) })
+}
@@ -0,0 +1,304 @@
+// Building terms by hand with their types checked as they're combined, so that a mistake is
+// reported where the term is built rather than when the whole program is checked.
+// A term built here checks in any env where the variables and functions it mentions have the
+// types they were given.
+
+use ast::{Operator, Position};
+use type_check::*;
+
+// Built terms have no place in the source.
+fn generated() -> Position {
+    Position { start_pos: (0, 0), end_pos: (0, 0) }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TypedExpr {
+    term: TaggedTerm<Position>,
+    ty: Type,
+}
+
+impl TypedExpr {
+    pub fn lit_i32(i: i32) -> TypedExpr {
+        TypedExpr { term: TaggedTerm::Literal(generated(), i), ty: Type::I32Ty }
+    }
+
+    pub fn lit_i64(i: i64) -> TypedExpr {
+        TypedExpr {
+            term: TaggedTerm::SuffixedLiteral(generated(), i, Type::I64Ty),
+            ty: Type::I64Ty,
+        }
+    }
+
+    pub fn lit_u32(i: u32) -> TypedExpr {
+        TypedExpr {
+            term: TaggedTerm::SuffixedLiteral(generated(), i as i64, Type::U32Ty),
+            ty: Type::U32Ty,
+        }
+    }
+
+    pub fn lit_f64(f: f64) -> TypedExpr {
+        TypedExpr { term: TaggedTerm::FloatLiteral(generated(), f.to_bits()), ty: Type::F64Ty }
+    }
+
+    pub fn lit_char(c: char) -> TypedExpr {
+        TypedExpr { term: TaggedTerm::CharLit(generated(), c), ty: Type::CharTy }
+    }
+
+    pub fn lit_str(s: &str) -> TypedExpr {
+        TypedExpr { term: TaggedTerm::StrLit(generated(), s.to_string()), ty: Type::StrTy }
+    }
+
+    pub fn lit_bool(b: bool) -> TypedExpr {
+        TypedExpr { term: TaggedTerm::BoolLiteral(generated(), b), ty: Type::BoolTy }
+    }
+
+    // A variable the env is expected to bind at `ty`.
+    pub fn var(name: &str, ty: Type) -> TypedExpr {
+        TypedExpr { term: TaggedTerm::Var(generated(), name.to_string()), ty: ty }
+    }
+
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    pub fn into_term(self) -> TaggedTerm<Position> {
+        self.term
+    }
+
+    pub fn infix(self, op: Operator, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        let ty = match builtin_operator_type(op, &self.ty, &right.ty) {
+            Some(ty) => ty,
+            None => return Err(vec![format!("The left-hand-side of {} has type {}, \
+                                             but the right-hand-side of it has type {}.",
+                                            op, self.ty, right.ty)]),
+        };
+        Ok(TypedExpr {
+            term: TaggedTerm::Infix(generated(), Box::new(self.term), op, Box::new(right.term)),
+            ty: ty,
+        })
+    }
+
+    pub fn add(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Add, right)
+    }
+
+    pub fn sub(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Sub, right)
+    }
+
+    pub fn mul(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Mul, right)
+    }
+
+    pub fn div(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Div, right)
+    }
+
+    pub fn rem(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Rem, right)
+    }
+
+    pub fn eq(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Eq, right)
+    }
+
+    pub fn ne(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Ne, right)
+    }
+
+    pub fn lt(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Lt, right)
+    }
+
+    pub fn le(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Le, right)
+    }
+
+    pub fn gt(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Gt, right)
+    }
+
+    pub fn ge(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Ge, right)
+    }
+
+    pub fn and(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::And, right)
+    }
+
+    pub fn or(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::Or, right)
+    }
+
+    pub fn bit_and(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::BitAnd, right)
+    }
+
+    pub fn bit_or(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::BitOr, right)
+    }
+
+    pub fn bit_xor(self, right: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        self.infix(Operator::BitXor, right)
+    }
+
+    pub fn cast(self, target: Type) -> Result<TypedExpr, Vec<String>> {
+        if !can_cast(&self.ty, &target) {
+            return Err(vec![format!("Can't cast from {} to {}; casts only go between integer \
+                                     types, or from Char.", self.ty, target)]);
+        }
+        Ok(TypedExpr {
+            term: TaggedTerm::Cast(generated(), Box::new(self.term), target.clone()),
+            ty: target,
+        })
+    }
+
+    pub fn tuple(elems: Vec<TypedExpr>) -> Result<TypedExpr, Vec<String>> {
+        if elems.len() < 2 {
+            return Err(vec![format!("A tuple needs at least two elements, but {} are given.",
+                                    elems.len())]);
+        }
+        for elem in &elems {
+            if !is_storable(&elem.ty) {
+                return Err(vec![format!("A tuple can't hold a value of type {}.", elem.ty)]);
+            }
+        }
+        let ty = Type::TupleTy(elems.iter().map(|elem| elem.ty.clone()).collect());
+        let elems = elems.into_iter().map(|elem| elem.term).collect();
+        Ok(TypedExpr { term: TaggedTerm::Tuple(generated(), elems), ty: ty })
+    }
+
+    pub fn project(self, index: u32) -> Result<TypedExpr, Vec<String>> {
+        let elem_ty = match self.ty {
+            Type::TupleTy(ref elems) if (index as usize) < elems.len() =>
+                elems[index as usize].clone(),
+            _ => return Err(vec![format!("Type {} has no element {}.", self.ty, index)]),
+        };
+        Ok(TypedExpr {
+            term: TaggedTerm::Project(generated(), Box::new(self.term), index),
+            ty: elem_ty,
+        })
+    }
+
+    pub fn array(elems: Vec<TypedExpr>) -> Result<TypedExpr, Vec<String>> {
+        let elem_ty = match elems.first() {
+            Some(first) => first.ty.clone(),
+            None => return Err(vec![format!("An array needs at least one element.")]),
+        };
+        if !is_storable(&elem_ty) {
+            return Err(vec![format!("An array can't hold a value of type {}.", elem_ty)]);
+        }
+        for elem in &elems {
+            if elem.ty != elem_ty {
+                return Err(vec![format!("The elements of an array have type {}, but one of them \
+                                         has type {}.", elem_ty, elem.ty)]);
+            }
+        }
+        let ty = Type::ArrayTy(Box::new(elem_ty), elems.len() as u32);
+        let elems = elems.into_iter().map(|elem| elem.term).collect();
+        Ok(TypedExpr { term: TaggedTerm::ArrayLit(generated(), elems), ty: ty })
+    }
+
+    pub fn index(self, index: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        let elem_ty = match self.ty {
+            Type::ArrayTy(ref elem, _) => *elem.clone(),
+            _ => return Err(vec![format!("Type {} isn't an array, so it can't be indexed.",
+                                         self.ty)]),
+        };
+        if index.ty != Type::I32Ty {
+            return Err(vec![format!("An index has type {}, but indices must have type I32.",
+                                    index.ty)]);
+        }
+        Ok(TypedExpr {
+            term: TaggedTerm::Index(generated(), Box::new(self.term), Box::new(index.term)),
+            ty: elem_ty,
+        })
+    }
+
+    // The variant called `variant` of `enumeration`, which the env is expected to bind.
+    pub fn variant(enumeration: &Enumeration,
+                   variant: &str,
+                   payload: Vec<TypedExpr>) -> Result<TypedExpr, Vec<String>> {
+        let payload_types = match enumeration.variant(variant) {
+            Some((_, payload_types)) => payload_types,
+            None => {
+                return Err(vec![format!("Enum {} has no variant {}; its variants are {}.",
+                                        enumeration, variant,
+                                        enumeration.variant_names().join(", "))]);
+            }
+        };
+        if payload_types.len() != payload.len() {
+            return Err(vec![format!("The variant {}::{} carries {} value(s), but {} are given.",
+                                    enumeration, variant, payload_types.len(), payload.len())]);
+        }
+        for (expected, actual) in payload_types.iter().zip(&payload) {
+            if *expected != actual.ty {
+                return Err(vec![format!("The payload of {}::{} should have type {}, but it has \
+                                         type {}.", enumeration, variant, expected, actual.ty)]);
+            }
+        }
+        let args = payload.into_iter().map(|value| value.term).collect();
+        Ok(TypedExpr {
+            term: TaggedTerm::Variant(generated(), enumeration.name.clone(), variant.to_string(),
+                                      args),
+            ty: Type::Enum(enumeration.clone()),
+        })
+    }
+
+    // A call to a function the env is expected to bind at `func_ty`.
+    pub fn call(name: &str,
+                func_ty: &Type,
+                args: Vec<TypedExpr>) -> Result<TypedExpr, Vec<String>> {
+        let (params, args_types, ret_ty) = match *func_ty {
+            Type::FunctionTy(ref params, ref args_types, ref ret_ty) =>
+                (params, args_types, ret_ty),
+            _ => {
+                return Err(vec![format!("{} is called as a function, but it has type {}",
+                                        name, func_ty)])
+            }
+        };
+        if args_types.len() != args.len() {
+            return Err(vec![format!("Function {} expects {} argument(s), but {} are provided.",
+                                    name, args_types.len(), args.len())]);
+        }
+        let actual: Vec<Type> = args.iter().map(|arg| arg.ty.clone()).collect();
+        let (args_types, ret_ty) = try!(instantiate(name, params, args_types, ret_ty, &actual));
+        let mut errors = Vec::new();
+        for (expected, actual) in args_types.iter().zip(&args) {
+            if *expected != actual.ty {
+                errors.push(format!("Expect term of type {}, found term of type {}.",
+                                    expected, actual.ty));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let func = TaggedFunctionCall { tag: generated(), name: name.to_string() };
+        let args = args.into_iter().map(|arg| arg.term).collect();
+        Ok(TypedExpr {
+            term: TaggedTerm::Call(generated(), func, args),
+            ty: ret_ty,
+        })
+    }
+
+    pub fn if_(cond: TypedExpr,
+               then: TypedExpr,
+               otherwise: TypedExpr) -> Result<TypedExpr, Vec<String>> {
+        if cond.ty != Type::BoolTy {
+            return Err(vec![format!("The condition of an if must have type Bool, \
+                                     but it has type {}.", cond.ty)]);
+        }
+        if then.ty != otherwise.ty {
+            return Err(vec![format!("The term of the then part has type {}, \
+                                     but that of the else part has type {}.",
+                                    then.ty, otherwise.ty)]);
+        }
+        Ok(TypedExpr {
+            term: TaggedTerm::If(generated(),
+                                 Box::new(cond.term),
+                                 Box::new(then.term),
+                                 Some(Box::new(otherwise.term))),
+            ty: then.ty,
+        })
+    }
+}
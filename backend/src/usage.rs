@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+
+use ast::Operator;
+use type_check::*;
+use visit::*;
+
+// Every construct a program can use, one per term or statement variant.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum Feature {
+    Literal,
+    Var,
+    Infix,
+    Call,
+    Scope,
+    If,
+    While,
+    TermSemicolon,
+    Let,
+    LetMut,
+    Mutate,
+    Extern,
+    Declare,
+    BoolLiteral,
+    SuffixedLiteral,
+    Cast,
+    FloatLiteral,
+    CharLit,
+    StrLit,
+    Tuple,
+    Project,
+    ArrayLit,
+    Index,
+    StructDecl,
+    EnumDecl,
+    StructLit,
+    Field,
+    Variant,
+    Match,
+    TypeAlias,
+    Borrow,
+    Deref,
+    BorrowMut,
+    MutateThrough,
+    Null,
+    PtrEq,
+    Lambda,
+    Apply,
+    Ascribe,
+    MethodCall,
+    Neg,
+    Not,
+}
+
+impl Feature {
+    pub fn all() -> Vec<Feature> {
+        use self::Feature::*;
+        vec![Literal, Var, Infix, Call, Scope, If, While,
+             TermSemicolon, Let, LetMut, Mutate, Extern, Declare, BoolLiteral, SuffixedLiteral,
+             Cast, FloatLiteral, CharLit, StrLit, Tuple, Project,
+             ArrayLit, Index, StructDecl, EnumDecl, StructLit, Field, Variant, Match, TypeAlias,
+             Borrow, Deref, BorrowMut, MutateThrough, Null, PtrEq, Lambda, Apply, Ascribe,
+             MethodCall, Neg, Not]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FeatureUsage {
+    pub features: HashMap<Feature, usize>,
+    pub operators: HashMap<Operator, usize>,
+    pub max_block_depth: usize,
+    pub distinct_variables: usize,
+}
+
+impl FeatureUsage {
+    pub fn count(&self, feature: Feature) -> usize {
+        *self.features.get(&feature).unwrap_or(&0)
+    }
+
+    // Checks that the program only uses the allowed features.
+    // The features used outside of the allowed subset are returned otherwise.
+    pub fn requires(&self, allowed: &[Feature]) -> Result<(), Vec<Feature>> {
+        let forbidden: Vec<Feature> =
+            Feature::all().into_iter()
+                          .filter(|feature| self.count(*feature) > 0 && !allowed.contains(feature))
+                          .collect();
+        if forbidden.is_empty() {
+            Ok(())
+        } else {
+            Err(forbidden)
+        }
+    }
+}
+
+struct UsageCounter {
+    usage: FeatureUsage,
+    depth: usize,
+    variables: HashSet<String>,
+}
+
+impl UsageCounter {
+    fn record(&mut self, feature: Feature) {
+        *self.usage.features.entry(feature).or_insert(0) += 1;
+    }
+}
+
+impl<Tag> Visitor<Tag> for UsageCounter {
+    fn visit_block(&mut self, block: &TaggedBlock<Tag>) {
+        self.depth += 1;
+        if self.depth > self.usage.max_block_depth {
+            self.usage.max_block_depth = self.depth;
+        }
+        walk_block(self, block);
+        self.depth -= 1;
+    }
+
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Tag>) {
+        use type_check::TaggedStatement::*;
+        match *stmt {
+            TermSemicolon(..) => self.record(Feature::TermSemicolon),
+            Let(_, ref name, _, _) => {
+                self.record(Feature::Let);
+                self.variables.insert(name.clone());
+            }
+            LetMut(_, ref name, _, _) => {
+                self.record(Feature::LetMut);
+                self.variables.insert(name.clone());
+            }
+            Mutate(_, ref name, _) => {
+                self.record(Feature::Mutate);
+                self.variables.insert(name.clone());
+            }
+            Extern(..) => self.record(Feature::Extern),
+            Declare(_, ref name, _) => {
+                self.record(Feature::Declare);
+                self.variables.insert(name.clone());
+            }
+            StructDecl(..) => self.record(Feature::StructDecl),
+            EnumDecl(..) => self.record(Feature::EnumDecl),
+            TypeAlias(..) => self.record(Feature::TypeAlias),
+            MutateThrough(..) => self.record(Feature::MutateThrough),
+        }
+        walk_statement(self, stmt);
+    }
+
+    fn visit_term(&mut self, term: &TaggedTerm<Tag>) {
+        use type_check::TaggedTerm::*;
+        match *term {
+            Literal(..) => self.record(Feature::Literal),
+            BoolLiteral(..) => self.record(Feature::BoolLiteral),
+            SuffixedLiteral(..) => self.record(Feature::SuffixedLiteral),
+            Cast(..) => self.record(Feature::Cast),
+            FloatLiteral(..) => self.record(Feature::FloatLiteral),
+            CharLit(..) => self.record(Feature::CharLit),
+            StrLit(..) => self.record(Feature::StrLit),
+            Tuple(..) => self.record(Feature::Tuple),
+            Project(..) => self.record(Feature::Project),
+            ArrayLit(..) => self.record(Feature::ArrayLit),
+            Index(..) => self.record(Feature::Index),
+            StructLit(..) => self.record(Feature::StructLit),
+            Field(..) => self.record(Feature::Field),
+            Variant(..) => self.record(Feature::Variant),
+            Match(..) => self.record(Feature::Match),
+            Borrow(..) => self.record(Feature::Borrow),
+            Deref(..) => self.record(Feature::Deref),
+            BorrowMut(..) => self.record(Feature::BorrowMut),
+            Null(..) => self.record(Feature::Null),
+            PtrEq(..) => self.record(Feature::PtrEq),
+            Lambda(..) => self.record(Feature::Lambda),
+            Apply(..) => self.record(Feature::Apply),
+            Ascribe(..) => self.record(Feature::Ascribe),
+            MethodCall(..) => self.record(Feature::MethodCall),
+            Neg(..) => self.record(Feature::Neg),
+            Not(..) => self.record(Feature::Not),
+            Var(_, ref name) => {
+                self.record(Feature::Var);
+                self.variables.insert(name.clone());
+            }
+            Infix(_, _, op, _) => {
+                self.record(Feature::Infix);
+                *self.usage.operators.entry(op).or_insert(0) += 1;
+            }
+            Call(..) => self.record(Feature::Call),
+            Scope(..) => self.record(Feature::Scope),
+            If(..) => self.record(Feature::If),
+            While(..) => self.record(Feature::While),
+            // The statement itself is counted when it's visited.
+            Stmt(_) => {}
+        }
+        walk_term(self, term);
+    }
+}
+
+// Counts the constructs a program uses, e.g. to restrict scripts to a subset of the language.
+pub fn feature_usage<Tag>(program: &TaggedProgram<Tag>) -> FeatureUsage {
+    let mut counter = UsageCounter {
+        usage: FeatureUsage {
+            features: HashMap::new(),
+            operators: HashMap::new(),
+            max_block_depth: 0,
+            distinct_variables: 0,
+        },
+        depth: 0,
+        variables: HashSet::new(),
+    };
+    counter.visit_program(program);
+    counter.usage.distinct_variables = counter.variables.len();
+    counter.usage
+}
@@ -0,0 +1,151 @@
+// Which names a program looks up, and where each one comes from, e.g. for a host to allow only
+// some of the functions it registers.
+// The checker reports every lookup that succeeds to its env, so a use is counted against the
+// binding it's checked against, and names that aren't bound aren't counted at all.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ast::{Operator, Position};
+use strictness::Strictness;
+use type_check::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Origin {
+    // In the env the program is checked in, i.e. registered by the host.
+    Host,
+    // Declared by an extern statement in the program.
+    Extern,
+    // Bound by the program otherwise: by a let, a lambda or a match arm.
+    Local,
+}
+
+impl Origin {
+    fn of(kind: BindingKind) -> Origin {
+        match kind {
+            BindingKind::Host => Origin::Host,
+            BindingKind::Extern => Origin::Extern,
+            _ => Origin::Local,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UsedBinding {
+    pub name: String,
+    pub origin: Origin,
+    // Reads, calls and assignments all count as uses.
+    pub count: usize,
+    pub first_use: Position,
+}
+
+// In the order of first use.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct UsedBindings {
+    pub bindings: Vec<UsedBinding>,
+}
+
+impl UsedBindings {
+    pub fn get(&self, name: &str, origin: Origin) -> Option<&UsedBinding> {
+        self.bindings.iter().find(|used| used.name == name && used.origin == origin)
+    }
+
+    pub fn names_from(&self, origin: Origin) -> Vec<&str> {
+        self.bindings.iter()
+                     .filter(|used| used.origin == origin)
+                     .map(|used| &used.name[..])
+                     .collect()
+    }
+
+    fn record(&mut self, name: &str, origin: Origin, pos: &Position) {
+        if let Some(used) = self.bindings
+                                .iter_mut()
+                                .find(|used| used.name == name && used.origin == origin) {
+            used.count += 1;
+            return;
+        }
+        self.bindings.push(UsedBinding {
+            name: name.to_string(),
+            origin: origin,
+            count: 1,
+            first_use: pos.clone(),
+        });
+    }
+}
+
+// Checks with the bindings of another env, recording the uses of each.
+#[derive(Clone)]
+pub struct UsageEnv<E: TypeEnv> {
+    inner: E,
+    // Shared by all the copies made while checking.
+    used: Rc<RefCell<UsedBindings>>,
+}
+
+impl<E: TypeEnv> UsageEnv<E> {
+    pub fn new(inner: E) -> UsageEnv<E> {
+        UsageEnv { inner: inner, used: Rc::new(RefCell::new(UsedBindings::default())) }
+    }
+
+    pub fn used_bindings(&self) -> UsedBindings {
+        self.used.borrow().clone()
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: TypeEnv> TypeEnv for UsageEnv<E> {
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.inner.binding(name)
+    }
+
+    fn bind(&mut self, name: String, binding: Binding) {
+        self.inner.bind(name, binding);
+    }
+
+    fn declared_type(&self, kind: TypeKind, name: &str) -> Option<&Type> {
+        self.inner.declared_type(kind, name)
+    }
+
+    fn declare_type(&mut self, kind: TypeKind, name: String, ty: Type) {
+        self.inner.declare_type(kind, name, ty);
+    }
+
+    fn operator(&self, op: Operator, left: &Type, right: &Type) -> Option<&Type> {
+        self.inner.operator(op, left, right)
+    }
+
+    fn add_operator(&mut self, op: Operator, left: Type, right: Type, result: Type) {
+        self.inner.add_operator(op, left, right, result);
+    }
+
+    fn used(&self, name: &str, binding: &Binding, position: &Position) {
+        self.used.borrow_mut().record(name, Origin::of(binding.kind), position);
+        self.inner.used(name, binding, position);
+    }
+
+    fn checked_item(&self, done: usize, total: usize) {
+        self.inner.checked_item(done, total);
+    }
+
+    fn strictness(&self) -> Strictness {
+        self.inner.strictness()
+    }
+
+    fn warn(&self, warning: String) {
+        self.inner.warn(warning);
+    }
+
+    fn unify(&self, left: &Type, right: &Type) -> bool {
+        self.inner.unify(left, right)
+    }
+
+    fn zonk(&self, ty: &Type) -> Type {
+        self.inner.zonk(ty)
+    }
+
+    fn fresh_meta(&self, origin: String) -> Option<Type> {
+        self.inner.fresh_meta(origin)
+    }
+}
@@ -0,0 +1,532 @@
+// Checks that the tags of a typed tree agree with the typing rules.
+// Trees built or rewritten by hand can carry tags the checker would never produce, and codegen
+// trusts the tags, so it runs this first, in release builds too.
+// Variables aren't looked up in an outer env; their tags only have to agree with the binding
+// inside the tree, or with the first use when the binding isn't part of the tree.
+
+use std::fmt::{Display, Formatter};
+use std::fmt::Result as FmtResult;
+
+use ast::Pattern;
+use codegen::Map;
+use print::is_builtin_print;
+use type_check::*;
+use type_check::Type::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ValidationError {
+    // Where the node is, e.g. `main.stmts[1].rhs.left`.
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+// `Forbidden` can't be displayed, but corrupted trees may carry it anywhere.
+pub fn show_tag(ty: &Type) -> String {
+    match *ty {
+        Forbidden => "Forbidden".to_string(),
+        FunctionTy(ref params, ref args_types, ref ret_ty) => {
+            let args: Vec<String> = args_types.iter().map(show_tag).collect();
+            let params = if params.is_empty() {
+                String::new()
+            } else {
+                format!("<{}>", params.join(", "))
+            };
+            format!("{}({}) -> {}", params, args.join(", "), show_tag(ret_ty))
+        }
+        _ => format!("{}", ty),
+    }
+}
+
+struct Validator {
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    // Never fits wherever another type is expected, like it does when checking.
+    fn expect(&mut self, path: &str, expected: &Type, found: &Type) {
+        if expected != found && *expected != Never && *found != Never {
+            self.errors.push(ValidationError {
+                path: path.to_string(),
+                message: format!("expected tag {}, found {}", show_tag(expected), show_tag(found)),
+            });
+        }
+    }
+
+    // Legacy mode lets I32 conditions through.
+    fn expect_cond(&mut self, path: &str, found: &Type) {
+        if *found != I32Ty {
+            self.expect(path, &BoolTy, found);
+        }
+    }
+
+    fn error(&mut self, path: &str, message: String) {
+        self.errors.push(ValidationError { path: path.to_string(), message: message });
+    }
+
+    fn bind_or_expect(&mut self, path: &str, env: &mut Map<Type>, name: &str, found: &Type) {
+        match env.get(name).cloned() {
+            Some(ty) => self.expect(path, &ty, found),
+            None => {
+                env.insert(name.to_string(), found.clone());
+            }
+        }
+    }
+
+    fn block(&mut self, path: &str, block: &TaggedBlock<Type>, env: &mut Map<Type>) {
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            self.statement(&format!("{}.stmts[{}]", path, i), stmt, env);
+        }
+        let ty = match *block.end {
+            Some(ref term) => {
+                self.term(&format!("{}.end", path), term, env);
+                *term.get_tag()
+            }
+            None => UnitTy,
+        };
+        self.expect(path, &ty, &block.tag);
+    }
+
+    fn statement(&mut self, path: &str, stmt: &TaggedStatement<Type>, env: &mut Map<Type>) {
+        use type_check::TaggedStatement::*;
+        match *stmt {
+            TermSemicolon(ref tag, ref term) => {
+                self.term(&format!("{}.term", path), term, &mut env.clone());
+                self.expect(path, &UnitTy, tag);
+            }
+            Let(ref tag, ref name, ref ty, ref rhs) |
+            LetMut(ref tag, ref name, ref ty, ref rhs) => {
+                self.term(&format!("{}.rhs", path), rhs, &mut env.clone());
+                if *ty != Hole {
+                    self.expect(&format!("{}.rhs", path), ty, &rhs.get_tag());
+                }
+                env.insert(name.clone(), let_type(ty, rhs));
+                self.expect(path, &Forbidden, tag);
+            }
+            Mutate(ref tag, _, ref rhs) => {
+                self.term(&format!("{}.rhs", path), rhs, &mut env.clone());
+                self.expect(path, &Forbidden, tag);
+            }
+            Extern(ref tag, ref name, ref ty) | Declare(ref tag, ref name, ref ty) => {
+                env.insert(name.clone(), ty.clone());
+                self.expect(path, &Forbidden, tag);
+            }
+            // Struct literals and variants are checked against the types in their own tags.
+            StructDecl(ref tag, _, _) | EnumDecl(ref tag, _, _) | TypeAlias(ref tag, _, _) =>
+                self.expect(path, &Forbidden, tag),
+            MutateThrough(ref tag, ref target, ref rhs) => {
+                let target_path = format!("{}.target", path);
+                self.term(&target_path, target, &mut env.clone());
+                self.term(&format!("{}.rhs", path), rhs, &mut env.clone());
+                match *target.get_tag() {
+                    RefMut(ref referent) =>
+                        self.expect(&format!("{}.rhs", path), referent, &rhs.get_tag()),
+                    ref ty => {
+                        let message = format!("expected a &mut reference tag, found {}",
+                                              show_tag(ty));
+                        self.error(&target_path, message)
+                    }
+                }
+                self.expect(path, &Forbidden, tag);
+            }
+        }
+    }
+
+    fn term(&mut self, path: &str, term: &TaggedTerm<Type>, env: &mut Map<Type>) {
+        use type_check::TaggedTerm::*;
+        match *term {
+            // A literal can have any integer type it fits in.
+            Literal(ref tag, i) => {
+                if !is_integer(tag) || !literal_fits(i as i64, tag) {
+                    let message = format!("expected an integer tag {} fits in, found {}",
+                                          i, show_tag(tag));
+                    self.error(path, message);
+                }
+            }
+            BoolLiteral(ref tag, _) => self.expect(path, &BoolTy, tag),
+            SuffixedLiteral(ref tag, _, ref ty) => self.expect(path, ty, tag),
+            FloatLiteral(ref tag, _) => self.expect(path, &F64Ty, tag),
+            CharLit(ref tag, _) => self.expect(path, &CharTy, tag),
+            StrLit(ref tag, _) => self.expect(path, &StrTy, tag),
+            Null(ref tag) => self.expect(path, &Ptr, tag),
+            Var(ref tag, ref name) => self.bind_or_expect(path, env, name, tag),
+            // Codegen only knows the built-in operators.
+            Infix(ref tag, ref left, op, ref right) => {
+                let left_path = format!("{}.left", path);
+                let right_path = format!("{}.right", path);
+                self.term(&left_path, left, &mut env.clone());
+                self.term(&right_path, right, &mut env.clone());
+                let operand = if *left.get_tag() == Never {
+                    right.get_tag()
+                } else {
+                    left.get_tag()
+                };
+                self.expect(&left_path, &operand, &left.get_tag());
+                self.expect(&right_path, &operand, &right.get_tag());
+                match builtin_operator_type(op, &operand, &operand) {
+                    Some(result) => self.expect(path, &result, tag),
+                    None if *operand == Never => {}
+                    None => {
+                        let message = format!("expected operands of a built-in {}, found {}",
+                                              op, show_tag(&operand));
+                        self.error(path, message);
+                    }
+                }
+            }
+            PtrEq(ref tag, ref left, ref right) => {
+                let left_path = format!("{}.left", path);
+                let right_path = format!("{}.right", path);
+                self.term(&left_path, left, &mut env.clone());
+                self.term(&right_path, right, &mut env.clone());
+                self.expect(path, &BoolTy, tag);
+                self.expect(&left_path, &Ptr, &left.get_tag());
+                self.expect(&right_path, &Ptr, &right.get_tag());
+            }
+            Lambda(ref tag, ref params, ref body) => {
+                let mut body_env = env.clone();
+                for &(ref name, ref ty) in params {
+                    body_env.insert(name.clone(), ty.clone());
+                }
+                self.block(&format!("{}.body", path), body, &mut body_env);
+                let args_types = params.iter().map(|&(_, ref ty)| ty.clone()).collect();
+                let ty = FunctionTy(Vec::new(), args_types, Box::new(body.tag.clone()));
+                self.expect(path, &ty, tag);
+            }
+            Apply(ref tag, ref callee, ref args) => {
+                let callee_path = format!("{}.callee", path);
+                self.term(&callee_path, callee, &mut env.clone());
+                for (i, arg) in args.iter().enumerate() {
+                    self.term(&format!("{}.args[{}]", path, i), arg, &mut env.clone());
+                }
+                match *callee.get_tag() {
+                    FunctionTy(_, ref args_types, ref ret_ty) => {
+                        if args_types.len() != args.len() {
+                            self.error(path, format!("the callee takes {} argument(s), but {} \
+                                                      are given",
+                                                     args_types.len(), args.len()));
+                        }
+                        for (i, (expected, arg)) in args_types.iter().zip(args).enumerate() {
+                            self.expect(&format!("{}.args[{}]", path, i),
+                                        expected, &arg.get_tag());
+                        }
+                        self.expect(path, ret_ty, tag);
+                    }
+                    ref ty => {
+                        let message = format!("expected a function type, found {}", show_tag(ty));
+                        self.error(&callee_path, message)
+                    }
+                }
+            }
+            Call(ref tag, ref func, ref args) => {
+                let func_path = format!("{}.func", path);
+                // Every call to the prelude's print has a type of its own.
+                // A call to a generic extern is tagged with the instantiation it calls.
+                match env.get(&func.name).cloned() {
+                    Some(ref generic @ FunctionTy(..)) if is_generic(generic) => {
+                        if !is_instance(generic, &func.tag) {
+                            let message = format!("expected an instance of {}, found {}",
+                                                  show_tag(generic), show_tag(&func.tag));
+                            self.error(&func_path, message)
+                        }
+                    }
+                    _ if is_builtin_print(&func.name, env.contains_key(&func.name)) => {}
+                    _ => self.bind_or_expect(&func_path, env, &func.name, &func.tag),
+                }
+                for (i, arg) in args.iter().enumerate() {
+                    self.term(&format!("{}.args[{}]", path, i), arg, &mut env.clone());
+                }
+                match func.tag {
+                    FunctionTy(_, ref args_types, ref ret_ty) => {
+                        if args_types.len() != args.len() {
+                            self.error(path, format!("{} takes {} argument(s), but {} are given",
+                                                     func.name, args_types.len(), args.len()));
+                        }
+                        for (i, (expected, arg)) in args_types.iter().zip(args).enumerate() {
+                            self.expect(&format!("{}.args[{}]", path, i),
+                                        expected, &arg.get_tag());
+                        }
+                        self.expect(path, ret_ty, tag);
+                    }
+                    ref ty => {
+                        let message = format!("expected a function type, found {}", show_tag(ty));
+                        self.error(&func_path, message)
+                    }
+                }
+            }
+            Scope(ref tag, ref block) => {
+                self.block(&format!("{}.block", path), block, &mut env.clone());
+                self.expect(path, &block.tag, tag);
+            }
+            If(ref tag, ref cond, ref if_true, ref if_false) => {
+                let cond_path = format!("{}.cond", path);
+                self.term(&cond_path, cond, &mut env.clone());
+                self.expect_cond(&cond_path, &cond.get_tag());
+                self.term(&format!("{}.then", path), if_true, &mut env.clone());
+                match *if_false {
+                    Some(ref if_false) => {
+                        let else_path = format!("{}.else", path);
+                        self.term(&else_path, if_false, &mut env.clone());
+                        self.expect(&else_path, &if_true.get_tag(), &if_false.get_tag());
+                    }
+                    None => self.expect(path, &if_true.get_tag(), &UnitTy),
+                }
+                self.expect(path, &if_true.get_tag(), tag);
+            }
+            While(ref tag, ref cond, ref block) => {
+                let cond_path = format!("{}.cond", path);
+                self.term(&cond_path, cond, &mut env.clone());
+                self.expect_cond(&cond_path, &cond.get_tag());
+                self.block(&format!("{}.block", path), block, env);
+                self.expect(path, &block.tag, tag);
+            }
+            Stmt(ref stmt) => self.statement(&format!("{}.stmt", path), stmt, env),
+            Cast(ref tag, ref term, ref ty) => {
+                let term_path = format!("{}.term", path);
+                self.term(&term_path, term, &mut env.clone());
+                if !can_cast(&term.get_tag(), ty) {
+                    let message = format!("expected a cast between numeric tags, found {} to {}",
+                                          show_tag(&term.get_tag()), show_tag(ty));
+                    self.error(path, message);
+                }
+                self.expect(path, ty, tag);
+            }
+            Ascribe(ref tag, ref term, ref ty) => {
+                self.term(&format!("{}.term", path), term, &mut env.clone());
+                self.expect(&format!("{}.term", path), ty, &term.get_tag());
+                self.expect(path, ty, tag);
+            }
+            // Checking turns them into calls.
+            MethodCall(..) => self.error(path, "expected a call, found a method call".to_string()),
+            Tuple(ref tag, ref elems) => {
+                for (i, elem) in elems.iter().enumerate() {
+                    self.term(&format!("{}.elems[{}]", path, i), elem, &mut env.clone());
+                }
+                let elems_tags = elems.iter().map(|elem| *elem.get_tag()).collect();
+                self.expect(path, &TupleTy(elems_tags), tag);
+            }
+            Project(ref tag, ref term, index) => {
+                let term_path = format!("{}.term", path);
+                self.term(&term_path, term, &mut env.clone());
+                match *term.get_tag() {
+                    TupleTy(ref elems) if (index as usize) < elems.len() =>
+                        self.expect(path, &elems[index as usize], tag),
+                    ref ty => {
+                        let message = format!("expected a tuple tag with an element {}, found {}",
+                                              index, show_tag(ty));
+                        self.error(&term_path, message)
+                    }
+                }
+            }
+            ArrayLit(ref tag, ref elems) => {
+                for (i, elem) in elems.iter().enumerate() {
+                    self.term(&format!("{}.elems[{}]", path, i), elem, &mut env.clone());
+                }
+                let elem_tag = match (elems.first(), tag) {
+                    (Some(first), _) => *first.get_tag(),
+                    // The element type of an empty array was inferred.
+                    (None, &ArrayTy(ref elem_tag, 0)) => *elem_tag.clone(),
+                    (None, _) => return self.error(path, format!("expected an empty array")),
+                };
+                for (i, elem) in elems.iter().enumerate() {
+                    self.expect(&format!("{}.elems[{}]", path, i), &elem_tag, &elem.get_tag());
+                }
+                self.expect(path, &ArrayTy(Box::new(elem_tag), elems.len() as u32), tag);
+            }
+            StructLit(ref tag, ref name, ref inits) => {
+                for (i, &(_, ref term)) in inits.iter().enumerate() {
+                    self.term(&format!("{}.inits[{}]", path, i), term, &mut env.clone());
+                }
+                let structure = match *tag {
+                    Struct(ref structure) if structure.name == *name => structure,
+                    ref ty => {
+                        let message = format!("expected a tag of struct {}, found {}",
+                                              name, show_tag(ty));
+                        return self.error(path, message);
+                    }
+                };
+                for (i, &(ref field, ref term)) in inits.iter().enumerate() {
+                    let init_path = format!("{}.inits[{}]", path, i);
+                    match structure.field(field) {
+                        Some((_, ty)) => self.expect(&init_path, ty, &term.get_tag()),
+                        None => self.error(&init_path, format!("expected a field of {}, found {}",
+                                                               name, field)),
+                    }
+                }
+                for field in structure.field_names() {
+                    let count = inits.iter().filter(|&&(ref init, _)| init == field).count();
+                    if count != 1 {
+                        self.error(path, format!("expected the field {} to be initialized once, \
+                                                  found {} times", field, count));
+                    }
+                }
+            }
+            Match(ref tag, ref scrutinee, ref arms) => {
+                let scrutinee_path = format!("{}.scrutinee", path);
+                self.term(&scrutinee_path, scrutinee, &mut env.clone());
+                let enumeration = match *scrutinee.get_tag() {
+                    Enum(enumeration) => enumeration,
+                    ref ty => {
+                        let message = format!("expected an enum tag, found {}", show_tag(ty));
+                        return self.error(&scrutinee_path, message);
+                    }
+                };
+                for (i, &(ref pattern, ref body)) in arms.iter().enumerate() {
+                    let arm_path = format!("{}.arms[{}]", path, i);
+                    let mut arm_env = env.clone();
+                    if let Pattern::Constructor(ref name, ref variant, ref binds) = *pattern {
+                        match enumeration.variant(variant) {
+                            Some((_, payload)) if *name == enumeration.name &&
+                                                  payload.len() == binds.len() => {
+                                for (bind, ty) in binds.iter().zip(payload) {
+                                    arm_env.insert(bind.clone(), ty.clone());
+                                }
+                            }
+                            _ => {
+                                let message = format!("expected a pattern of {}, found {}",
+                                                      enumeration.name, pattern);
+                                self.error(&arm_path, message);
+                            }
+                        }
+                    }
+                    self.term(&arm_path, body, &mut arm_env);
+                    self.expect(&arm_path, tag, &body.get_tag());
+                }
+                // Codegen relies on the arms covering every variant.
+                for variant in enumeration.variant_names() {
+                    let covered = arms.iter().any(|&(ref pattern, _)| match *pattern {
+                        Pattern::Constructor(_, ref covered, _) => covered == variant,
+                        Pattern::Wildcard => true,
+                    });
+                    if !covered {
+                        self.error(path, format!("expected an arm for {}", variant));
+                    }
+                }
+            }
+            Variant(ref tag, ref name, ref variant, ref args) => {
+                for (i, arg) in args.iter().enumerate() {
+                    self.term(&format!("{}.args[{}]", path, i), arg, &mut env.clone());
+                }
+                let enumeration = match *tag {
+                    Enum(ref enumeration) if enumeration.name == *name => enumeration,
+                    ref ty => {
+                        let message = format!("expected a tag of enum {}, found {}",
+                                              name, show_tag(ty));
+                        return self.error(path, message);
+                    }
+                };
+                let payload = match enumeration.variant(variant) {
+                    Some((_, payload)) => payload,
+                    None => {
+                        return self.error(path, format!("expected a variant of {}, found {}",
+                                                        name, variant));
+                    }
+                };
+                if payload.len() != args.len() {
+                    return self.error(path, format!("expected {} payload value(s), found {}",
+                                                    payload.len(), args.len()));
+                }
+                for (i, (ty, arg)) in payload.iter().zip(args).enumerate() {
+                    self.expect(&format!("{}.args[{}]", path, i), ty, &arg.get_tag());
+                }
+            }
+            Field(ref tag, ref term, ref field) => {
+                let term_path = format!("{}.term", path);
+                self.term(&term_path, term, &mut env.clone());
+                match *term.get_tag() {
+                    Struct(ref structure) if structure.field(field).is_some() =>
+                        self.expect(path, structure.field(field).unwrap().1, tag),
+                    ref ty => {
+                        let message = format!("expected a struct tag with a field {}, found {}",
+                                              field, show_tag(ty));
+                        self.error(&term_path, message)
+                    }
+                }
+            }
+            // Codegen can only borrow variables.
+            Borrow(ref tag, ref term) => {
+                let term_path = format!("{}.term", path);
+                self.term(&term_path, term, &mut env.clone());
+                if let Var(..) = **term {
+                    self.expect(path, &Ref(term.get_tag()), tag);
+                } else {
+                    self.error(&term_path, format!("expected a variable"));
+                }
+            }
+            BorrowMut(ref tag, ref term) => {
+                let term_path = format!("{}.term", path);
+                self.term(&term_path, term, &mut env.clone());
+                if let Var(..) = **term {
+                    self.expect(path, &RefMut(term.get_tag()), tag);
+                } else {
+                    self.error(&term_path, format!("expected a variable"));
+                }
+            }
+            // A negated literal fits its type once it's negated.
+            Neg(ref tag, ref term) => {
+                let term_path = format!("{}.term", path);
+                match **term {
+                    Literal(ref literal_tag, i) => {
+                        if !is_integer(literal_tag) || !literal_fits(-(i as i64), literal_tag) {
+                            let message = format!("expected an integer tag -{} fits in, found {}",
+                                                  i, show_tag(literal_tag));
+                            self.error(&term_path, message);
+                        }
+                    }
+                    _ => self.term(&term_path, term, &mut env.clone()),
+                }
+                if !can_negate(tag) && *tag != Never {
+                    let message = format!("expected a signed tag, found {}", show_tag(tag));
+                    self.error(path, message);
+                }
+                self.expect(&term_path, tag, &term.get_tag());
+            }
+            Not(ref tag, ref term) => {
+                let term_path = format!("{}.term", path);
+                self.term(&term_path, term, &mut env.clone());
+                self.expect(&term_path, &BoolTy, &term.get_tag());
+                self.expect(path, &BoolTy, tag);
+            }
+            Deref(ref tag, ref term) => {
+                let term_path = format!("{}.term", path);
+                self.term(&term_path, term, &mut env.clone());
+                match *term.get_tag() {
+                    Ref(ref referent) | RefMut(ref referent) => self.expect(path, referent, tag),
+                    ref ty => {
+                        let message = format!("expected a reference tag, found {}", show_tag(ty));
+                        self.error(&term_path, message)
+                    }
+                }
+            }
+            Index(ref tag, ref array, ref index) => {
+                let array_path = format!("{}.array", path);
+                let index_path = format!("{}.index", path);
+                self.term(&array_path, array, &mut env.clone());
+                self.term(&index_path, index, &mut env.clone());
+                self.expect(&index_path, &I32Ty, &index.get_tag());
+                match *array.get_tag() {
+                    ArrayTy(ref elem, _) => self.expect(path, elem, tag),
+                    ref ty => {
+                        let message = format!("expected an array tag, found {}", show_tag(ty));
+                        self.error(&array_path, message)
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn validate(program: &TaggedProgram<Type>) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator { errors: Vec::new() };
+    validator.block("main", &program.main, &mut Map::new());
+    validator.expect("program", &Forbidden, &program.tag);
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
@@ -0,0 +1,136 @@
+use type_check::*;
+
+// A read-only traversal over tagged trees.
+// Override the `visit_*` methods you care about and call the matching `walk_*` function
+// to keep descending.
+pub trait Visitor<Tag> {
+    fn visit_program(&mut self, program: &TaggedProgram<Tag>) {
+        walk_program(self, program)
+    }
+
+    fn visit_block(&mut self, block: &TaggedBlock<Tag>) {
+        walk_block(self, block)
+    }
+
+    fn visit_statement(&mut self, stmt: &TaggedStatement<Tag>) {
+        walk_statement(self, stmt)
+    }
+
+    fn visit_term(&mut self, term: &TaggedTerm<Tag>) {
+        walk_term(self, term)
+    }
+}
+
+pub fn walk_program<Tag, V: Visitor<Tag> + ?Sized>(visitor: &mut V, program: &TaggedProgram<Tag>) {
+    visitor.visit_block(&program.main);
+}
+
+pub fn walk_block<Tag, V: Visitor<Tag> + ?Sized>(visitor: &mut V, block: &TaggedBlock<Tag>) {
+    for stmt in &block.stmts {
+        visitor.visit_statement(stmt);
+    }
+    if let Some(ref term) = *block.end {
+        visitor.visit_term(term);
+    }
+}
+
+pub fn walk_statement<Tag, V: Visitor<Tag> + ?Sized>(visitor: &mut V,
+                                                     stmt: &TaggedStatement<Tag>) {
+    use type_check::TaggedStatement::*;
+    match *stmt {
+        TermSemicolon(_, ref term) => visitor.visit_term(term),
+        Let(_, _, _, ref rhs) => visitor.visit_term(rhs),
+        LetMut(_, _, _, ref rhs) => visitor.visit_term(rhs),
+        Mutate(_, _, ref rhs) => visitor.visit_term(rhs),
+        Extern(_, _, _) => {}
+        Declare(_, _, _) => {}
+        StructDecl(_, _, _) | EnumDecl(_, _, _) | TypeAlias(_, _, _) => {}
+        MutateThrough(_, ref target, ref rhs) => {
+            visitor.visit_term(target);
+            visitor.visit_term(rhs);
+        }
+    }
+}
+
+pub fn walk_term<Tag, V: Visitor<Tag> + ?Sized>(visitor: &mut V, term: &TaggedTerm<Tag>) {
+    use type_check::TaggedTerm::*;
+    match *term {
+        Literal(_, _) => {}
+        BoolLiteral(_, _) => {}
+        SuffixedLiteral(_, _, _) => {}
+        FloatLiteral(_, _) => {}
+        CharLit(_, _) => {}
+        StrLit(_, _) => {}
+        Var(_, _) => {}
+        Infix(_, ref left, _, ref right) => {
+            visitor.visit_term(left);
+            visitor.visit_term(right);
+        }
+        Call(_, _, ref args) => {
+            for arg in args {
+                visitor.visit_term(arg);
+            }
+        }
+        Scope(_, ref block) => visitor.visit_block(block),
+        If(_, ref cond, ref if_true, ref if_false) => {
+            visitor.visit_term(cond);
+            visitor.visit_term(if_true);
+            if let Some(ref if_false) = *if_false {
+                visitor.visit_term(if_false);
+            }
+        }
+        While(_, ref cond, ref block) => {
+            visitor.visit_term(cond);
+            visitor.visit_block(block);
+        }
+        Stmt(ref stmt) => visitor.visit_statement(stmt),
+        Cast(_, ref term, _) | Ascribe(_, ref term, _) | Neg(_, ref term) |
+        Not(_, ref term) => visitor.visit_term(term),
+        Tuple(_, ref elems) => {
+            for elem in elems {
+                visitor.visit_term(elem);
+            }
+        }
+        Project(_, ref term, _) => visitor.visit_term(term),
+        ArrayLit(_, ref elems) => {
+            for elem in elems {
+                visitor.visit_term(elem);
+            }
+        }
+        Index(_, ref array, ref index) => {
+            visitor.visit_term(array);
+            visitor.visit_term(index);
+        }
+        StructLit(_, _, ref inits) => {
+            for &(_, ref term) in inits {
+                visitor.visit_term(term);
+            }
+        }
+        Field(_, ref term, _) => visitor.visit_term(term),
+        Variant(_, _, _, ref args) => {
+            for arg in args {
+                visitor.visit_term(arg);
+            }
+        }
+        Match(_, ref scrutinee, ref arms) => {
+            visitor.visit_term(scrutinee);
+            for &(_, ref body) in arms {
+                visitor.visit_term(body);
+            }
+        }
+        Borrow(_, ref term) | Deref(_, ref term) | BorrowMut(_, ref term) =>
+            visitor.visit_term(term),
+        Null(_) => {}
+        PtrEq(_, ref left, ref right) => {
+            visitor.visit_term(left);
+            visitor.visit_term(right);
+        }
+        Lambda(_, _, ref body) => visitor.visit_block(body),
+        Apply(_, ref callee, ref args) | MethodCall(_, ref callee, _, ref args) => {
+            visitor.visit_term(callee);
+            for arg in args {
+                visitor.visit_term(arg);
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// Changes to the stable surface should be deliberate, so the items `api` exports are listed here,
+// and a change to them fails until the list is updated too.
+
+const API: &'static str = include_str!("../src/api.rs");
+const LIB: &'static str = include_str!("../src/lib.rs");
+
+// The names of the items `source` exports at its top level, except hidden ones.
+fn exported_items(source: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut hidden = false;
+    let mut lines = source.lines();
+    while let Some(line) = lines.next() {
+        if line == "#[doc(hidden)]" {
+            hidden = true;
+            continue;
+        }
+        if line.starts_with("#[") {
+            continue;
+        }
+        let was_hidden = hidden;
+        hidden = false;
+        if was_hidden || !line.starts_with("pub ") {
+            continue;
+        }
+        if line.starts_with("pub use ") {
+            let mut item = line.to_string();
+            while !item.ends_with(';') {
+                item.push_str(lines.next().unwrap().trim());
+            }
+            let names = item.trim_right_matches(';')
+                            .rsplit(|c| c == '{' || c == ':')
+                            .next()
+                            .unwrap()
+                            .trim_right_matches('}');
+            for name in names.split(',') {
+                let name = name.split(" as ").last().unwrap().trim();
+                items.push(format!("use {}", name));
+            }
+        } else {
+            let words: Vec<&str> =
+                line.split(|c: char| !c.is_alphanumeric() && c != '_').collect();
+            let keyword = words.iter().position(|word| {
+                ["fn", "struct", "enum", "trait", "const", "static", "mod"].contains(word)
+            }).unwrap();
+            items.push(format!("{} {}", words[keyword], words[keyword + 1]));
+        }
+    }
+    items.sort();
+    items
+}
+
+#[test]
+fn the_api_exports_these_items() {
+    let expected = vec![
+        "fn check_program",
+        "fn check_str",
+        "fn check_str_all",
+        "fn check_str_with",
+        "fn check_str_with_options",
+        "fn compile_checked",
+        "fn compile_checked_with",
+        "fn compile_str",
+        "fn compile_str_with",
+        "fn compile_str_with_options",
+        "fn into_thread_safe",
+        "fn parse_str",
+        "fn shutdown",
+        "struct CompilationReport",
+        "struct CompileOptions",
+        "use Binding",
+        "use BindingKind",
+        "use CheckOptions",
+        "use CheckResult",
+        "use CheckTrace",
+        "use ConstValue",
+        "use Diagnostic",
+        "use EnvEvent",
+        "use Env",
+        "use ExternSet",
+        "use Feature",
+        "use FeatureUsage",
+        "use LayeredEnv",
+        "use LinkOptions",
+        "use Operator",
+        "use Origin",
+        "use Phase",
+        "use Position",
+        "use Progress",
+        "use ProgressEvent",
+        "use RecordingEnv",
+        "use Severity",
+        "use Strictness",
+        "use TaggedBlock",
+        "use TaggedFunctionCall",
+        "use TaggedProgram",
+        "use TaggedStatement",
+        "use TaggedTerm",
+        "use Type",
+        "use TypeEnv",
+        "use TypeKind",
+        "use UsedBinding",
+        "use UsedBindings",
+        "use ValidationError",
+        "use Value",
+        "use builder",
+        "use dump_env",
+        "use emit_exe",
+        "use emit_exe_with",
+        "use emit_ir",
+        "use feature_usage",
+        "use read_typed_ast",
+        "use runtime_extern_set",
+        "use validate",
+        "use write_typed_ast",
+    ];
+    let mut expected: Vec<String> = expected.into_iter().map(|item| item.to_string()).collect();
+    expected.sort();
+    assert_eq!(exported_items(API), expected);
+}
+
+#[test]
+fn only_the_api_module_is_visible() {
+    assert_eq!(exported_items(LIB), vec!["mod api"]);
+}
@@ -0,0 +1,36 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::infer::InferEnv;
+use ende::type_check::*;
+use ende::type_check::Type::*;
+
+fn empty() -> TaggedTerm<ende::ast::Position> {
+    TaggedTerm::ArrayLit(pos(), vec![])
+}
+
+#[test]
+fn the_element_type_of_an_empty_array_is_not_guessed() {
+    let errors = check(&program(vec![let_("a", empty())], Some(lit(0)))).unwrap_err();
+    assert_eq!(errors,
+               vec!["Cannot infer the type of the elements of the empty array at line 1, \
+                     column 1."]);
+}
+
+#[test]
+fn the_element_type_of_an_empty_array_is_inferred() {
+    // [] : [I32; 0]
+    let ascribed = TaggedTerm::Ascribe(pos(), Box::new(empty()), ArrayTy(Box::new(I32Ty), 0));
+    let mut env = InferEnv::new(Env::new());
+    let typed = block(vec![], Some(ascribed)).type_check(&mut env).unwrap();
+    let typed = env.solve_block(typed).unwrap();
+    assert_eq!(typed.tag, ArrayTy(Box::new(I32Ty), 0));
+    let mut env = InferEnv::new(Env::new());
+    let typed = block(vec![], Some(empty())).type_check(&mut env).unwrap();
+    // Nothing says what the elements are.
+    assert_eq!(env.solve_block(typed).unwrap_err(),
+               vec!["Cannot infer the type of the elements of the empty array at line 1, \
+                     column 1."]);
+}
@@ -0,0 +1,97 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::ast::Position;
+use ende::canonical::*;
+use ende::type_check::*;
+
+// A xorshift generator, so the trees are the same on every run.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+
+    fn position(&mut self) -> Position {
+        let line = self.below(100) as u32;
+        Position { start_pos: (line, 1), end_pos: (line, 1 + self.below(10) as u32) }
+    }
+}
+
+// A random term over few enough choices that many generated trees are the same. The structure
+// comes from `shape` and the positions from `places`, so trees of one shape differ only in tags.
+fn term(shape: &mut Random, places: &mut Random, depth: u32) -> TaggedTerm<Position> {
+    let choices = if depth == 0 { 4 } else { 6 };
+    match shape.below(choices) {
+        0 => TaggedTerm::Literal(places.position(), shape.below(3) as i32),
+        1 => TaggedTerm::Var(places.position(), ["a", "b"][shape.below(2) as usize].to_string()),
+        2 => {
+            // Both zeros and a NaN, whose bits are what's compared.
+            let floats = [0.0f64, -0.0, ::std::f64::NAN, 1.5];
+            TaggedTerm::FloatLiteral(places.position(), floats[shape.below(4) as usize].to_bits())
+        }
+        3 => TaggedTerm::BoolLiteral(places.position(), shape.below(2) == 0),
+        4 => {
+            let op = [Add, Mul][shape.below(2) as usize];
+            let left = term(shape, places, depth - 1);
+            let right = term(shape, places, depth - 1);
+            TaggedTerm::Infix(places.position(), Box::new(left), op, Box::new(right))
+        }
+        _ => {
+            let rhs = term(shape, places, depth - 1);
+            let end = term(shape, places, depth - 1);
+            let let_ = TaggedStatement::Let(places.position(), "a".to_string(), Type::Hole, rhs);
+            TaggedTerm::Scope(places.position(),
+                              TaggedBlock {
+                                  tag: places.position(),
+                                  stmts: vec![let_],
+                                  end: Box::new(Some(end)),
+                              })
+        }
+    }
+}
+
+fn tree(shape: u64, places: u64) -> TaggedProgram<Position> {
+    let mut shape = Random(shape);
+    let end = term(&mut shape, &mut Random(places), 2);
+    program(vec![], Some(end))
+}
+
+#[test]
+fn same_trees_hash_the_same() {
+    let trees: Vec<_> = (1..150).flat_map(|seed| vec![tree(seed, seed), tree(seed, seed * 31)])
+                                .collect();
+    let mut same_pairs = 0;
+    for a in &trees {
+        for b in &trees {
+            if same(a, b) {
+                same_pairs += 1;
+                assert_eq!(canonical_hash(a), canonical_hash(b), "{:?} and {:?}", a, b);
+            }
+        }
+    }
+    // Every tree is the same as itself and its copy at other places, and some shapes repeat.
+    assert!(same_pairs > 2 * trees.len(), "only {} pairs are the same", same_pairs);
+}
+
+#[test]
+fn tags_are_ignored_but_everything_else_is_not() {
+    assert!(same(&tree(7, 1), &tree(7, 2)));
+    assert!(tree(7, 1) != tree(7, 2));
+    let untyped = program(vec![let_("a", lit(1))], Some(var("a")));
+    let typed = check(&untyped).unwrap();
+    assert!(same(&untyped, &typed));
+    assert_eq!(canonical_hash(&untyped), canonical_hash(&typed));
+    assert!(!same(&untyped, &program(vec![let_("a", lit(2))], Some(var("a")))));
+    assert!(!same(&untyped, &program(vec![let_("b", lit(1))], Some(var("b")))));
+    let zero = program(vec![], Some(TaggedTerm::FloatLiteral(pos(), 0.0f64.to_bits())));
+    let negative_zero = program(vec![], Some(TaggedTerm::FloatLiteral(pos(), (-0.0f64).to_bits())));
+    assert!(!same(&zero, &negative_zero));
+}
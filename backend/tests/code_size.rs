@@ -0,0 +1,36 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::code_size::*;
+use ende::codegen::compile_many;
+use ende::type_check::*;
+
+#[test]
+fn sizes_are_reported_per_function() {
+    // extern f: (I32) -> I32;
+    // f(0)
+    let straight = check(&program(vec![extern_("f", i32_function(1))],
+                                  Some(call("f", vec![lit(0)])))).unwrap();
+    // let mut n = true;
+    // while n { n = false; };
+    // 0
+    let body = block(vec![mutate("n", bool_lit(false))], None);
+    let looping = check(&program(vec![let_mut("n", bool_lit(true)),
+                                      semicolon(while_(var("n"), body))],
+                                 Some(lit(0)))).unwrap();
+    let module = compile_many(&[("straight", &straight), ("looping", &looping)]).unwrap();
+    let report = unsafe { code_size_report(module) };
+    let names: Vec<&str> = report.functions.iter().map(|function| &function.name[..]).collect();
+    // The extern is only declared, so it has no code.
+    assert_eq!(names, vec!["straight", "looping"]);
+    // The call and the return.
+    assert_eq!(report.functions[0],
+               FunctionSize { name: "straight".to_string(), basic_blocks: 1, instructions: 2 });
+    // The entry, the loop and what comes after it.
+    assert_eq!(report.functions[1].basic_blocks, 3);
+    assert!(report.functions[1].instructions > report.functions[0].instructions);
+    assert_eq!(report.total_instructions(),
+               report.functions[0].instructions + report.functions[1].instructions);
+}
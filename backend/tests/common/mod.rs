@@ -0,0 +1,79 @@
+// Builders for trees to check, since each test only needs a few small programs.
+
+#![allow(dead_code)]
+
+use ende::ast::*;
+use ende::type_check::*;
+
+pub fn pos() -> Position {
+    Position { start_pos: (1, 1), end_pos: (1, 1) }
+}
+
+pub fn lit(i: i32) -> TaggedTerm<Position> {
+    TaggedTerm::Literal(pos(), i)
+}
+
+pub fn bool_lit(b: bool) -> TaggedTerm<Position> {
+    TaggedTerm::BoolLiteral(pos(), b)
+}
+
+pub fn var(name: &str) -> TaggedTerm<Position> {
+    TaggedTerm::Var(pos(), name.to_string())
+}
+
+pub fn infix(left: TaggedTerm<Position>, op: Operator, right: TaggedTerm<Position>)
+             -> TaggedTerm<Position> {
+    TaggedTerm::Infix(pos(), Box::new(left), op, Box::new(right))
+}
+
+pub fn call(name: &str, args: Vec<TaggedTerm<Position>>) -> TaggedTerm<Position> {
+    TaggedTerm::Call(pos(), TaggedFunctionCall { tag: pos(), name: name.to_string() }, args)
+}
+
+pub fn block(stmts: Vec<TaggedStatement<Position>>, end: Option<TaggedTerm<Position>>)
+             -> TaggedBlock<Position> {
+    TaggedBlock { tag: pos(), stmts: stmts, end: Box::new(end) }
+}
+
+pub fn scope(stmts: Vec<TaggedStatement<Position>>, end: Option<TaggedTerm<Position>>)
+             -> TaggedTerm<Position> {
+    TaggedTerm::Scope(pos(), block(stmts, end))
+}
+
+pub fn while_(cond: TaggedTerm<Position>, body: TaggedBlock<Position>) -> TaggedTerm<Position> {
+    TaggedTerm::While(pos(), Box::new(cond), body)
+}
+
+pub fn let_(name: &str, rhs: TaggedTerm<Position>) -> TaggedStatement<Position> {
+    TaggedStatement::Let(pos(), name.to_string(), Type::Hole, rhs)
+}
+
+pub fn let_mut(name: &str, rhs: TaggedTerm<Position>) -> TaggedStatement<Position> {
+    TaggedStatement::LetMut(pos(), name.to_string(), Type::Hole, rhs)
+}
+
+pub fn mutate(name: &str, rhs: TaggedTerm<Position>) -> TaggedStatement<Position> {
+    TaggedStatement::Mutate(pos(), name.to_string(), rhs)
+}
+
+pub fn semicolon(term: TaggedTerm<Position>) -> TaggedStatement<Position> {
+    TaggedStatement::TermSemicolon(pos(), term)
+}
+
+pub fn extern_(name: &str, ty: Type) -> TaggedStatement<Position> {
+    TaggedStatement::Extern(pos(), name.to_string(), ty)
+}
+
+pub fn program(stmts: Vec<TaggedStatement<Position>>, end: Option<TaggedTerm<Position>>)
+               -> TaggedProgram<Position> {
+    TaggedProgram { tag: pos(), main: block(stmts, end) }
+}
+
+// The type of a function from I32s to an I32.
+pub fn i32_function(arity: usize) -> Type {
+    Type::FunctionTy(vec![], vec![Type::I32Ty; arity], Box::new(Type::I32Ty))
+}
+
+pub fn check(program: &TaggedProgram<Position>) -> Result<TaggedProgram<Type>, Vec<String>> {
+    program.type_check(&mut Env::new())
+}
@@ -0,0 +1,77 @@
+extern crate ende;
+extern crate llvm_sys;
+
+mod common;
+
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::codegen::compile_many;
+use ende::type_check::*;
+use llvm_sys::analysis::*;
+use llvm_sys::analysis::LLVMVerifierFailureAction::LLVMReturnStatusAction;
+use llvm_sys::core::LLVMGetNamedFunction;
+use llvm_sys::execution_engine::*;
+use llvm_sys::target::*;
+
+extern "C" fn twice(x: i32) -> i32 {
+    x * 2
+}
+
+// extern twice: (I32) -> I32;
+// twice(<i>)
+fn twice_of(i: i32) -> TaggedProgram<Type> {
+    check(&program(vec![extern_("twice", i32_function(1))],
+                   Some(call("twice", vec![lit(i)])))).unwrap()
+}
+
+// let x = 5;
+// x * 3
+fn fifteen() -> TaggedProgram<Type> {
+    check(&program(vec![let_("x", lit(5))], Some(infix(var("x"), Mul, lit(3))))).unwrap()
+}
+
+#[test]
+fn every_unit_is_callable() {
+    let (a, b, c) = (twice_of(4), fifteen(), twice_of(21));
+    let module = compile_many(&[("a", &a), ("b", &b), ("c", &c)]).unwrap();
+    unsafe {
+        let mut error = ptr::null_mut();
+        assert_eq!(LLVMVerifyModule(module, LLVMReturnStatusAction, &mut error), 0);
+        LLVMLinkInMCJIT();
+        assert_eq!(LLVM_InitializeNativeTarget(), 0);
+        assert_eq!(LLVM_InitializeNativeAsmPrinter(), 0);
+        let mut engine = ptr::null_mut();
+        assert_eq!(LLVMCreateExecutionEngineForModule(&mut engine, module, &mut error), 0);
+        // Both units that use `twice` share its one declaration.
+        let name = CString::new("twice").unwrap();
+        let declaration = LLVMGetNamedFunction(module, name.as_ptr());
+        LLVMAddGlobalMapping(engine, declaration, twice as *mut c_void);
+        let mut results = Vec::new();
+        for unit in &["a", "b", "c"] {
+            let name = CString::new(*unit).unwrap();
+            let address = LLVMGetFunctionAddress(engine, name.as_ptr());
+            assert!(address != 0, "unit {} wasn't exported", unit);
+            let function: extern "C" fn() -> i32 = mem::transmute(address as usize);
+            results.push(function());
+        }
+        assert_eq!(results, vec![8, 15, 42]);
+    }
+}
+
+#[test]
+fn unit_names_must_be_unique() {
+    let (a, b) = (twice_of(4), fifteen());
+    let errors = compile_many(&[("a", &a), ("a", &b)]).unwrap_err();
+    assert_eq!(errors, vec!["There is more than one unit named a.".to_string()]);
+}
+
+#[test]
+fn a_unit_cannot_be_named_like_an_extern() {
+    let errors = compile_many(&[("twice", &twice_of(4))]).unwrap_err();
+    assert_eq!(errors, vec!["Unit twice has the same name as an extern.".to_string()]);
+}
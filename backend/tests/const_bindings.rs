@@ -0,0 +1,59 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::ast::Position;
+use ende::codegen::Map;
+use ende::const_eval::{ConstValue, Value};
+use ende::diagnostics::check_all;
+use ende::type_check::*;
+
+fn const_bindings(program: &TaggedProgram<Position>) -> Vec<(String, ConstValue)> {
+    let mut env = Env::new();
+    env.insert("f".to_string(), i32_function(1));
+    let result = check_all(program, &mut env, &CheckOptions::default(), &[], &Map::new());
+    result.const_bindings().iter().map(|(name, value)| (name.clone(), value.clone())).collect()
+}
+
+fn i32(i: i32) -> ConstValue {
+    ConstValue { value: Value::I32(i), ty: Type::I32Ty }
+}
+
+#[test]
+fn constant_lets_are_read_without_running() {
+    // let width = 4 * 20;
+    // let debug = true;
+    // let mut count = 0;
+    // let port = f(80);
+    // let half = { let w = width; w / 2 };
+    // let port = 8080;
+    // let debug = f(1);
+    // 0
+    let half = scope(vec![let_("w", var("width"))], Some(infix(var("w"), Div, lit(2))));
+    let program = program(vec![let_("width", infix(lit(4), Mul, lit(20))),
+                               let_("debug", bool_lit(true)),
+                               let_mut("count", lit(0)),
+                               let_("port", call("f", vec![lit(80)])),
+                               let_("half", half),
+                               let_("port", lit(8080)),
+                               let_("debug", call("f", vec![lit(1)]))],
+                          Some(lit(0)));
+    // The later `port` is constant and replaces the call, but the later `debug` isn't and
+    // leaves it out. A mutable let is never constant.
+    assert_eq!(const_bindings(&program),
+               vec![("half".to_string(), i32(40)),
+                    ("port".to_string(), i32(8080)),
+                    ("width".to_string(), i32(80))]);
+}
+
+#[test]
+fn a_program_with_errors_has_no_constants() {
+    // let a = 1;
+    // let b = true + 1;
+    // 0
+    let program = program(vec![let_("a", lit(1)), let_("b", infix(bool_lit(true), Add, lit(1)))],
+                          Some(lit(0)));
+    assert_eq!(const_bindings(&program), vec![]);
+}
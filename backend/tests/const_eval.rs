@@ -0,0 +1,31 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::const_eval::{evaluate_main, Value, DEFAULT_BUDGET};
+use ende::type_check::*;
+
+#[test]
+fn a_statement_term_binds_in_a_scope_of_its_own() {
+    // let x = 1;
+    // (let x = 2);
+    // x
+    let inner = TaggedTerm::Stmt(Box::new(let_("x", lit(2))));
+    let typed = check(&program(vec![let_("x", lit(1)), semicolon(inner)], Some(var("x"))))
+                    .unwrap();
+    let evaluation = evaluate_main(&typed, DEFAULT_BUDGET).unwrap();
+    assert_eq!(evaluation.value, Value::I32(1));
+}
+
+#[test]
+fn a_statement_term_still_assigns_to_outer_variables() {
+    // let mut x = 1;
+    // (x = 2);
+    // x
+    let inner = TaggedTerm::Stmt(Box::new(mutate("x", lit(2))));
+    let typed = check(&program(vec![let_mut("x", lit(1)), semicolon(inner)], Some(var("x"))))
+                    .unwrap();
+    let evaluation = evaluate_main(&typed, DEFAULT_BUDGET).unwrap();
+    assert_eq!(evaluation.value, Value::I32(2));
+}
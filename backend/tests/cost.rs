@@ -0,0 +1,62 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Position;
+use ende::cost::*;
+use ende::type_check::*;
+
+fn report(stmts: Vec<TaggedStatement<Position>>) -> CostReport {
+    let mut all = vec![extern_("f", i32_function(1)), let_mut("n", bool_lit(true))];
+    all.extend(stmts);
+    estimate_cost(&check(&program(all, Some(lit(0)))).unwrap())
+}
+
+// while n { f(1); };
+fn single_loop() -> TaggedTerm<Position> {
+    while_(var("n"), block(vec![semicolon(call("f", vec![lit(1)]))], None))
+}
+
+#[test]
+fn straight_line_code() {
+    let report = report(vec![semicolon(call("f", vec![lit(1)]))]);
+    assert_eq!(report.bucket, CostBucket::StraightLine);
+    assert_eq!(report.call_sites, 1);
+}
+
+#[test]
+fn a_single_loop() {
+    let report = report(vec![semicolon(single_loop())]);
+    assert_eq!(report,
+               CostReport { max_loop_depth: 1, loops: 1, call_sites: 1,
+                            bucket: CostBucket::SingleLoop });
+}
+
+#[test]
+fn nested_loops() {
+    // while n { while n { f(1); }; };
+    // while false { while n {}; };
+    let outer = while_(var("n"), block(vec![semicolon(single_loop())], None));
+    let dead = while_(bool_lit(false),
+                      block(vec![semicolon(while_(var("n"), block(vec![], None)))], None));
+    let report = report(vec![semicolon(outer), semicolon(dead)]);
+    // The loop that never runs isn't counted, and neither is the one inside it.
+    assert_eq!(report,
+               CostReport { max_loop_depth: 2, loops: 2, call_sites: 1,
+                            bucket: CostBucket::NestedLoops });
+}
+
+#[test]
+fn enforce_lists_every_violation() {
+    let report = report(vec![semicolon(single_loop()), semicolon(single_loop())]);
+    assert_eq!(enforce(&CostLimits::default(), &report), Ok(()));
+    let limits = CostLimits {
+        max_loop_depth: Some(1),
+        max_loops: Some(1),
+        max_call_sites: Some(1),
+    };
+    assert_eq!(enforce(&limits, &report),
+               Err(vec![Violation::Loops { limit: 1, actual: 2 },
+                        Violation::CallSites { limit: 1, actual: 2 }]));
+}
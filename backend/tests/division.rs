@@ -0,0 +1,95 @@
+extern crate ende;
+extern crate llvm_sys;
+
+mod common;
+
+use std::ffi::CString;
+use std::i32;
+use std::mem;
+use std::ptr;
+
+use common::*;
+use ende::ast::Operator;
+use ende::ast::Operator::*;
+use ende::codegen::compile_many;
+use ende::const_eval::{evaluate_main, GiveUp, Value, DEFAULT_BUDGET};
+use ende::type_check::*;
+use llvm_sys::analysis::*;
+use llvm_sys::analysis::LLVMVerifierFailureAction::LLVMReturnStatusAction;
+use llvm_sys::execution_engine::*;
+use llvm_sys::target::*;
+
+const OPERANDS: &'static [i32] = &[i32::MIN, -7, -2, -1, 0, 1, 2, 7, i32::MAX];
+
+// let mut a = <left>;
+// let mut b = <right>;
+// a <op> b
+// The operands are variables, so that the division is done at runtime rather than folded.
+fn divide(op: Operator, left: i32, right: i32) -> TaggedProgram<Type> {
+    check(&program(vec![let_mut("a", lit(left)), let_mut("b", lit(right))],
+                   Some(infix(var("a"), op, var("b"))))).unwrap()
+}
+
+// Runs the units of one module, each a main returning an I32.
+fn run(units: &[(String, TaggedProgram<Type>)]) -> Vec<i32> {
+    let units: Vec<(&str, &TaggedProgram<Type>)> =
+        units.iter().map(|&(ref name, ref program)| (&name[..], program)).collect();
+    let module = compile_many(&units).unwrap();
+    unsafe {
+        let mut error = ptr::null_mut();
+        assert_eq!(LLVMVerifyModule(module, LLVMReturnStatusAction, &mut error), 0);
+        LLVMLinkInMCJIT();
+        assert_eq!(LLVM_InitializeNativeTarget(), 0);
+        assert_eq!(LLVM_InitializeNativeAsmPrinter(), 0);
+        let mut engine = ptr::null_mut();
+        assert_eq!(LLVMCreateExecutionEngineForModule(&mut engine, module, &mut error), 0);
+        units.iter().map(|&(name, _)| {
+            let name = CString::new(name).unwrap();
+            let address = LLVMGetFunctionAddress(engine, name.as_ptr());
+            let function: extern "C" fn() -> i32 = mem::transmute(address as usize);
+            function()
+        }).collect()
+    }
+}
+
+// Every cell of the grid is either computed the same by const eval and compiled code, or has no
+// value, which const eval refuses instead of folding. Only the latter cells aren't run, since
+// compiled code traps there.
+fn agree(op: Operator) {
+    let mut units = Vec::new();
+    let mut evaluated = Vec::new();
+    for &left in OPERANDS {
+        for &right in OPERANDS {
+            let program = divide(op, left, right);
+            let evaluation = evaluate_main(&program, DEFAULT_BUDGET).map(|e| e.value);
+            if right == 0 || (left == i32::MIN && right == -1) {
+                assert_eq!(evaluation, Err(GiveUp::Unsupported), "{} {:?} {}", left, op, right);
+                continue;
+            }
+            units.push((format!("cell{}", units.len()), program));
+            evaluated.push((left, right, evaluation));
+        }
+    }
+    for ((left, right, evaluation), compiled) in evaluated.into_iter().zip(run(&units)) {
+        assert_eq!(evaluation, Ok(Value::I32(compiled)), "{} {:?} {}", left, op, right);
+    }
+}
+
+#[test]
+fn division_agrees_everywhere() {
+    agree(Div);
+}
+
+#[test]
+fn remainder_agrees_everywhere() {
+    agree(Rem);
+}
+
+#[test]
+fn division_truncates_and_the_remainder_takes_the_sign_of_the_dividend() {
+    let evaluate = |op, left, right| evaluate_main(&divide(op, left, right), DEFAULT_BUDGET);
+    let cells = [(Div, -7, 2, -3), (Div, 7, -2, -3), (Rem, -7, 2, -1), (Rem, 7, -2, 1)];
+    for &(op, left, right, expected) in &cells {
+        assert_eq!(evaluate(op, left, right).map(|e| e.value), Ok(Value::I32(expected)));
+    }
+}
@@ -0,0 +1,62 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Position;
+use ende::env_trace::{RecordingEnv, dump_env};
+use ende::type_check::*;
+
+fn line(line: u32) -> Position {
+    Position { start_pos: (line, 1), end_pos: (line, 1) }
+}
+
+// extern putchar: (I32) -> I32;
+// let x = 1;
+// let mut y = x;
+// let f = |a: I32| { a };
+// y
+fn program() -> TaggedProgram<Position> {
+    use ende::type_check::TaggedStatement::*;
+    let lambda = TaggedTerm::Lambda(line(5),
+                                    vec![("a".to_string(), Type::I32Ty)],
+                                    block(vec![], Some(var("a"))));
+    TaggedProgram {
+        tag: line(1),
+        main: TaggedBlock {
+            tag: line(1),
+            stmts: vec![Extern(line(2), "putchar".to_string(), i32_function(1)),
+                        Let(line(3), "x".to_string(), Type::Hole, lit(1)),
+                        LetMut(line(4), "y".to_string(), Type::Hole, var("x")),
+                        Let(line(5), "f".to_string(), Type::Hole, lambda)],
+            end: Box::new(Some(var("y"))),
+        },
+    }
+}
+
+#[test]
+fn the_env_at_the_end_of_main() {
+    let mut env = RecordingEnv::new(1000);
+    env.insert("getchar".to_string(), i32_function(0));
+    program().type_check(&mut env).unwrap();
+    let trace = env.trace();
+    assert_eq!(dump_env(&trace.env_at(trace.events.len())),
+               "f: (I32) -> I32 (let, line 5)\n\
+                getchar: () -> I32 (host)\n\
+                putchar: (I32) -> I32 (extern, line 2)\n\
+                x: I32 (let, line 3)\n\
+                y: I32 (let mut, line 4)\n");
+}
+
+#[test]
+fn the_env_inside_a_lambda() {
+    let mut env = RecordingEnv::new(1000);
+    program().type_check(&mut env).unwrap();
+    let trace = env.trace();
+    let step = trace.step_introducing("a").unwrap();
+    assert_eq!(trace.dump_at(step + 1),
+               "a: I32 (parameter, line 5)\n\
+                putchar: (I32) -> I32 (extern, line 2)\n\
+                x: I32 (let, line 3)\n\
+                y: I32 (let mut, line 4)\n");
+}
@@ -0,0 +1,54 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::ast::Position;
+use ende::codegen::Map;
+use ende::env_trace::*;
+use ende::type_check::*;
+
+// let a = 1;
+// let b = { let c = 2; c + a };
+// let d = 3;
+// d
+fn program() -> TaggedProgram<Position> {
+    let b = scope(vec![let_("c", lit(2))], Some(infix(var("c"), Add, var("a"))));
+    common::program(vec![let_("a", lit(1)), let_("b", b), let_("d", lit(3))], Some(var("d")))
+}
+
+fn names(env: Map<Binding>) -> Vec<String> {
+    let mut names: Vec<String> = env.keys().cloned().collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn checking_is_replayed_step_by_step() {
+    let mut env = RecordingEnv::new(1000);
+    program().type_check(&mut env).unwrap();
+    let trace = env.trace();
+    assert!(!trace.truncated);
+    let pushes = trace.events.iter().filter(|event| **event == EnvEvent::ScopePush).count();
+    let pops = trace.events.iter().filter(|event| **event == EnvEvent::ScopePop).count();
+    assert_eq!(pushes, pops);
+    let c = trace.step_introducing("c").unwrap();
+    assert_eq!(names(trace.env_at(c)), vec!["a"]);
+    assert_eq!(names(trace.env_at(c + 1)), vec!["a", "c"]);
+    // `c` went out of scope with its block before `b` was bound.
+    let b = trace.step_introducing("b").unwrap();
+    assert!(c < b);
+    assert_eq!(names(trace.env_at(b)), vec!["a"]);
+    assert_eq!(names(trace.env_at(trace.events.len())), vec!["a", "b", "d"]);
+    assert_eq!(trace.step_introducing("e"), None);
+}
+
+#[test]
+fn a_trace_stops_at_its_limit() {
+    let mut env = RecordingEnv::new(2);
+    program().type_check(&mut env).unwrap();
+    let trace = env.trace();
+    assert!(trace.truncated);
+    assert_eq!(trace.events, vec![EnvEvent::ScopePush, EnvEvent::ScopePop]);
+}
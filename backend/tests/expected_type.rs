@@ -0,0 +1,31 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::type_check::*;
+
+fn check_expecting(ty: Type,
+                   program: &TaggedProgram<ende::ast::Position>)
+                   -> Result<TaggedProgram<Type>, Vec<String>> {
+    let options = CheckOptions { expected_program_type: Some(ty), ..CheckOptions::default() };
+    program.type_check_with(&mut Env::new(), &options)
+}
+
+#[test]
+fn main_must_produce_the_expected_type() {
+    assert!(check_expecting(Type::I32Ty, &program(vec![], Some(lit(0)))).is_ok());
+    let errors = check_expecting(Type::I32Ty, &program(vec![], None)).unwrap_err();
+    assert_eq!(errors,
+               vec!["This script must produce I32 as required by the host, but it produces \
+                     ().".to_string()]);
+}
+
+#[test]
+fn a_main_that_never_finishes_produces_any_type() {
+    // while true {}
+    let forever = program(vec![], Some(while_(bool_lit(true), block(vec![], None))));
+    assert_eq!(check(&forever).unwrap().main.tag, Type::Never);
+    assert!(check_expecting(Type::I32Ty, &forever).is_ok());
+    assert!(check_expecting(Type::UnitTy, &forever).is_ok());
+}
@@ -0,0 +1,13 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+
+#[test]
+fn keywords_are_not_names() {
+    for keyword in &["struct", "enum", "match", "type"] {
+        let errors = check(&program(vec![let_(keyword, lit(1))], Some(lit(0)))).unwrap_err();
+        assert_eq!(errors, vec![format!("The keyword {} can't be used as a name.", keyword)]);
+    }
+}
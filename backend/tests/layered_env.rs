@@ -0,0 +1,52 @@
+extern crate ende;
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::type_check::*;
+
+fn global() -> Env {
+    let mut global = Env::new();
+    global.insert("f".to_string(), i32_function(1));
+    global
+}
+
+#[test]
+fn an_overlay_leaves_the_global_env_unchanged() {
+    let global = global();
+    let before = global.clone();
+    let mut env = LayeredEnv::new(&global);
+    // let x = f(1);
+    // let f = x + 1;
+    // f
+    let program = program(vec![let_("x", call("f", vec![lit(1)])),
+                               let_("f", infix(var("x"), Add, lit(1)))],
+                          Some(var("f")));
+    program.type_check(&mut env).unwrap();
+    // The shadowing `f` is only in the overlay.
+    assert_eq!(env.get("f"), Some(&Type::I32Ty));
+    assert_eq!(env.local.get("x"), Some(&Type::I32Ty));
+    assert_eq!(global, before);
+    assert_eq!(global.get("f"), Some(&i32_function(1)));
+}
+
+#[test]
+fn threads_share_one_global_env() {
+    let global = Arc::new(global());
+    let handles: Vec<_> = (0..4).map(|i| {
+        let global = global.clone();
+        thread::spawn(move || {
+            let mut env = LayeredEnv::new(&global);
+            let program = program(vec![let_("x", call("f", vec![lit(i)]))], Some(var("x")));
+            program.type_check(&mut env).map(|_| env.local.values.len())
+        })
+    }).collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), Ok(1));
+    }
+    assert_eq!(*global, self::global());
+}
@@ -0,0 +1,52 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::type_check::*;
+
+fn limited() -> CheckOptions {
+    CheckOptions {
+        max_statements: Some(3),
+        max_nodes: Some(7),
+        max_identifier_length: Some(5),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn a_program_at_the_limits_is_checked() {
+    // let a = 1; let b = 1; let abcde = 1;
+    let program = program(vec![let_("a", lit(1)), let_("b", lit(1)), let_("abcde", lit(1))],
+                          None);
+    assert!(program.type_check_with(&mut Env::new(), &limited()).is_ok());
+}
+
+#[test]
+fn a_program_over_the_limits_is_refused_before_checking() {
+    // let a = 1; let b = 1; let c = 1; let abcdefghijklmnopq = x;
+    // ...where `x` isn't bound, which checking would report.
+    let name = "abcdefghijklmnopq";
+    let program = program(vec![let_("a", lit(1)),
+                               let_("b", lit(1)),
+                               let_("c", lit(1)),
+                               let_(name, var("x"))],
+                          None);
+    let errors = program.type_check_with(&mut Env::new(), &limited()).unwrap_err();
+    assert_eq!(errors,
+               vec!["The program has 4 top-level statements, but at most 3 are allowed."
+                        .to_string(),
+                    "The program has 8 terms and statements, but at most 7 are allowed."
+                        .to_string(),
+                    "The name abcdefghijklmnop... is 17 bytes long, but names can be at most \
+                     5 bytes long."
+                        .to_string()]);
+    let unlimited = CheckOptions {
+        max_statements: None,
+        max_nodes: None,
+        max_identifier_length: None,
+        ..Default::default()
+    };
+    let errors = program.type_check_with(&mut Env::new(), &unlimited).unwrap_err();
+    assert!(errors.iter().all(|error| !error.contains("allowed")), "{:?}", errors);
+}
@@ -0,0 +1,48 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::codegen::Map;
+use ende::lint::*;
+use ende::type_check::*;
+use ende::usage::Feature;
+
+// An embedder's own rule.
+struct NoWhile;
+
+impl Lint for NoWhile {
+    fn name(&self) -> &str {
+        "no_while"
+    }
+
+    fn check(&self, _: &TaggedProgram<Type>, ctx: &LintContext, sink: &mut DiagnosticSink) {
+        if ctx.usage.count(Feature::While) > 0 {
+            sink.report("while loops aren't allowed.".to_string());
+        }
+    }
+}
+
+// let a = 1;
+// while false {};
+// 0
+fn program() -> TaggedProgram<Type> {
+    let loop_ = while_(bool_lit(false), block(vec![], None));
+    check(&common::program(vec![let_("a", lit(1)), semicolon(loop_)], Some(lit(0)))).unwrap()
+}
+
+#[test]
+fn lints_warn_by_default() {
+    assert_eq!(run_lints(&program(), &[&UnusedVariable, &NoWhile], &Map::new()),
+               Ok(vec!["[unused_variable] Variable a is never read.".to_string(),
+                       "[no_while] while loops aren't allowed.".to_string()]));
+}
+
+#[test]
+fn a_denied_lint_fails_and_an_allowed_one_is_silent() {
+    let mut levels = Map::new();
+    levels.insert("no_while".to_string(), LintLevel::Deny);
+    levels.insert("unused_variable".to_string(), LintLevel::Allow);
+    assert_eq!(run_lints(&program(), &[&UnusedVariable, &NoWhile], &levels),
+               Err(vec!["[no_while] while loops aren't allowed.".to_string()]));
+}
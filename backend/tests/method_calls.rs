@@ -0,0 +1,44 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::codegen::Compile;
+use ende::type_check::*;
+
+#[test]
+fn checking_turns_a_method_call_into_a_call() {
+    let mut env = Env::new();
+    env.insert("add".to_string(), i32_function(2));
+    let method = TaggedFunctionCall { tag: pos(), name: "add".to_string() };
+    let program = program(vec![],
+                          Some(TaggedTerm::MethodCall(pos(), Box::new(lit(1)), method,
+                                                      vec![lit(2)])));
+    let typed = program.type_check(&mut env).unwrap();
+    match *typed.main.end {
+        Some(TaggedTerm::Call(Type::I32Ty, ref func, ref args)) => {
+            assert_eq!(func.name, "add");
+            assert_eq!(args.len(), 2);
+        }
+        ref end => panic!("expected a call, found {:?}", end),
+    }
+}
+
+#[test]
+fn a_method_call_left_in_a_typed_tree_is_an_error() {
+    let method = TaggedFunctionCall { tag: i32_function(1), name: "f".to_string() };
+    let call = TaggedTerm::MethodCall(Type::I32Ty,
+                                      Box::new(TaggedTerm::Literal(Type::I32Ty, 1)),
+                                      method,
+                                      vec![]);
+    let program = TaggedProgram {
+        tag: Type::Forbidden,
+        main: TaggedBlock {
+            tag: Type::I32Ty,
+            stmts: vec![TaggedStatement::Extern(Type::Forbidden, "f".to_string(), i32_function(1))],
+            end: Box::new(Some(call)),
+        },
+    };
+    let errors = program.gen_module().unwrap_err();
+    assert!(errors.iter().any(|error| error.contains("method call")));
+}
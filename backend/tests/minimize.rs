@@ -0,0 +1,47 @@
+extern crate ende;
+
+mod common;
+
+use std::time::Instant;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::ast::Position;
+use ende::minimize::{minimize, program_size};
+use ende::type_check::*;
+
+const FAILURE: &'static str = "The operator + is not defined for types Bool and I32.";
+
+// Whether the program still fails the way the planted statement makes it fail.
+fn fails(program: &TaggedProgram<Position>) -> bool {
+    match check(program) {
+        Ok(_) => false,
+        Err(errors) => errors.iter().any(|error| error == FAILURE),
+    }
+}
+
+#[test]
+fn a_planted_failure_is_all_that_is_left() {
+    // let v<i> = i + { let q = 1; q * 2 };
+    // ...with `let z = { let bad = true + 1; 0 };` planted in the middle.
+    let mut stmts = Vec::new();
+    for i in 0..400 {
+        let rhs = infix(lit(i),
+                        Add,
+                        scope(vec![let_("q", lit(1))], Some(infix(var("q"), Mul, lit(2)))));
+        stmts.push(let_(&format!("v{}", i), rhs));
+        if i == 237 {
+            let planted = infix(bool_lit(true), Add, lit(1));
+            stmts.push(let_("z", scope(vec![let_("bad", planted)], Some(lit(0)))));
+        }
+    }
+    let program = program(stmts, Some(var("v399")));
+    assert!(program_size(&program) > 3000);
+    assert!(fails(&program));
+    let start = Instant::now();
+    let minimized = minimize(&program, fails);
+    println!("minimized in {:?}", start.elapsed());
+    assert!(fails(&minimized));
+    // let z = { let bad = true + 1; };
+    assert_eq!(program_size(&minimized), 6);
+}
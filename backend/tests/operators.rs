@@ -0,0 +1,45 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator;
+use ende::codegen::Compile;
+use ende::type_check::*;
+use ende::validate::validate;
+
+fn bool_sum() -> TaggedProgram<ende::ast::Position> {
+    program(vec![let_("b", infix(bool_lit(true), Operator::Add, bool_lit(false)))], Some(lit(0)))
+}
+
+#[test]
+fn check_only_operators_are_checked() {
+    let errors = bool_sum().type_check_with(&mut Env::new(), &CheckOptions::default())
+                           .unwrap_err();
+    assert_eq!(errors, vec!["The operator + is not defined for types Bool and Bool."]);
+    let mut options = CheckOptions::default();
+    options.check_only_operators.push((Operator::Add, Type::BoolTy, Type::BoolTy, Type::BoolTy));
+    assert!(bool_sum().type_check_with(&mut Env::new(), &options).is_ok());
+}
+
+#[test]
+fn check_only_operators_are_not_compiled() {
+    let mut options = CheckOptions::default();
+    options.check_only_operators.push((Operator::Add, Type::BoolTy, Type::BoolTy, Type::BoolTy));
+    let typed = bool_sum().type_check_with(&mut Env::new(), &options).unwrap();
+    let errors = validate(&typed).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path, "main.stmts[0].rhs");
+    assert_eq!(errors[0].message, "expected operands of a built-in +, found Bool");
+}
+
+// Codegen validates in every build, so this holds whether debug assertions are on or not.
+#[test]
+fn compiling_check_only_operators_is_an_error() {
+    let mut options = CheckOptions::default();
+    options.check_only_operators.push((Operator::Add, Type::BoolTy, Type::BoolTy, Type::BoolTy));
+    let typed = bool_sum().type_check_with(&mut Env::new(), &options).unwrap();
+    let errors = typed.gen_module().unwrap_err();
+    assert_eq!(errors,
+               vec!["main.stmts[0].rhs: expected operands of a built-in +, found Bool"]);
+}
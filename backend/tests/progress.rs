@@ -0,0 +1,114 @@
+extern crate ende;
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::*;
+use ende::api::*;
+use ende::api::Phase::*;
+use ende::api::ProgressEvent::*;
+use ende::progress::report;
+
+const PROGRAM: &'static str = "fn main() -> I32 {
+   extern putchar(I32) -> I32;
+   putchar(65);
+   0
+};";
+
+// A type error, so checking fails.
+const ILL_TYPED: &'static str = "fn main() -> I32 {
+   let both = true * false;
+   0
+};";
+
+// Options that record the events of a compilation, and the events recorded.
+fn recording() -> (CompileOptions, Arc<Mutex<Vec<ProgressEvent>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    let options = CompileOptions {
+        progress: Some(Progress::new(move |event| recorded.lock().unwrap().push(event))),
+        ..CompileOptions::default()
+    };
+    (options, events)
+}
+
+fn events(input: &str) -> (bool, Vec<ProgressEvent>) {
+    let (options, events) = recording();
+    let compiled = compile_str_with_options(input, &[], &CheckOptions::default(), &options);
+    let events = events.lock().unwrap().clone();
+    (compiled.is_ok(), events)
+}
+
+// let id = |a: I32| { a };
+// let one = |a: I32| { 1 };
+// id(0)
+fn with_lambdas() -> TaggedProgram<Position> {
+    let lambda = |end| {
+        TaggedTerm::Lambda(pos(), vec![("a".to_string(), Type::I32Ty)], block(vec![], Some(end)))
+    };
+    let apply = TaggedTerm::Apply(pos(), Box::new(var("id")), vec![lit(0)]);
+    program(vec![let_("id", lambda(var("a"))), let_("one", lambda(lit(1)))], Some(apply))
+}
+
+#[test]
+fn every_phase_starts_and_finishes_in_order() {
+    assert_eq!(events(PROGRAM),
+               (true,
+                vec![PhaseStarted(Parse),
+                     PhaseFinished(Parse),
+                     PhaseStarted(Check),
+                     ItemsChecked { done: 1, total: 2 },
+                     ItemsChecked { done: 2, total: 2 },
+                     PhaseFinished(Check),
+                     PhaseStarted(Codegen),
+                     Pass("validate"),
+                     FunctionsEmitted { done: 1, total: 1 },
+                     PhaseFinished(Codegen)]));
+}
+
+#[test]
+fn a_failing_phase_never_finishes() {
+    assert_eq!(events(ILL_TYPED),
+               (false, vec![PhaseStarted(Parse), PhaseFinished(Parse), PhaseStarted(Check)]));
+}
+
+#[test]
+fn compiling_a_checked_program_reports_each_function() {
+    let result = check_program(&with_lambdas(), &mut Env::new(), &CheckOptions::default());
+    let (options, events) = recording();
+    assert!(compile_checked_with(&result, &options).is_ok());
+    assert_eq!(*events.lock().unwrap(),
+               vec![PhaseStarted(Codegen),
+                    Pass("validate"),
+                    FunctionsEmitted { done: 1, total: 3 },
+                    FunctionsEmitted { done: 2, total: 3 },
+                    FunctionsEmitted { done: 3, total: 3 },
+                    PhaseFinished(Codegen)]);
+}
+
+#[test]
+fn evaluating_main_is_a_pass() {
+    let result = check_program(&program(vec![], Some(lit(7))), &mut Env::new(),
+                               &CheckOptions::default());
+    let (options, events) = recording();
+    let options = CompileOptions { evaluate_const_main: true, ..options };
+    assert!(compile_checked_with(&result, &options).is_ok());
+    assert_eq!(*events.lock().unwrap(),
+               vec![PhaseStarted(Codegen),
+                    Pass("evaluate const main"),
+                    Pass("validate"),
+                    FunctionsEmitted { done: 1, total: 1 },
+                    PhaseFinished(Codegen)]);
+}
+
+#[test]
+fn no_event_is_made_without_a_callback() {
+    report(&None, || panic!("made an event without a callback"));
+    let result = check_program(&with_lambdas(), &mut Env::new(), &CheckOptions::default());
+    assert!(compile_checked_with(&result, &CompileOptions::default()).is_ok());
+    let options = CompileOptions::default();
+    let errors = compile_str_with_options(ILL_TYPED, &[], &CheckOptions::default(), &options)
+                     .unwrap_err();
+    assert_eq!(errors, compile_str(ILL_TYPED).unwrap_err());
+}
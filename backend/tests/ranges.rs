@@ -0,0 +1,50 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::ranges::*;
+use ende::trace::type_check_traced;
+use ende::type_check::*;
+
+#[test]
+fn ranges_follow_literals_and_branches() {
+    // let mut t = true;
+    // let c = if t { 3 } else { 0 - 4 };
+    // c * 2
+    let branch = TaggedTerm::If(pos(),
+                                Box::new(var("t")),
+                                Box::new(lit(3)),
+                                Some(Box::new(infix(lit(0), Sub, lit(4)))));
+    let program = program(vec![let_mut("t", bool_lit(true)), let_("c", branch)],
+                          Some(infix(var("c"), Mul, lit(2))));
+    let typed = check(&program).unwrap();
+    // The trace numbers nodes the same way, and tells which node is the product.
+    let (_, judgments) = type_check_traced(&program, &mut Env::new()).unwrap();
+    let product = judgments.iter().rev().find(|judgment| judgment.rule == "Infix-I32").unwrap();
+    assert_eq!(infer_ranges(&typed.main)[&product.node], Range { lo: -8, hi: 6 });
+    assert_eq!(range_warnings(&typed.main), vec![]);
+}
+
+#[test]
+fn operations_that_always_fail_are_warned_about() {
+    // let d = 2 - 2;
+    // let q = 7 / d;
+    // let big = 2147483647 + 1;
+    // 0
+    let program = program(vec![let_("d", infix(lit(2), Sub, lit(2))),
+                               let_("q", infix(lit(7), Div, var("d"))),
+                               let_("big", infix(lit(2147483647), Add, lit(1)))],
+                          Some(lit(0)));
+    let typed = check(&program).unwrap();
+    let (_, judgments) = type_check_traced(&program, &mut Env::new()).unwrap();
+    let infixes: Vec<_> = judgments.iter()
+                                   .filter(|judgment| judgment.rule == "Infix-I32")
+                                   .map(|judgment| judgment.node)
+                                   .collect();
+    assert_eq!(infixes.len(), 3);
+    assert_eq!(range_warnings(&typed.main),
+               vec![RangeWarning::DivisionByZero(infixes[1]),
+                    RangeWarning::Overflow(infixes[2], Add)]);
+}
@@ -0,0 +1,45 @@
+extern crate ende;
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+
+use common::*;
+use ende::api::*;
+use ende::codegen::Compile;
+
+// Everything the API hands back, except for LLVM modules, can be checked and compiled on worker
+// threads and kept in shared caches. This fails to compile if a type stops being Send or Sync.
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn public_types_are_send_and_sync() {
+    assert_send_sync::<TaggedProgram<Position>>();
+    assert_send_sync::<TaggedProgram<Type>>();
+    assert_send_sync::<CheckResult>();
+    assert_send_sync::<Diagnostic>();
+    assert_send_sync::<Severity>();
+    assert_send_sync::<CheckOptions>();
+    assert_send_sync::<Strictness>();
+    assert_send_sync::<CompileOptions>();
+    assert_send_sync::<CompilationReport>();
+    assert_send_sync::<ConstValue>();
+    assert_send_sync::<Value>();
+    assert_send_sync::<ExternSet>();
+    assert_send_sync::<LinkOptions>();
+    assert_send_sync::<UsedBindings>();
+    assert_send_sync::<Phase>();
+    assert_send_sync::<ProgressEvent>();
+    assert_send_sync::<Progress>();
+}
+
+#[test]
+fn a_checked_program_is_compiled_on_another_thread() {
+    let typed = Arc::new(check(&program(vec![], Some(lit(7)))).unwrap());
+    let shared = typed.clone();
+    let ir = thread::spawn(move || unsafe { into_thread_safe(shared.gen_module().unwrap()) })
+                 .join()
+                 .unwrap();
+    assert!(ir.contains("define i32 @main"), "{}", ir);
+}
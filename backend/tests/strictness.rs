@@ -0,0 +1,38 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::strictness::Strictness;
+use ende::type_check::*;
+
+fn check_in(env: &mut Env,
+            strictness: Strictness,
+            program: &TaggedProgram<ende::ast::Position>)
+            -> Result<TaggedProgram<Type>, Vec<String>> {
+    let options = CheckOptions { strictness: strictness, ..CheckOptions::default() };
+    program.type_check_with(env, &options)
+}
+
+#[test]
+fn both_modes_leave_the_env_the_same() {
+    // let x = 1;
+    // x
+    let program = program(vec![let_("x", lit(1))], Some(var("x")));
+    let (mut strict, mut legacy) = (Env::new(), Env::new());
+    check_in(&mut strict, Strictness::Strict, &program).unwrap();
+    check_in(&mut legacy, Strictness::Legacy, &program).unwrap();
+    assert_eq!(legacy, strict);
+    assert_eq!(legacy.get("x"), Some(&Type::I32Ty));
+}
+
+#[test]
+fn a_legacy_check_sees_the_bindings_of_the_one_before() {
+    let mut env = Env::new();
+    // let x = 1;
+    check_in(&mut env, Strictness::Legacy, &program(vec![let_("x", lit(1))], None)).unwrap();
+    // x + 1
+    let next = program(vec![], Some(infix(var("x"), Add, lit(1))));
+    assert_eq!(check_in(&mut env, Strictness::Legacy, &next).unwrap().main.tag, Type::I32Ty);
+}
@@ -0,0 +1,229 @@
+extern crate ende;
+
+mod common;
+
+use std::io::ErrorKind;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::type_check::*;
+use ende::typed_ast::*;
+
+const TYPE_CHECK: &'static str = include_str!("../src/type_check.rs");
+const AST: &'static str = include_str!("../src/ast.rs");
+
+// The declarations the format is made of, as they were when `FORMAT_VERSION` was last bumped.
+// When this test fails, the format changed: bump `FORMAT_VERSION`, then update both below.
+const CHECKED_VERSION: u32 = 33;
+
+fn checklist() -> Vec<(&'static str, &'static str, Vec<&'static str>)> {
+    vec![
+        (AST, "pub enum Operator {", vec![
+            "Add", "Sub", "Mul", "Div", "Rem", "Eq", "Ne", "Lt", "Le", "Gt", "Ge", "And", "Or",
+            "BitAnd", "BitOr", "BitXor",
+        ]),
+        (AST, "pub enum Pattern {", vec!["Constructor(String, String, Vec<String>)", "Wildcard"]),
+        (TYPE_CHECK, "pub enum Type {", vec![
+            "Forbidden",
+            "I32Ty",
+            "Enum(Enumeration)",
+            "FunctionTy(Vec<String>, Vec<Type>, Box<Type>)",
+            "BoolTy",
+            "I64Ty",
+            "U32Ty",
+            "I8Ty",
+            "I16Ty",
+            "F64Ty",
+            "CharTy",
+            "StrTy",
+            "UnitTy",
+            "TupleTy(Vec<Type>)",
+            "ArrayTy(Box<Type>, u32)",
+            "Struct(Structure)",
+            "Named(String)",
+            "Ref(Box<Type>)",
+            "RefMut(Box<Type>)",
+            "Ptr",
+            "Param(String)",
+            "Hole",
+            "Meta(u32)",
+            "Never",
+        ]),
+        (TYPE_CHECK, "pub struct Enumeration {", vec![
+            "pub name: String",
+            "variants: Vec<(String, Vec<Type>)>",
+        ]),
+        (TYPE_CHECK, "pub struct Structure {", vec![
+            "pub name: String",
+            "fields: Vec<(String, Type)>",
+        ]),
+        (TYPE_CHECK, "pub struct TaggedFunctionCall<Tag> {", vec![
+            "pub tag: Tag",
+            "pub name: String",
+        ]),
+        (TYPE_CHECK, "pub enum TaggedTerm<Tag> {", vec![
+            "Literal(Tag, i32)",
+            "Var(Tag, String)",
+            "Infix(Tag, Box<TaggedTerm<Tag>>, Operator, Box<TaggedTerm<Tag>>)",
+            "Call(Tag, TaggedFunctionCall<Tag>, Vec<TaggedTerm<Tag>>)",
+            "Scope(Tag, TaggedBlock<Tag>)",
+            "If(Tag, Box<TaggedTerm<Tag>>, Box<TaggedTerm<Tag>>, Option<Box<TaggedTerm<Tag>>>)",
+            "While(Tag, Box<TaggedTerm<Tag>>, TaggedBlock<Tag>)",
+            "Stmt(Box<TaggedStatement<Tag>>)",
+            "BoolLiteral(Tag, bool)",
+            "SuffixedLiteral(Tag, i64, Type)",
+            "Cast(Tag, Box<TaggedTerm<Tag>>, Type)",
+            "FloatLiteral(Tag, u64)",
+            "CharLit(Tag, char)",
+            "StrLit(Tag, String)",
+            "Tuple(Tag, Vec<TaggedTerm<Tag>>)",
+            "Project(Tag, Box<TaggedTerm<Tag>>, u32)",
+            "ArrayLit(Tag, Vec<TaggedTerm<Tag>>)",
+            "Index(Tag, Box<TaggedTerm<Tag>>, Box<TaggedTerm<Tag>>)",
+            "StructLit(Tag, String, Vec<(String, TaggedTerm<Tag>)>)",
+            "Field(Tag, Box<TaggedTerm<Tag>>, String)",
+            "Variant(Tag, String, String, Vec<TaggedTerm<Tag>>)",
+            "Match(Tag, Box<TaggedTerm<Tag>>, Vec<(Pattern, TaggedTerm<Tag>)>)",
+            "Borrow(Tag, Box<TaggedTerm<Tag>>)",
+            "Deref(Tag, Box<TaggedTerm<Tag>>)",
+            "BorrowMut(Tag, Box<TaggedTerm<Tag>>)",
+            "Null(Tag)",
+            "PtrEq(Tag, Box<TaggedTerm<Tag>>, Box<TaggedTerm<Tag>>)",
+            "Lambda(Tag, Vec<(String, Type)>, TaggedBlock<Tag>)",
+            "Apply(Tag, Box<TaggedTerm<Tag>>, Vec<TaggedTerm<Tag>>)",
+            "Ascribe(Tag, Box<TaggedTerm<Tag>>, Type)",
+            "MethodCall(Tag, Box<TaggedTerm<Tag>>, TaggedFunctionCall<Tag>, Vec<TaggedTerm<Tag>>)",
+            "Neg(Tag, Box<TaggedTerm<Tag>>)",
+            "Not(Tag, Box<TaggedTerm<Tag>>)",
+        ]),
+        (TYPE_CHECK, "pub enum TaggedStatement<Tag> {", vec![
+            "TermSemicolon(Tag, TaggedTerm<Tag>)",
+            "Let(Tag, String, Type, TaggedTerm<Tag>)",
+            "LetMut(Tag, String, Type, TaggedTerm<Tag>)",
+            "Mutate(Tag, String, TaggedTerm<Tag>)",
+            "Extern(Tag, String, Type)",
+            "Declare(Tag, String, Type)",
+            "StructDecl(Tag, String, Vec<(String, Type)>)",
+            "EnumDecl(Tag, String, Vec<(String, Vec<Type>)>)",
+            "TypeAlias(Tag, String, Type)",
+            "MutateThrough(Tag, TaggedTerm<Tag>, TaggedTerm<Tag>)",
+        ]),
+        (TYPE_CHECK, "pub struct TaggedBlock<Tag> {", vec![
+            "pub tag: Tag",
+            "pub stmts: Vec<TaggedStatement<Tag>>",
+            "pub end: Box<Option<TaggedTerm<Tag>>>",
+        ]),
+        (TYPE_CHECK, "pub struct TaggedProgram<Tag> {", vec![
+            "pub tag: Tag",
+            "pub main: TaggedBlock<Tag>",
+        ]),
+    ]
+}
+
+// The variants or fields declared after `header` in `source`, without comments and with their
+// whitespace collapsed.
+fn declaration(source: &str, header: &str) -> Vec<String> {
+    let body: Vec<&str> = source.lines()
+                                .skip_while(|line| *line != header)
+                                .skip(1)
+                                .take_while(|line| *line != "}")
+                                .map(|line| line.split("//").next().unwrap())
+                                .collect();
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut depth = 0;
+    for c in body.join(" ").chars() {
+        match c {
+            '(' | '<' | '{' => depth += 1,
+            ')' | '>' | '}' => depth -= 1,
+            _ => {}
+        }
+        if c == ',' && depth == 0 {
+            items.push(item.split_whitespace().collect::<Vec<_>>().join(" "));
+            item.clear();
+        } else {
+            item.push(c);
+        }
+    }
+    if !item.trim().is_empty() {
+        items.push(item.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+    items
+}
+
+#[test]
+fn the_format_changes_with_its_version() {
+    for (source, header, expected) in checklist() {
+        assert_eq!(declaration(source, header), expected,
+                   "`{}` changed, so the typed AST format did: bump FORMAT_VERSION and update \
+                    the checklist in this test", header);
+    }
+    assert_eq!(FORMAT_VERSION, CHECKED_VERSION,
+               "FORMAT_VERSION was bumped: update the checklist in this test");
+}
+
+// extern putchar: (I32) -> I32;
+// let mut a = 0; let mut b = 1; let mut n = true;
+// while n { let next = a + b; a = b; n = false; };
+// putchar(-a);
+// if !n { { 1 } } else { 2 }
+fn program() -> TaggedProgram<ende::ast::Position> {
+    let body = block(vec![let_("next", infix(var("a"), Add, var("b"))),
+                          mutate("a", var("b")),
+                          mutate("n", bool_lit(false))],
+                     None);
+    let negated = TaggedTerm::Neg(pos(), Box::new(var("a")));
+    let not_n = TaggedTerm::Not(pos(), Box::new(var("n")));
+    let end = TaggedTerm::If(pos(),
+                             Box::new(not_n),
+                             Box::new(scope(vec![], Some(lit(1)))),
+                             Some(Box::new(lit(2))));
+    common::program(vec![extern_("putchar", i32_function(1)),
+                         let_mut("a", lit(0)),
+                         let_mut("b", lit(1)),
+                         let_mut("n", bool_lit(true)),
+                         semicolon(while_(var("n"), body)),
+                         semicolon(call("putchar", vec![negated]))],
+                    Some(end))
+}
+
+fn written() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_typed_ast(&mut bytes, &check(&program()).unwrap()).unwrap();
+    bytes
+}
+
+#[test]
+fn a_program_round_trips() {
+    let typed = check(&program()).unwrap();
+    let bytes = written();
+    assert_eq!(&bytes[..4], MAGIC);
+    assert_eq!(bytes[4..8], [FORMAT_VERSION as u8, 0, 0, 0]);
+    assert_eq!(read_typed_ast(&bytes[..]).unwrap(), typed);
+}
+
+#[test]
+fn a_bad_magic_number_is_refused() {
+    let mut bytes = written();
+    bytes[0] = b'X';
+    let error = read_typed_ast(&bytes[..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(error.to_string(), "not a typed Ende AST: wrong magic number");
+}
+
+#[test]
+fn an_unknown_version_is_refused() {
+    let mut bytes = written();
+    bytes[4] = (FORMAT_VERSION + 1) as u8;
+    let error = read_typed_ast(&bytes[..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(error.to_string(),
+               format!("unsupported typed AST format version {}, expected {}",
+                       FORMAT_VERSION + 1, FORMAT_VERSION));
+}
+
+#[test]
+fn a_truncated_file_is_refused() {
+    let bytes = written();
+    assert!(read_typed_ast(&bytes[..bytes.len() - 1]).is_err());
+}
@@ -0,0 +1,34 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::type_check::*;
+use ende::type_check::Type::*;
+use ende::typed_builder::TypedExpr;
+
+#[test]
+fn a_built_term_checks_with_the_type_it_was_built_with() {
+    // f(3 + x)
+    let sum = TypedExpr::lit_i32(3).add(TypedExpr::var("x", I32Ty)).unwrap();
+    let built = TypedExpr::call("f", &i32_function(1), vec![sum]).unwrap();
+    assert_eq!(*built.ty(), I32Ty);
+    let mut env = Env::new();
+    env.insert("x".to_string(), I32Ty);
+    env.insert("f".to_string(), i32_function(1));
+    let typed = built.into_term().type_check(&mut env).unwrap();
+    assert_eq!(*typed.get_tag(), I32Ty);
+}
+
+#[test]
+fn a_mismatch_is_reported_when_the_terms_are_combined() {
+    let error = TypedExpr::lit_i32(1).add(TypedExpr::lit_bool(true)).unwrap_err();
+    assert_eq!(error,
+               vec!["The left-hand-side of + has type I32, but the right-hand-side of it has \
+                     type Bool.".to_string()]);
+    let error = TypedExpr::call("f", &i32_function(1), vec![TypedExpr::lit_bool(true)])
+                    .unwrap_err();
+    assert_eq!(error, vec!["Expect term of type I32, found term of type Bool.".to_string()]);
+    let error = TypedExpr::call("f", &i32_function(1), vec![]).unwrap_err();
+    assert_eq!(error, vec!["Function f expects 1 argument(s), but 0 are provided.".to_string()]);
+}
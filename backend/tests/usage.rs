@@ -0,0 +1,75 @@
+extern crate ende;
+extern crate serde_json;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::ast::Position;
+use ende::type_check::*;
+use ende::usage::*;
+
+// let mut x = 3;
+// while x { x = x - 1; };
+// 0
+fn countdown() -> TaggedProgram<Position> {
+    let body = block(vec![mutate("x", infix(var("x"), Sub, lit(1)))], None);
+    program(vec![let_mut("x", lit(3)), semicolon(while_(var("x"), body))], Some(lit(0)))
+}
+
+// let a = 1 + 2 * 3;
+// let b = { let c = a; c == a };
+// f(a, 2)
+fn arithmetic() -> TaggedProgram<Position> {
+    let b = scope(vec![let_("c", var("a"))], Some(infix(var("c"), Eq, var("a"))));
+    program(vec![let_("a", infix(lit(1), Add, infix(lit(2), Mul, lit(3)))), let_("b", b)],
+            Some(call("f", vec![var("a"), lit(2)])))
+}
+
+#[test]
+fn counts_of_a_loop() {
+    let usage = feature_usage(&countdown());
+    assert_eq!(usage.count(Feature::Literal), 3);
+    assert_eq!(usage.count(Feature::Var), 2);
+    assert_eq!(usage.count(Feature::While), 1);
+    assert_eq!(usage.count(Feature::Mutate), 1);
+    assert_eq!(usage.count(Feature::Let), 0);
+    assert_eq!(usage.operators.get(&Sub), Some(&1));
+    assert_eq!(usage.max_block_depth, 2);
+    assert_eq!(usage.distinct_variables, 1);
+}
+
+#[test]
+fn counts_of_arithmetic() {
+    let usage = feature_usage(&arithmetic());
+    assert_eq!(usage.count(Feature::Let), 3);
+    assert_eq!(usage.count(Feature::Literal), 4);
+    assert_eq!(usage.count(Feature::Var), 4);
+    assert_eq!(usage.count(Feature::Infix), 3);
+    assert_eq!(usage.count(Feature::Scope), 1);
+    assert_eq!(usage.count(Feature::Call), 1);
+    assert_eq!(usage.operators.len(), 3);
+    assert_eq!(usage.max_block_depth, 2);
+    // The called function isn't a variable.
+    assert_eq!(usage.distinct_variables, 3);
+}
+
+#[test]
+fn requires_rejects_a_while() {
+    let allowed = [Feature::Literal, Feature::Var, Feature::Infix, Feature::LetMut,
+                   Feature::Mutate, Feature::TermSemicolon];
+    assert_eq!(feature_usage(&countdown()).requires(&allowed), Err(vec![Feature::While]));
+    let mut allowed = allowed.to_vec();
+    allowed.push(Feature::While);
+    assert_eq!(feature_usage(&countdown()).requires(&allowed), Ok(()));
+}
+
+#[test]
+fn usage_serializes() {
+    let json = serde_json::to_value(&feature_usage(&countdown())).unwrap();
+    assert_eq!(json["features"]["While"], 1);
+    assert_eq!(json["features"]["Literal"], 3);
+    assert_eq!(json["operators"]["Sub"], 1);
+    assert_eq!(json["max_block_depth"], 2);
+    assert_eq!(json["distinct_variables"], 1);
+}
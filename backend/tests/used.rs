@@ -0,0 +1,45 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator;
+use ende::codegen::Map;
+use ende::diagnostics::check_all;
+use ende::type_check::*;
+use ende::used::Origin;
+
+#[test]
+fn only_the_host_functions_called_are_used() {
+    let mut env = Env::new();
+    for name in &["a", "b", "c", "d", "e"] {
+        env.insert(name.to_string(), i32_function(1));
+    }
+    // `b` is called twice, once after a local `b` shadowed it in a scope of its own.
+    let program = program(vec![let_("x", call("b", vec![lit(1)])),
+                               extern_("g", i32_function(1)),
+                               semicolon(call("g", vec![var("x")])),
+                               let_("y", scope(vec![let_("b", lit(2))], Some(var("b"))))],
+                          Some(infix(call("b", vec![var("y")]),
+                                     Operator::Add,
+                                     call("d", vec![lit(1)]))));
+    let result = check_all(&program, &mut env, &CheckOptions::default(), &[], &Map::new());
+    assert!(!result.has_errors());
+    let used = result.used_bindings();
+    assert_eq!(used.names_from(Origin::Host), vec!["b", "d"]);
+    assert_eq!(used.get("b", Origin::Host).unwrap().count, 2);
+    assert_eq!(used.get("d", Origin::Host).unwrap().count, 1);
+    assert_eq!(used.names_from(Origin::Extern), vec!["g"]);
+    assert_eq!(used.get("b", Origin::Local).unwrap().count, 1);
+    assert_eq!(used.get("x", Origin::Local).unwrap().count, 1);
+}
+
+#[test]
+fn undeclared_names_are_not_used() {
+    let mut env = Env::new();
+    env.insert("a".to_string(), i32_function(1));
+    let program = program(vec![], Some(call("a", vec![var("nowhere")])));
+    let result = check_all(&program, &mut env, &CheckOptions::default(), &[], &Map::new());
+    assert!(result.has_errors());
+    assert!(result.used_bindings().get("nowhere", Origin::Host).is_none());
+}
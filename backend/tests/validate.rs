@@ -0,0 +1,47 @@
+extern crate ende;
+
+mod common;
+
+use common::*;
+use ende::ast::Operator::*;
+use ende::type_check::*;
+use ende::type_check::Type::*;
+use ende::validate::*;
+
+fn typed_block(tag: Type, stmts: Vec<TaggedStatement<Type>>, end: Option<TaggedTerm<Type>>)
+               -> TaggedBlock<Type> {
+    TaggedBlock { tag: tag, stmts: stmts, end: Box::new(end) }
+}
+
+// `{}`, tagged Unit.
+fn unit() -> TaggedTerm<Type> {
+    TaggedTerm::Scope(UnitTy, typed_block(UnitTy, vec![], None))
+}
+
+#[test]
+fn a_checked_tree_is_valid() {
+    let program = program(vec![let_("x", infix(lit(1), Add, lit(2)))],
+                          Some(infix(var("x"), Mul, lit(3))));
+    assert_eq!(validate(&check(&program).unwrap()), Ok(()));
+}
+
+#[test]
+fn a_mistagged_infix_is_reported_where_it_is() {
+    // let a = 1;
+    // let b = {} + {};
+    // a
+    // ...with the `+` tagged I32, as if it added numbers.
+    let sum = TaggedTerm::Infix(I32Ty, Box::new(unit()), Add, Box::new(unit()));
+    let program = TaggedProgram {
+        tag: Forbidden,
+        main: typed_block(I32Ty,
+                          vec![TaggedStatement::Let(Forbidden, "a".to_string(), Hole,
+                                                    TaggedTerm::Literal(I32Ty, 1)),
+                               TaggedStatement::Let(Forbidden, "b".to_string(), Hole, sum)],
+                          Some(TaggedTerm::Var(I32Ty, "a".to_string()))),
+    };
+    let errors = validate(&program).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path, "main.stmts[1].rhs");
+    assert_eq!(errors[0].message, "expected operands of a built-in +, found ()");
+}
@@ -2,6 +2,7 @@
 
 module Ast ( Position(..)
            , Operator(..)
+           , Pattern(..)
            , TaggedTerm(..)
            , TaggedFunctionCall(..)
            , TaggedStatement(..)
@@ -18,30 +19,82 @@ data Position = Position { startPos :: (Word, Word)
                          , endPos :: (Word, Word)
                          } deriving (Show, Eq, Generic, NFData)
 
-data Operator = Add | Sub | Mul | Div deriving (Show, Eq, Generic, NFData)
+data Operator = Add | Sub | Mul | Div | Rem | Eq | Ne | Lt | Le | Gt | Ge | And | Or
+              | BitAnd | BitOr | BitXor
+  deriving (Show, Eq, Generic, NFData)
+
+-- `Shape::Circle(r)`, or `_` which matches anything.
+data Pattern = Constructor String String [String]
+             | Wildcard
+             deriving (Show, Eq, Generic, NFData)
 
 data TaggedTerm t = Literal t Int
                   | Var t String
                   | Infix t (TaggedTerm t) Operator (TaggedTerm t)
                   | Call t (TaggedFunctionCall t) [TaggedTerm t]
                   | Scope t (TaggedBlock t)
-                  | If t (TaggedTerm t) (TaggedTerm t) (TaggedTerm t)
+                  | If t (TaggedTerm t) (TaggedTerm t) (Maybe (TaggedTerm t))
                   | While t (TaggedTerm t) (TaggedBlock t)
                   | Stmt (TaggedStatement t)
+                  | BoolLiteral t Bool
+                  | SuffixedLiteral t Int Type
+                  | Cast t (TaggedTerm t) Type
+                  | FloatLiteral t Double
+                  | CharLit t Char
+                  | StrLit t String
+                  | Tuple t [TaggedTerm t]
+                  | Project t (TaggedTerm t) Word
+                  | ArrayLit t [TaggedTerm t]
+                  | Index t (TaggedTerm t) (TaggedTerm t)
+                  | StructLit t String [(String, TaggedTerm t)]
+                  | Field t (TaggedTerm t) String
+                  | Variant t String String [TaggedTerm t]
+                  | Match t (TaggedTerm t) [(Pattern, TaggedTerm t)]
+                  | Borrow t (TaggedTerm t)
+                  | Deref t (TaggedTerm t)
+                  | BorrowMut t (TaggedTerm t)
+                  | Null t
+                  | PtrEq t (TaggedTerm t) (TaggedTerm t)
+                  | Lambda t [(String, Type)] (TaggedBlock t)
+                  | Apply t (TaggedTerm t) [TaggedTerm t]
+                  | Ascribe t (TaggedTerm t) Type
+                  | MethodCall t (TaggedTerm t) (TaggedFunctionCall t) [TaggedTerm t]
+                  | Neg t (TaggedTerm t)
+                  | Not t (TaggedTerm t)
                   deriving (Show, Eq, Generic, NFData)
 
 data TaggedFunctionCall t = TaggedFunctionCall t String deriving (Show, Eq, Generic, NFData)
 
 data TaggedStatement t = TermSemicolon t (TaggedTerm t)
-                       | Let t String (TaggedTerm t)
-                       | LetMut t String (TaggedTerm t)
+                       | Let t String Type (TaggedTerm t)
+                       | LetMut t String Type (TaggedTerm t)
                        | Mutate t String (TaggedTerm t)
                        | Extern t String Type
+                       | Declare t String Type
+                       | StructDecl t String [(String, Type)]
+                       | EnumDecl t String [(String, [Type])]
+                       | TypeAlias t String Type
+                       | MutateThrough t (TaggedTerm t) (TaggedTerm t)
                        deriving (Show, Eq, Generic, NFData)
 
 data Type = Forbidden
           | I32Ty
-          | FunctionTy [Type] Type
+          | FunctionTy [String] [Type] Type
+          | BoolTy
+          | I64Ty
+          | U32Ty
+          | I8Ty
+          | I16Ty
+          | F64Ty
+          | CharTy
+          | StrTy
+          | TupleTy [Type]
+          | ArrayTy Type Word
+          | Named String
+          | Ref Type
+          | RefMut Type
+          | Ptr
+          | Hole
           deriving (Show, Eq, Generic, NFData)
 
 data TaggedBlock t = TaggedBlock { tag :: t
@@ -65,16 +118,46 @@ instance Tagged TaggedTerm where
   getTag (If t _ _ _) = t
   getTag (While t _ _) = t
   getTag (Stmt stmt) = getTag stmt
+  getTag (BoolLiteral t _) = t
+  getTag (SuffixedLiteral t _ _) = t
+  getTag (Cast t _ _) = t
+  getTag (FloatLiteral t _) = t
+  getTag (CharLit t _) = t
+  getTag (StrLit t _) = t
+  getTag (Tuple t _) = t
+  getTag (Project t _ _) = t
+  getTag (ArrayLit t _) = t
+  getTag (Index t _ _) = t
+  getTag (StructLit t _ _) = t
+  getTag (Field t _ _) = t
+  getTag (Variant t _ _ _) = t
+  getTag (Match t _ _) = t
+  getTag (Borrow t _) = t
+  getTag (Deref t _) = t
+  getTag (BorrowMut t _) = t
+  getTag (Null t) = t
+  getTag (PtrEq t _ _) = t
+  getTag (Lambda t _ _) = t
+  getTag (Apply t _ _) = t
+  getTag (Ascribe t _ _) = t
+  getTag (MethodCall t _ _ _) = t
+  getTag (Neg t _) = t
+  getTag (Not t _) = t
 
 instance Tagged TaggedFunctionCall where
   getTag (TaggedFunctionCall t _) = t
 
 instance Tagged TaggedStatement where
   getTag (TermSemicolon t _) = t
-  getTag (Let t _ _) = t
-  getTag (LetMut t _ _) = t
+  getTag (Let t _ _ _) = t
+  getTag (LetMut t _ _ _) = t
   getTag (Mutate t _ _) = t
   getTag (Extern t _ _) = t
+  getTag (Declare t _ _) = t
+  getTag (StructDecl t _ _) = t
+  getTag (EnumDecl t _ _) = t
+  getTag (TypeAlias t _ _) = t
+  getTag (MutateThrough t _ _) = t
 
 instance Tagged TaggedBlock where
   getTag block = tag block
@@ -7,7 +7,8 @@ import Foreign.StablePtr
 import Foreign.C.String
 
 import Data.Maybe
-import Control.Monad (void)
+import Data.Int (Int32)
+import Control.Monad (void, when)
 import Control.DeepSeq (($!!))
 import Text.Megaparsec hiding (space)
 import Text.Megaparsec.String
@@ -45,16 +46,71 @@ leftParen = symbol "(" <?> "left parenthesis"
 rightParen :: Parser (String, Position)
 rightParen = symbol ")" <?> "right parenthesis"
 
+-- `42i64` and `42u32` have the type their suffix names; `42i32` is the same as `42`.
+-- An I32 literal too big for I32 stays suffixed, so that the checker reports it instead of it
+-- wrapping around on the way to the backend.
 literal :: Parser (TaggedTerm Position)
 literal = do
   start <- getWordPair
   int <- Lexer.integer
+  suffix <- optional $ try (string "i32") <|> try (string "i64") <|> string "u32"
   end <- getWordPair
   space
-  return . Literal (Position start end) . fromInteger $ int
+  let pos = Position start end
+  when (int > toInteger (maxBound :: Int)) $
+    fail $ "The literal " ++ show int ++ " doesn't fit in 64 bits."
+  return $ case suffix of
+    Just "i64" -> SuffixedLiteral pos (fromInteger int) I64Ty
+    Just "u32" -> SuffixedLiteral pos (fromInteger int) U32Ty
+    _ | int > toInteger (maxBound :: Int32) -> SuffixedLiteral pos (fromInteger int) I32Ty
+      | otherwise -> Literal pos (fromInteger int)
+
+-- `1.5` or `2e3`; a float literal needs a fraction or an exponent.
+floatLiteral :: Parser (TaggedTerm Position)
+floatLiteral = do
+  (float, pos) <- withPosition Lexer.float
+  space
+  return $ FloatLiteral pos float
+
+-- `'a'`, with the escapes of Haskell, like `'\n'`.
+charLit :: Parser (TaggedTerm Position)
+charLit = do
+  (c, pos) <- withPosition $ between (char '\'') (char '\'') Lexer.charLiteral
+  space
+  return $ CharLit pos c
+
+-- `"hello"`, with the same escapes as character literals.
+strLit :: Parser (TaggedTerm Position)
+strLit = do
+  (s, pos) <- withPosition $ char '"' >> manyTill Lexer.charLiteral (char '"')
+  space
+  return $ StrLit pos s
+
+boolLiteral :: Parser (TaggedTerm Position)
+boolLiteral = do
+  (b, pos) <- withPosition $ (True <$ string "true") <|> (False <$ string "false")
+  notFollowedBy (alphaNumChar <|> char '_')
+  space
+  return $ BoolLiteral pos b
+
+nullLiteral :: Parser (TaggedTerm Position)
+nullLiteral = do
+  (_, pos) <- withPosition $ string "null"
+  notFollowedBy (alphaNumChar <|> char '_')
+  space
+  return $ Null pos
+
+-- Must agree with `RESERVED_WORDS` in backend/src/ast.rs.
+reservedWords :: [String]
+reservedWords = ["fn", "let", "mut", "if", "then", "else", "while", "extern", "true", "false", "as",
+                 "struct", "enum", "match", "type", "null"]
 
 identifier :: Parser String
-identifier = lexeme $ some letterChar
+identifier = (lexeme . try) $ do
+  name <- (:) <$> (letterChar <|> char '_') <*> many (alphaNumChar <|> char '_')
+  if name `elem` reservedWords
+    then fail $ "the keyword " ++ show name ++ " can't be used as a name"
+    else return name
 
 var :: Parser (TaggedTerm Position)
 var = withPosition identifier >>= \(str, pos) -> return $ Var pos str
@@ -69,10 +125,84 @@ functionCall = do
   let position = Position start (endPos pair)
   return $ Call position name vars
 
+-- A parenthesized term, or a tuple like `(1, true)` if there's more than one.
+parens :: Parser (TaggedTerm Position)
+parens = do
+  (terms, pos) <- withPosition $ between leftParen rightParen (expr `sepBy1` symbol ",")
+  return $ case terms of
+    [t] -> t
+    _ -> Tuple pos terms
+
+-- `(1 : I64)`, a term ascribed a type.
+ascription :: Parser (TaggedTerm Position)
+ascription = do
+  ((t, ascribed), pos) <- withPosition $ between leftParen rightParen $
+    (,) <$> expr <* (symbol ":" <?> "colon") <*> ty
+  return $ Ascribe pos t ascribed
+
+arrayLit :: Parser (TaggedTerm Position)
+arrayLit = do
+  (terms, pos) <- withPosition $ between (symbol "[") (symbol "]") (expr `sepBy1` symbol ",")
+  return $ ArrayLit pos terms
+
+-- `Point { x: 1, y: 2 }`. A name and a brace only start a struct literal if a field and a colon
+-- follow, so `while going { ... }` is still a loop.
+structLit :: Parser (TaggedTerm Position)
+structLit = do
+  lookAhead $ try (identifier *> symbol "{" *> identifier *> symbol ":" *> notFollowedBy (char ':'))
+  ((name, inits), pos) <- withPosition $ do
+    name <- identifier
+    inits <- between (symbol "{") (symbol "}") (fieldInit `sepEndBy1` symbol ",")
+    return (name, inits)
+  return $ StructLit pos name inits
+ where fieldInit = (,) <$> identifier <* symbol ":" <*> expr
+
+-- `Shape::Circle(1)`, or `Shape::Empty` for a variant without a payload.
+variant :: Parser (TaggedTerm Position)
+variant = do
+  ((name, variantName, args), pos) <- withPosition $ do
+    name <- identifier
+    symbol "::"
+    variantName <- identifier
+    args <- option [] $ between leftParen rightParen (expr `sepEndBy` symbol ",")
+    return (name, variantName, args)
+  return $ Variant pos name variantName args
+
+-- `match s { Shape::Circle(r) => r, _ => 0 }`.
+match :: Parser (TaggedTerm Position)
+match = do
+  start <- getWordPair
+  symbol "match" <?> "match"
+  scrutinee <- expr
+  (arms, pos) <- withPosition $ between (symbol "{") (symbol "}") (arm `sepEndBy1` symbol ",")
+  return $ Match (Position start (endPos pos)) scrutinee arms
+ where arm = (,) <$> pattern <* symbol "=>" <*> expr
+
+pattern :: Parser Pattern
+pattern = try (Wildcard <$ symbol "_" <* notFollowedBy alphaNumChar) <|> constructor
+ where constructor = do
+         name <- identifier
+         symbol "::"
+         variantName <- identifier
+         binds <- option [] $ between leftParen rightParen (identifier `sepEndBy` symbol ",")
+         return $ Constructor name variantName binds
+
 scope :: Parser (TaggedTerm Position)
 scope = do (b, pos) <- withPosition block
            return $ Scope pos b
 
+-- `fn(x: I32, y: I32) { x + y }`. The return type is the type of the body, and the types of the
+-- parameters can be left out for the checker to infer.
+lambda :: Parser (TaggedTerm Position)
+lambda = do
+  start <- getWordPair
+  try (string "fn" <* notFollowedBy (alphaNumChar <|> char '_'))
+  space
+  params <- between leftParen rightParen (param `sepEndBy` symbol ",")
+  b <- block
+  return $ Lambda (Position start (endPos $ getTag b)) params b
+ where param = (,) <$> (identifier <?> "parameter name") <*> option Hole (symbol ":" *> ty)
+
 if_clause :: Parser (TaggedTerm Position)
 if_clause = do
   start <- getWordPair
@@ -80,9 +210,9 @@ if_clause = do
   cond <- expr
   symbol "then" <?> "then"
   thenPart <- expr
-  symbol "else" <?> "else"
-  elsePart <- expr
-  let pos = Position start (endPos $ getTag elsePart)
+  -- Without an else, the then part has to be Unit.
+  elsePart <- optional ((symbol "else" <?> "else") *> expr)
+  let pos = Position start (endPos $ getTag $ fromMaybe thenPart elsePart)
   return $ If pos cond thenPart elsePart
 
 while :: Parser (TaggedTerm Position)
@@ -98,16 +228,84 @@ term :: Parser (TaggedTerm Position)
 term =
    (try if_clause <?> "if clause") <|>
    (try while <?> "while loop") <|>
+   (try match <?> "match") <|>
+   (lambda <?> "lambda") <|>
+   (try boolLiteral <?> "boolean literal") <|>
+   (try nullLiteral <?> "null") <|>
+   (try structLit <?> "struct literal") <|>
+   (try variant <?> "enum variant") <|>
    (try functionCall <?> "function call") <|>
    (try var <?> "variable") <|>
+   (charLit <?> "character literal") <|>
+   (strLit <?> "string literal") <|>
+   (try floatLiteral <?> "float literal") <|>
    (literal <?> "literal") <|>
+   (try ascription <?> "ascription") <|>
+   (parens <?> "parenthesized term") <|>
+   (arrayLit <?> "array literal") <|>
    (scope <?> "scope")
 
+data Postfix = ProjectBy Integer
+             | MethodOf String Position [TaggedTerm Position]
+             | FieldOf String
+             | IndexBy (TaggedTerm Position)
+             | ApplyTo [TaggedTerm Position]
+
+-- `t.0.1` projects elements out of tuples, `p.x` reads a field of a struct, `x.f(1)` calls `f`
+-- with `x` first, `a[i]` indexes arrays and `t.0(1)` calls a function that isn't a name, all
+-- binding tighter than `as`.
+postfixes :: Parser (TaggedTerm Position)
+postfixes = do
+  t <- term
+  suffixes <- many $ withPosition suffix <* space
+  return $ foldl apply t suffixes
+ where suffix = (ProjectBy <$> try (char '.' *> Lexer.integer)) <|>
+                methodOf <|>
+                (FieldOf <$> try (char '.' *> identifier)) <|>
+                (IndexBy <$> (symbol "[" *> expr <* char ']')) <|>
+                (ApplyTo <$> (leftParen *> (expr `sepEndBy` symbol ",") <* char ')'))
+       methodOf = do
+         (name, namePos) <- try (char '.' *> withPosition identifier <* lookAhead (char '('))
+         args <- leftParen *> (expr `sepEndBy` symbol ",") <* char ')'
+         return $ MethodOf name namePos args
+       apply t (ProjectBy index, pos) = Project (from t pos) t (fromInteger index)
+       apply t (MethodOf name namePos args, pos) =
+         MethodCall (from t pos) t (TaggedFunctionCall namePos name) args
+       apply t (FieldOf field, pos) = Field (from t pos) t field
+       apply t (IndexBy index, pos) = Index (from t pos) t index
+       apply t (ApplyTo args, pos) = Apply (from t pos) t args
+       from t pos = Position (startPos $ getTag t) (endPos pos)
+
+-- `&x` and `&mut x` borrow a variable, `*p` reads through a reference, `-x` negates a number and
+-- `!b` a Bool. They bind looser than postfixes, so `*p.0` is `*(p.0)`, but tighter than `as`.
+prefixes :: Parser (TaggedTerm Position)
+prefixes = prefixed <|> postfixes
+ where prefixed = do
+         start <- getWordPair
+         wrap <- (symbol "&" *> option Borrow (BorrowMut <$ mutKeyword)) <|>
+                 (Deref <$ symbol "*") <|>
+                 (Neg <$ symbol "-") <|>
+                 (Not <$ symbol "!")
+         t <- prefixes
+         return $ wrap (Position start (endPos $ getTag t)) t
+
 opToString :: Operator -> String
 opToString Add = "+"
 opToString Sub = "-"
 opToString Mul = "*"
 opToString Div = "/"
+opToString Rem = "%"
+opToString Eq = "=="
+opToString Ne = "!="
+opToString Lt = "<"
+opToString Le = "<="
+opToString Gt = ">"
+opToString Ge = ">="
+opToString And = "&&"
+opToString Or = "||"
+opToString BitAnd = "&"
+opToString BitOr = "|"
+opToString BitXor = "^"
 
 -- TODO: Now only a dummy position is being tagged. Fix it.
 opToFunc :: Operator ->
@@ -120,14 +318,44 @@ opToFunc op = let dummyPosition = Position (1, 1) (1, 1)
 opToElement :: Operator -> Expr.Operator Parser (TaggedTerm Position)
 opToElement op = Expr.InfixL $ opToFunc op <$ (symbol $ opToString op)
 
+-- Comparisons bind looser than any arithmetic and only once, so `a < b < c` doesn't parse. `<`
+-- mustn't take the start of `<=`, nor `==` the start of `===`.
+comparisonToElement :: Operator -> Expr.Operator Parser (TaggedTerm Position)
+comparisonToElement op = Expr.InfixN $ cmp <$ lexeme (try opString)
+ where opString = string (opToString op) <* notFollowedBy (char '=')
+       cmp l r = Infix (Position (startPos $ getTag l) (endPos $ getTag r)) l op r
+
+-- `&` and `|` mustn't take the start of `&&` and `||`.
+bitwiseToElement :: Operator -> Expr.Operator Parser (TaggedTerm Position)
+bitwiseToElement op = Expr.InfixL $ opToFunc op <$ lexeme (try opString)
+ where opString = string (opToString op) <* notFollowedBy (oneOf "&|")
+
+-- `x as I8` binds tighter than any infix operator.
+cast :: Parser (TaggedTerm Position -> TaggedTerm Position)
+cast = do
+  try (string "as" <* notFollowedBy (alphaNumChar <|> char '_'))
+  space
+  (target, tyPos) <- withPosition ty
+  return $ \t -> Cast (Position (startPos $ getTag t) (endPos tyPos)) t target
+
+-- Levels and associativity must agree with `Operator::precedence` and
+-- `Operator::associativity` in backend/src/ast.rs.
 table :: [[Expr.Operator Parser (TaggedTerm Position)]]
-table = [ [ opToElement Mul
-          , opToElement Div ]
+table = [ [ Expr.Postfix cast ]
+        , [ opToElement Mul
+          , opToElement Div
+          , opToElement Rem ]
         , [ opToElement Add
-          , opToElement Sub ] ]
+          , opToElement Sub ]
+        , [ bitwiseToElement BitAnd ]
+        , [ bitwiseToElement BitXor ]
+        , [ bitwiseToElement BitOr ]
+        , map comparisonToElement [Eq, Ne, Le, Lt, Ge, Gt]
+        , [ opToElement And ]
+        , [ opToElement Or ] ]
 
 expr :: Parser (TaggedTerm Position)
-expr = Expr.makeExprParser term table
+expr = Expr.makeExprParser prefixes table
 
 semicolon :: Parser (String, Position)
 semicolon = symbol ";" <?> "semicolon"
@@ -147,24 +375,45 @@ binding = do
   rhs <- expr
   return (var, rhs)
 
+-- `x: I32 = e`, or `x = e` without the annotation.
+annotatedBinding :: Parser (String, Type, (TaggedTerm Position))
+annotatedBinding = do
+  var <- identifier <?> "variable name"
+  t <- option Hole ((symbol ":" <?> "colon") *> ty)
+  symbol "=" <?> "equal sign"
+  rhs <- expr
+  return (var, t, rhs)
+
 letBinding :: Parser (TaggedStatement Position)
 letBinding = do
   start <- getWordPair
   symbol "let" <?> "let"
-  (var, rhs) <- binding
+  (var, t, rhs) <- annotatedBinding
   (_, scPos) <- semicolon
   let pos = Position start (endPos scPos)
-  return $ Let pos var rhs
+  return $ Let pos var t rhs
 
 letMut :: Parser (TaggedStatement Position)
 letMut = do
   start <- getWordPair
   symbol "let" <?> "let"
   symbol "mut" <?> "mut"
-  (var, rhs) <- binding
+  (var, t, rhs) <- annotatedBinding
+  (_, scPos) <- semicolon
+  let pos = Position start (endPos scPos)
+  return $ LetMut pos var t rhs
+
+-- `*p = e;` assigns through the reference `p`.
+mutateThrough :: Parser (TaggedStatement Position)
+mutateThrough = do
+  start <- getWordPair
+  symbol "*"
+  target <- prefixes
+  symbol "=" <?> "equal sign"
+  rhs <- expr
   (_, scPos) <- semicolon
   let pos = Position start (endPos scPos)
-  return $ LetMut pos var rhs
+  return $ MutateThrough pos target rhs
 
 mutate :: Parser (TaggedStatement Position)
 mutate = do
@@ -174,15 +423,67 @@ mutate = do
   let pos = Position start (endPos scPos)
   return $ Mutate pos var rhs
 
--- TODO: This is a stub. Fix it.
 ty :: Parser Type
-ty = do
-  symbol "("
-  types <- symbol "I32" `sepEndBy` symbol ","
-  symbol ")"
-  symbol "->"
-  symbol "I32"
-  return $ FunctionTy (replicate (length types) I32Ty) I32Ty
+ty = (namedTy <?> "type name") <|>
+     (refTy <?> "reference type") <|>
+     (arrayTy <?> "array type") <|>
+     (parenTy <?> "function or tuple type") <|>
+     (genericTy <?> "generic function type")
+
+builtinTypes :: [(String, Type)]
+builtinTypes = [("I32", I32Ty), ("Bool", BoolTy), ("I64", I64Ty), ("U32", U32Ty), ("I8", I8Ty),
+                ("I16", I16Ty), ("F64", F64Ty), ("Char", CharTy), ("Str", StrTy), ("Ptr", Ptr)]
+
+-- A built-in type, or a name the checker resolves, like a type alias. Reading the whole name
+-- first keeps `Str` from being read off the front of `StrPair`.
+namedTy :: Parser Type
+namedTy = do
+  name <- identifier
+  return $ maybe (Named name) id (lookup name builtinTypes)
+
+-- `&I32` is a reference to an I32, and `&mut I32` a mutable one.
+refTy :: Parser Type
+refTy = do
+  symbol "&"
+  wrap <- option Ref (RefMut <$ mutKeyword)
+  wrap <$> ty
+
+-- `mut`, but not the start of a name like `mutable`.
+mutKeyword :: Parser ()
+mutKeyword = try (string "mut" *> notFollowedBy (alphaNumChar <|> char '_')) *> space
+
+-- `[I32; 3]` is an array of three I32s.
+arrayTy :: Parser Type
+arrayTy = do
+  symbol "["
+  elemTy <- ty
+  symbol ";"
+  len <- lexeme Lexer.integer
+  symbol "]"
+  return $ ArrayTy elemTy (fromInteger len)
+
+-- `(I32, Bool) -> I32` is a function type, and `(I32, Bool)` a tuple type.
+parenTy :: Parser Type
+parenTy = do
+  leftParen
+  types <- ty `sepEndBy` symbol ","
+  rightParen
+  ret <- optional (symbol "->" *> ty)
+  case ret of
+    Just ret -> return $ FunctionTy [] types ret
+    Nothing | length types >= 2 -> return $ TupleTy types
+            | otherwise -> fail "a tuple type needs at least two elements"
+
+-- `<T>(T) -> T` is a function type generic over `T`.
+genericTy :: Parser Type
+genericTy = do
+  symbol "<"
+  params <- (identifier <?> "type parameter") `sepBy1` symbol ","
+  symbol ">"
+  fnTy <- parenTy
+  case fnTy of
+    FunctionTy _ types ret -> return $ FunctionTy params types ret
+    _ -> fail "only function types can be generic"
 
 extern_stmt :: Parser (TaggedStatement Position)
 extern_stmt = do
@@ -194,12 +495,66 @@ extern_stmt = do
   let pos = Position start (endPos scPos)
   return $ Extern pos fn args_ty
 
+-- `type Pair = (I32, I32);`
+typeAlias :: Parser (TaggedStatement Position)
+typeAlias = do
+  start <- getWordPair
+  symbol "type" <?> "type"
+  name <- identifier <?> "type name"
+  symbol "="
+  aliased <- ty
+  (_, scPos) <- semicolon
+  let pos = Position start (endPos scPos)
+  return $ TypeAlias pos name aliased
+
+-- `struct Point { x: I32, y: I32 };`
+structDecl :: Parser (TaggedStatement Position)
+structDecl = do
+  start <- getWordPair
+  symbol "struct" <?> "struct"
+  name <- identifier <?> "struct name"
+  fields <- between (symbol "{") (symbol "}") (field `sepEndBy1` symbol ",")
+  (_, scPos) <- semicolon
+  let pos = Position start (endPos scPos)
+  return $ StructDecl pos name fields
+ where field = (,) <$> (identifier <?> "field name") <* symbol ":" <*> ty
+
+-- `enum Shape { Circle(I32), Empty };`
+enumDecl :: Parser (TaggedStatement Position)
+enumDecl = do
+  start <- getWordPair
+  symbol "enum" <?> "enum"
+  name <- identifier <?> "enum name"
+  variants <- between (symbol "{") (symbol "}") (variant `sepEndBy1` symbol ",")
+  (_, scPos) <- semicolon
+  let pos = Position start (endPos scPos)
+  return $ EnumDecl pos name variants
+ where variant = (,) <$> (identifier <?> "variant name")
+                     <*> option [] (between leftParen rightParen (ty `sepEndBy` symbol ","))
+
+-- `let mut x: I32;`, or `let mut x;` for the checker to infer the type from the assignments.
+declaration :: Parser (TaggedStatement Position)
+declaration = do
+  start <- getWordPair
+  symbol "let" <?> "let"
+  symbol "mut" <?> "mut"
+  var <- identifier <?> "variable name"
+  t <- option Hole ((symbol ":" <?> "colon") *> ty)
+  (_, scPos) <- semicolon
+  let pos = Position start (endPos scPos)
+  return $ Declare pos var t
+
 statement :: Parser (TaggedStatement Position)
 statement =
+  try structDecl <|>
+  try enumDecl <|>
+  try typeAlias <|>
+  try declaration <|>
   try letMut <|>
   extern_stmt <|>
   letBinding <|>
   try mutate <|>
+  try mutateThrough <|>
   try termSemicolon <?> "statement"
 
 block :: Parser (TaggedBlock Position)
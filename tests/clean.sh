@@ -1,4 +1,4 @@
-for i in $(echo `ls | grep testcase`) ; do
+for i in $(echo `ls | grep -e testcase -e failcase`) ; do
     if [ -e "$i/$i.ende" ]; then
 	rm -f "$i/$i.bc"
     fi
@@ -4,17 +4,37 @@ failed=0
 for i in $(echo `ls | grep testcase`) ; do
     if [ -e "$i/$i.ende" ]; then
 	echo "Testing $i"
-	../backend/target/debug/ende "$i/$i.ende" -o "$i/$i"
+	# A testcase with a flags file is compiled with the flags in it.
+	flags=""
+	if [ -e "$i/flags" ]; then
+	    flags=$(cat $i/flags)
+	fi
+	compiler_output=$(../backend/target/debug/ende $flags "$i/$i.ende" -o "$i/$i" 2>&1)
 	if [ $? -ne 0 ]; then
+	    echo "$compiler_output"
 	    failed=1
 	else
 	    chmod +x $i/$i
-	    $i/$i
+	    output=$($i/$i)
 	    if [ $? -ne 0 ]; then
 		failed=1
 	    else
 		failed=0
 	    fi
+	    # A testcase with an expected_output file must print exactly that.
+	    if [ -e "$i/expected_output" ] && [ "$output" != "$(cat $i/expected_output)" ]; then
+		echo "unexpected output: $output"
+		failed=1
+	    fi
+	    # Every line of an expected_warnings file must appear in the compiler output.
+	    if [ -e "$i/expected_warnings" ]; then
+		while read -r expected; do
+		    if [ "$expected" != "" ] && ! echo "$compiler_output" | grep -qF -- "$expected"; then
+			echo "missing warning: $expected"
+			failed=1
+		    fi
+		done < "$i/expected_warnings"
+	    fi
 	fi
 	if [ $failed -eq 0 ]; then
 	    echo "test $i succeed"
@@ -24,6 +44,37 @@ for i in $(echo `ls | grep testcase`) ; do
 	fi
     fi
 done
+# A failcase must be rejected by the compiler with every line of its expected_errors file
+# appearing in the output.
+for i in $(echo `ls | grep failcase`) ; do
+    if [ -e "$i/$i.ende" ]; then
+	echo "Testing $i"
+	output=$(../backend/target/debug/ende "$i/$i.ende" -o "$i/$i" 2>&1)
+	if [ $? -eq 0 ]; then
+	    failed=1
+	else
+	    failed=0
+	    while read -r expected; do
+		if [ "$expected" != "" ] && ! echo "$output" | grep -qF -- "$expected"; then
+		    echo "missing error: $expected"
+		    failed=1
+		fi
+	    done < "$i/expected_errors"
+	    # A failcase with a diagnostic_count file must report exactly that many diagnostics.
+	    if [ -e "$i/diagnostic_count" ] && \
+		   [ "$(echo "$output" | wc -l)" -ne "$(cat $i/diagnostic_count)" ]; then
+		echo "unexpected number of diagnostics: $(echo "$output" | wc -l)"
+		failed=1
+	    fi
+	fi
+	if [ $failed -eq 0 ]; then
+	    echo "test $i succeed"
+	else
+	    echo "failcase $i failed"
+	    failed_tests="$i $failed_tests"
+	fi
+    fi
+done
 if [ "$failed_tests" != "" ]; then
     echo "failed tests: $failed_tests"
     exit 1